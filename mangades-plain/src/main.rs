@@ -1,13 +1,21 @@
 use std::sync::{Arc, mpsc, Mutex};
 use mangui::nodes::layout::Layout;
 use mangui::{MainEntry, SharedNode};
-use mangui::dpi::PhysicalPosition;
-use mangui::events::InnerEvent;
-use mangui::femtovg::{ImageFlags, Paint};
+use mangui::femtovg::ImageFlags;
 use mangui::nodes::text::Text;
-use mangui::nodes::{Style, TaffyStyle, ToShared};
+use mangui::nodes::{Positioning, Style, TaffyStyle, ToShared};
+use mangui::nodes::theme::{set_themes, StyleTokens};
+use mangui::nodes::variant::StyleCondition;
+use mangui::nodes::checkbox::{CheckState, Checkbox};
+use mangui::nodes::switch::Switch;
+use mangui::nodes::slider::Slider;
+use mangui::nodes::select::{Select, SelectOption};
+use mangui::nodes::tooltip::{set_tooltip, Tooltip};
+use mangui::nodes::overlay::{Placement, PlacementAlign, Side};
+use mangui::events::{ControlValue, InnerEvent};
 use mangui::nodes::image::{Image, ImageLoad};
-use mangui::taffy::{AlignItems, FlexDirection, JustifyContent, LengthPercentage, LengthPercentageAuto, Point, Rect};
+use mangui::nodes::virtual_list::{RowHeight, VirtualList};
+use mangui::taffy::{AlignItems, FlexDirection, JustifyContent, LengthPercentage, LengthPercentageAuto, Overflow, Point, Rect};
 use uno_gen::uno;
 use crate::anilist::load_demo_async;
 use crate::tokens::TEXT_LARGE;
@@ -17,8 +25,53 @@ mod anilist;
 mod utils;
 mod tokens;
 
+/// Row of the main list
+#[derive(Clone)]
+enum Row {
+    Header(String),
+    Entry { title: String, is_adult: bool, is_favourite: bool, score: f32, status: String }
+}
+
+/// Entries shown in the main list
+struct Filters {
+    adult: bool,
+    favourites_only: bool,
+    /// Lowest and highest score shown, from 0 to 10
+    score: (f32, f32),
+    /// List status (`CURRENT`, `PLANNING`...) shown, all statuses if None
+    status: Option<String>
+}
+
+impl Filters {
+    fn shows(&self, row: &Row) -> bool {
+        match row {
+            Row::Header(_) => true,
+            Row::Entry { is_adult, is_favourite, score, status, .. } => (self.adult || !is_adult)
+                && (!self.favourites_only || *is_favourite)
+                && (self.score.0..=self.score.1).contains(score)
+                && self.status.as_ref().is_none_or(|shown| shown == status)
+        }
+    }
+}
+
+/// Returns the rows passing the filters, without the headers of lists left empty.
+fn visible_rows(rows: &[Row], filters: &Filters) -> Vec<Row> {
+    let mut visible: Vec<Row> = Vec::new();
+    for row in rows.iter().filter(|row| filters.shows(row)) {
+        if let (Row::Header(_), Some(Row::Header(_))) = (row, visible.last()) {
+            visible.pop();
+        }
+        visible.push(row.clone());
+    }
+    if let Some(Row::Header(_)) = visible.last() {
+        visible.pop();
+    }
+    visible
+}
+
 #[tokio::main]
 async fn main() {
+    set_themes(tokens::light(), tokens::dark());
     let (tx, rx) = mpsc::channel();
     let tx = Arc::new(tx);
     let root = Layout::default();
@@ -32,13 +85,13 @@ async fn main() {
                 align_items: AlignItems::Center.into(),
                 ..Default::default()
             },
-            background: Some(Paint::color(*tokens::BACKGROUND)),
+            tokens: StyleTokens { background: Some("background"), ..Default::default() },
             ..Default::default()
         })
         .to_shared();
     let loading_text = Text::new("Loading...".to_owned(), TEXT_LARGE)
         .style(Style {
-            text_fill: Some(Paint::color(*tokens::WHITE)),
+            tokens: StyleTokens { text_fill: Some("text"), ..Default::default() },
             ..Default::default()
         })
         .to_shared();
@@ -49,78 +102,31 @@ async fn main() {
     tokio::spawn(async move {
         let data = load_demo_async().await;
 
-        let mut mainview_container = Layout::default()
+        let mainview_container = Layout::default()
             .style(Style {
                 layout: TaffyStyle {
                     flex_grow: 1.,
                     flex_direction: FlexDirection::Column,
                     ..Default::default()
                 },
-                background: Some(Paint::color(*tokens::BACKGROUND)),
+                tokens: StyleTokens { background: Some("background"), ..Default::default() },
                 ..Default::default()
             })
-            .to_arcmutex();
-        mainview_container.lock().unwrap().events.add_handler(Box::new({
-            let mainview_container = mainview_container.clone();
-            move |event| {
-                if let InnerEvent::Wheel { delta, .. } = event.event {
-                    let delta = match delta {
-                        mangui::events::MouseScrollDelta::LineDelta(_, y) => y * 30f32,
-                        mangui::events::MouseScrollDelta::PixelDelta(PhysicalPosition { y, .. }) => y as f32,
-                    };
-                    let mut layout = mainview_container.lock().unwrap();
-                    // layout.style.layout.scroll.y -= delta * 10.;
-                    layout.style.scroll_y -= delta;
-                    // cap scroll_y to 0
-                    if layout.style.scroll_y < 0. {
-                        layout.style.scroll_y = 0.;
-                    }
-                    println!("scroll_y: {}", layout.style.scroll_y);
-                }
-            }
-        }));
+            .to_shared();
         let i = LengthPercentageAuto::Length(5.);
         let title = Text::new("Mangades".to_owned(), TEXT_LARGE)
             .style(Style {
-                text_fill: Some(Paint::color(*tokens::WHITE)),
-                ..uno!(p-10)
+                tokens: StyleTokens { text_fill: Some("text"), ..Default::default() },
+                ..uno!(p-5 md:p-10)
             })
             .to_shared();
         append(&{ mainview_container.clone() }, &title);
-        
-        for list in data.lists {
-            let list_container = Layout::default()
-                .style(Style {
-                    layout: TaffyStyle {
-                        flex_grow: 1.,
-                        flex_direction: FlexDirection::Column,
-                        ..Default::default()
-                    },
-                    background: Some(Paint::color(*tokens::BACKGROUND)),
-                    ..Default::default()
-                })
-                .to_shared();
-            let list_title = Text::new(list.name, TEXT_LARGE)
-                .style(Style {
-                    text_fill: Some(Paint::color(*tokens::WHITE)),
-                    ..uno!(p-10)
-                })
-                .to_shared();
-            append(&{ mainview_container.clone() }, &list_container);
-            append(&list_container, &list_title);
 
+        // lists are flattened into rows of a single virtual list, so that only the visible rows have nodes
+        let mut rows = Vec::new();
+        for list in data.lists {
+            rows.push(Row::Header(list.name));
             for entry in list.entries {
-                let entry_container = Layout::default()
-                    .style(Style {
-                        layout: TaffyStyle {
-                            flex_grow: 1.,
-                            flex_direction: FlexDirection::Row,
-                            ..Default::default()
-                        },
-                        background: Some(Paint::color(*tokens::BACKGROUND)),
-                        ..Default::default()
-                    })
-                    .to_shared();
                 // image loading disabled for speed
                 // let addr = entry.media.cover_image.large;
                 // // use only last two parts from url, which is a folder and a file (either medium/something.jpg or large/something.jpg)
@@ -136,17 +142,132 @@ async fn main() {
                 //         ..Default::default()
                 //     })
                 //     .to_shared();
-                let title = Text::new(entry.media.title.user_preferred, TEXT_LARGE)
-                    .style(Style {
-                        text_fill: Some(Paint::color(*tokens::WHITE)),
-                        ..uno!(p-10)
-                    })
-                    .to_shared();
-                append(&list_container, &entry_container);
-                // append(&entry_container, &image);
-                append(&entry_container, &title);
+                rows.push(Row::Entry {
+                    title: entry.media.title.user_preferred,
+                    is_adult: entry.media.is_adult,
+                    is_favourite: entry.media.is_favourite,
+                    score: entry.score,
+                    status: entry.status
+                });
             }
         }
+        let filters = Arc::new(Mutex::new(Filters { adult: true, favourites_only: false, score: (0., 10.), status: None }));
+        let all_rows = Arc::new(rows);
+        let rows = Arc::new(Mutex::new(visible_rows(&all_rows, &filters.lock().unwrap())));
+        let row_count = rows.lock().unwrap().len();
+        let builder_rows = rows.clone();
+        let sticky_rows = rows.clone();
+        let mut texts: Vec<Arc<Mutex<Text>>> = Vec::new();
+        let list = VirtualList::new(row_count, RowHeight::Estimated(44.), Box::new(move |index, recycled| {
+            let rows = builder_rows.lock().unwrap();
+            let (content, font_size, fill) = match &rows[index] {
+                Row::Header(name) => (name.clone(), 24., "text"),
+                // titles wrap instead of being truncated, a tooltip with the full title (`Tooltip::when`) waits for `Text` to truncate
+                Row::Entry { title, .. } => (title.clone(), 20., "text-muted")
+            };
+            let text = recycled
+                .and_then(|node| texts.iter().find(|text| std::ptr::addr_eq(Arc::as_ptr(text), Arc::as_ptr(&node))).cloned())
+                .unwrap_or_else(|| {
+                    let text = Text::new(String::new(), TEXT_LARGE)
+                        .style(uno!(p-10).variant(StyleCondition::Hovered, |style| style.tokens.background = Some("surface")))
+                        .to_arcmutex();
+                    texts.push(text.clone());
+                    text
+                });
+            {
+                let mut text = text.lock().unwrap();
+                text.text = content;
+                text.style.font_size = Some(font_size);
+                text.style.tokens.text_fill = Some(fill);
+                // list names stick to the top while their entries scroll below them
+                let header = matches!(rows[index], Row::Header(_));
+                text.style.position = if header { Positioning::sticky_top(0.) } else { Positioning::Static };
+                text.style.z_index = header.then_some(1);
+                text.style.tokens.background = header.then_some("background");
+            }
+            let node: SharedNode = text;
+            node
+        }))
+            .sticky_rows(move |index| matches!(sticky_rows.lock().unwrap().get(index), Some(Row::Header(_))))
+            .style(Style {
+                layout: TaffyStyle {
+                    flex_grow: 1.,
+                    flex_direction: FlexDirection::Column,
+                    overflow: Point { x: Overflow::Hidden, y: Overflow::Scroll },
+                    ..Default::default()
+                },
+                tokens: StyleTokens { background: Some("background"), ..Default::default() },
+                ..Default::default()
+            })
+            .to_arcmutex();
+
+        let refilter = {
+            let list = list.clone();
+            let filters = filters.clone();
+            move |update: &dyn Fn(&mut Filters)| {
+                let mut filters = filters.lock().unwrap();
+                update(&mut filters);
+                let visible = visible_rows(&all_rows, &filters);
+                let count = visible.len();
+                *rows.lock().unwrap() = visible;
+                let mut list = list.lock().unwrap();
+                list.set_item_count(count);
+                list.refresh();
+            }
+        };
+        let mut adult = Checkbox::with_label(CheckState::Checked, "Adult");
+        let refilter_adult = refilter.clone();
+        adult.events.add_handler(Box::new(move |event| {
+            if let InnerEvent::Change(ControlValue::Check(state)) = &event.event {
+                refilter_adult(&|filters| filters.adult = *state == CheckState::Checked);
+            }
+        }));
+        let mut favourites = Switch::with_label(false, "Favourites only");
+        let refilter_favourites = refilter.clone();
+        favourites.events.add_handler(Box::new(move |event| {
+            if let InnerEvent::Change(ControlValue::Toggle(checked)) = &event.event {
+                refilter_favourites(&|filters| filters.favourites_only = *checked);
+            }
+        }));
+        let score_label = Text::new("Score".to_string(), TEXT_LARGE)
+            .style(Style {
+                tokens: StyleTokens { text_fill: Some("text"), ..Default::default() },
+                ..Default::default()
+            });
+        let mut score = Slider::range(0., 10., 0., 10.).step(0.5);
+        let refilter_score = refilter.clone();
+        // refiltering rebuilds the list, so wait for the thumb to be released
+        score.events.add_handler(Box::new(move |event| {
+            if let InnerEvent::Commit(ControlValue::Range(min, max)) = &event.event {
+                refilter_score(&|filters| filters.score = (*min, *max));
+            }
+        }));
+        let score: SharedNode = score.to_shared();
+        set_tooltip(&score, Tooltip::text("Your score, 0 for entries you didn't score")
+            .anchored(Placement::new(Side::Top, PlacementAlign::Center)));
+        let mut status = Select::new(vec![
+            SelectOption::new("", "Any status"),
+            SelectOption::new("CURRENT", "Current"),
+            SelectOption::new("PLANNING", "Planning"),
+            SelectOption::new("COMPLETED", "Completed"),
+            SelectOption::new("PAUSED", "Paused"),
+            SelectOption::new("DROPPED", "Dropped"),
+            SelectOption::new("REPEATING", "Repeating")
+        ]).selected(Some(0));
+        status.events.add_handler(Box::new(move |event| {
+            if let InnerEvent::Change(ControlValue::Selected { value, .. }) = &event.event {
+                let status = (!value.is_empty()).then(|| value.clone());
+                refilter(&|filters| filters.status = status.clone());
+            }
+        }));
+        let mut filter_bar_style = uno!(pl-5 pr-5 pb-5 gap-6 md:pl-10 md:pr-10);
+        filter_bar_style.layout.align_items = AlignItems::Center.into();
+        let filter_bar = Layout::new(vec![adult.to_shared(), favourites.to_shared(), score_label.to_shared(), score, status.to_shared()])
+            .style(filter_bar_style)
+            .to_shared();
+        append(&{ mainview_container.clone() }, &filter_bar);
+        let list: SharedNode = list;
+        append(&{ mainview_container.clone() }, &list);
 
         detach(&loading_container);
         append(&groot_clone, &{ mainview_container.clone() });
@@ -1,18 +1,37 @@
-use lazy_static::lazy_static;
 use mangui::cosmic_text::Metrics;
 use mangui::femtovg::Color;
+use mangui::nodes::theme::Theme;
 
-lazy_static!{
-    pub static ref BACKGROUND: Color = Color::hex("282C34");
-    pub static ref RED: Color = Color::hex("E06C75");
-    pub static ref GREEN: Color = Color::hex("98C379");
-    pub static ref YELLOW: Color = Color::hex("E5C07B");
-    pub static ref BLUE: Color = Color::hex("61AFEF");
-    pub static ref MAGENTA: Color = Color::hex("C678DD");
-    pub static ref CYAN: Color = Color::hex("56B6C2");
-    pub static ref GRAY: Color = Color::hex("ABB2BF");
-    pub static ref WHITE: Color = Color::hex("FFFFFF");
-    pub static ref BLACK: Color = Color::hex("000000");
+/// One Dark colors on top of the default dark theme
+pub fn dark() -> Theme {
+    Theme::dark()
+        .color("background", Color::hex("282C34"))
+        .color("text", Color::hex("FFFFFF"))
+        .color("text-muted", Color::hex("ABB2BF"))
+        .color("accent", Color::hex("61AFEF"))
+        .color("red", Color::hex("E06C75"))
+        .color("green", Color::hex("98C379"))
+        .color("yellow", Color::hex("E5C07B"))
+        .color("blue", Color::hex("61AFEF"))
+        .color("magenta", Color::hex("C678DD"))
+        .color("cyan", Color::hex("56B6C2"))
+}
+
+/// One Light colors on top of the default light theme
+pub fn light() -> Theme {
+    Theme::light()
+        .color("background", Color::hex("FAFAFA"))
+        .color("text", Color::hex("383A42"))
+        .color("text-muted", Color::hex("696C77"))
+        .color("accent", Color::hex("4078F2"))
+        .color("accent-hover", Color::hex("2F6AEF"))
+        .color("accent-pressed", Color::hex("1F5CE6"))
+        .color("red", Color::hex("E45649"))
+        .color("green", Color::hex("50A14F"))
+        .color("yellow", Color::hex("C18401"))
+        .color("blue", Color::hex("4078F2"))
+        .color("magenta", Color::hex("A626A4"))
+        .color("cyan", Color::hex("0184BC"))
 }
 
 pub static TEXT_NORMAL: Metrics = Metrics::new(16., 20.);
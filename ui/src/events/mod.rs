@@ -1,8 +1,9 @@
 use std::ops::{AddAssign, Add, SubAssign, Sub};
 
 use taffy::{prelude::Size, style::Dimension, geometry::Point};
-use winit::{event::ElementState, keyboard::{Key, KeyCode, ModifiersState}};
+use winit::{event::ElementState, keyboard::ModifiersState};
 pub use winit::event::{TouchPhase, MouseScrollDelta, DeviceId, Modifiers, MouseButton};
+pub use winit::keyboard::{Key, KeyCode, NamedKey, PhysicalKey};
 
 use crate::SharedNode;
 pub mod handler;
@@ -57,6 +58,58 @@ pub enum InnerEvent {
     KeyDown(KeyboardEvent),
     /// Key released
     KeyUp(KeyboardEvent),
+    /// A control (like [`crate::nodes::button::Button`]) was activated by a click, or by the keyboard while focused
+    Activate,
+    /// The user changed the value of a control (like a checkbox). Contains the new value
+    Change(ControlValue),
+    /// The user finished changing the value of a control, like releasing the thumb of a slider after dragging it.
+    /// Sent after [`InnerEvent::Change`] by controls which change continuously
+    Commit(ControlValue),
+    /// Scroll offset of the target node changed. Contains the new offset. Does not bubble
+    Scroll(Location),
+    /// Content of the target node (like an image) finished loading. Does not bubble
+    Load,
+    /// Content of the target node (like an image) failed to load. Contains the error message. Does not bubble
+    LoadError(String),
+}
+
+impl InnerEvent {
+    /// Returns `true` if the event is propagated to the parents of the target node.
+    pub fn bubbles(&self) -> bool {
+        !matches!(
+            self,
+            InnerEvent::MouseOver(_) | InnerEvent::MouseOut(_) | InnerEvent::Focus | InnerEvent::Blur | InnerEvent::Scroll(_)
+                | InnerEvent::Load | InnerEvent::LoadError(_)
+        )
+    }
+}
+
+/// Value of a control, sent with [`InnerEvent::Change`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum ControlValue {
+    /// New state of a checkbox
+    Check(CheckState),
+    /// New state of a switch
+    Toggle(bool),
+    /// Index and value of the selected option (of a radio group)
+    Selected {
+        index: usize,
+        value: String
+    },
+    /// Value of a slider
+    Number(f32),
+    /// Start and end values of a slider in range mode
+    Range(f32, f32)
+}
+
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum CheckState {
+    #[default]
+    Unchecked,
+    Checked,
+    /// Neither checked nor unchecked, like a "select all" checkbox when only some items are selected.
+    /// Only set by the application - toggling an indeterminate checkbox checks it.
+    Indeterminate
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -64,7 +117,7 @@ pub struct KeyboardEvent {
     /// Logical location ("it's effect") of the key
     pub key: Option<Key>,
     /// Physical location of the key
-    pub code: KeyCode,
+    pub code: PhysicalKey,
 
     // altKey: bool,
     // ctrlKey: bool,
@@ -73,7 +126,8 @@ pub struct KeyboardEvent {
     /// modifier keys pressed (alt, ctrl, shift or meta/logo/windows)
     pub modifiers: Modifiers,
 
-    // repeat: bool,
+    /// The key is held down and the event was sent again by the OS
+    pub repeat: bool,
     // char_code: u32,
     // key_code: u32,
     // which: u32,
@@ -191,6 +245,29 @@ impl Into<Size<Dimension>> for Location {
     }
 }
 
+impl KeyboardEvent {
+    /// Returns `true` if the logical key is the named key (like [`NamedKey::Enter`]).
+    pub fn is_named(&self, key: NamedKey) -> bool {
+        self.key == Some(Key::Named(key))
+    }
+    /// Returns `true` if the shift key is pressed.
+    pub fn shift(&self) -> bool {
+        self.modifiers.state().intersects(ModifiersState::SHIFT)
+    }
+    /// Returns `true` if the control key is pressed.
+    pub fn ctrl(&self) -> bool {
+        self.modifiers.state().intersects(ModifiersState::CONTROL)
+    }
+    /// Returns `true` if the alt key is pressed.
+    pub fn alt(&self) -> bool {
+        self.modifiers.state().intersects(ModifiersState::ALT)
+    }
+    /// Returns `true` if the logo key is pressed.
+    pub fn logo(&self) -> bool {
+        self.modifiers.state().intersects(ModifiersState::SUPER)
+    }
+}
+
 impl MouseEvent {
     /// Returns `true` if the shift key is pressed.
     pub fn shift(&self) -> bool {
@@ -2,17 +2,20 @@ use std::collections::HashMap;
 use std::num::NonZeroU32;
 use std::ops::Deref;
 use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::{Duration, Instant};
 
 use cosmic_text::FontSystem;
-use events::{Location, MouseValue, NodeEvent, MouseEvent};
+use events::{KeyboardEvent, Location, MouseValue, NamedKey, NodeEvent, MouseEvent};
 use femtovg::renderer::OpenGl;
 use femtovg::{Canvas, Color};
 use glutin::surface::Surface;
 use glutin::{context::PossiblyCurrentContext, display::Display};
 use glutin_winit::DisplayBuilder;
-use nodes::{get_element_at, run_event_handlers, run_single_event_handlers};
-use winit::event::{Event, WindowEvent, Modifiers, DeviceId};
-use winit::event_loop::EventLoop;
+use nodes::image::unload_images;
+use nodes::image_cache::IMAGE_CACHE;
+use nodes::{get_path_to, run_event_handlers, run_queued_events, window_location};
+use winit::event::{Event, WindowEvent, Modifiers, DeviceId, StartCause};
+use winit::event_loop::{ControlFlow, EventLoop, EventLoopProxy};
 use winit::{dpi::PhysicalSize, window::Window};
 
 use glutin::{
@@ -25,7 +28,13 @@ use glutin::{
 use taffy::geometry::Size;
 use taffy::{style::AvailableSpace, TaffyTree};
 use weak_table::PtrWeakKeyHashMap;
+use crate::nodes::units::UnitContext;
 use crate::nodes::{update_taffynode_children, MeasureContext, Node, render_recursively, RenderContext, prepare_render_recursively};
+use crate::nodes::interaction::{apply_focus_requests, focus_next, focus_pressed, send_focus_events, set_hovered, set_pressed, state_of};
+use crate::nodes::theme::{apply_theme_changes, current_theme, set_system_mode, ThemeMode};
+use crate::nodes::overlay::{dismiss_on_click, get_target_at, place_overlays, render_overlays};
+use crate::nodes::tooltip::{hide_tooltip, show_due_tooltip, update_tooltip};
+use crate::nodes::scroll_view::{run_scroll_animations, run_scroll_requests, stop_scroll_animations};
 
 pub mod nodes;
 pub mod events;
@@ -45,6 +54,17 @@ type NodeLayoutMap = PtrWeakKeyHashMap<Weak<Mutex<dyn Node>>, taffy::tree::NodeI
 
 lazy_static::lazy_static! {
     pub static ref FONT_SYSTEM: Mutex<FontSystem> = Mutex::new(FontSystem::new());
+    static ref EVENT_LOOP_PROXY: Mutex<Option<EventLoopProxy<()>>> = Mutex::new(None);
+}
+
+/// Schedules a frame from any thread, for example after a background task finished.
+/// Unlike the `render` channel of [`MainEntry`], this wakes up the event loop immediately, but doesn't recompute the layout by itself.
+/// Does nothing if the event loop isn't running.
+pub fn request_frame() {
+    if let Some(proxy) = EVENT_LOOP_PROXY.lock().unwrap().as_ref() {
+        // fails only if the event loop is closed already
+        let _ = proxy.send_event(());
+    }
 }
 
 /// The entry point of the UI.
@@ -55,6 +75,7 @@ pub struct MainEntry {
     /// This is checked every 'frame' based on the monitor refresh rate.
     /// If there are no messages and no user input, no frame is scheduled.
     /// Currently, you don't need to use this after an event callback - a frame is scheduled after any event.
+    /// Frames scheduled through this channel also recompute the layout, so use it after changing children or layout styles.
     /// The "render queue" is cleared on each frame so that sending multiple values to this channel will only schedule one frame.
     pub render: std::sync::mpsc::Receiver<()>,
 }
@@ -66,6 +87,7 @@ pub struct MainEntry {
 /// your app should exit at this point and only do cleanup.
 pub fn run_event_loop(entry: MainEntry) -> Result<(), winit::error::EventLoopError> {
     let event_loop = EventLoop::new().unwrap();
+    *EVENT_LOOP_PROXY.lock().unwrap() = Some(event_loop.create_proxy());
     let (buffer_context, gl_display, window, surface) = create_window(&event_loop);
 
     if let Err(res) = surface.set_swap_interval(&buffer_context, glutin::surface::SwapInterval::Wait(NonZeroU32::new(1).unwrap())) {
@@ -90,21 +112,44 @@ pub fn run_event_loop(entry: MainEntry) -> Result<(), winit::error::EventLoopErr
     }
 
     let size = window.inner_size();
+    if let Some(theme) = window.theme() {
+        set_system_mode(theme_mode(theme));
+    }
     let mut context = RenderContext {
         canvas,
         node_layout: taffy_map,
         taffy,
         mouse: None,
         keyboard_focus: None,
+        pressed: None,
         scale_factor: window.scale_factor() as f32,
-        window_size: Size { width: size.width as f32, height: size.height as f32 }
+        theme: current_theme(),
+        window_size: Size { width: size.width as f32, height: size.height as f32 },
+        frame_time: Instant::now(),
+        frame_delta: Duration::ZERO,
+        current_node: None,
+        pointer_capture: None,
+        queued_events: Vec::new(),
+        scroll_animations: Vec::new(),
+        frame_requested: false,
+        next_frame: None,
+        clip: None,
+        clip_layers: Default::default(),
+        overlays: Default::default(),
+        tooltips: Default::default(),
+        position_offsets: Default::default(),
+        fixed_nodes: Vec::new(),
+        sticky_container: Default::default(),
+        relayout_requested: false,
+        layout_uses_parent_size: false,
+        event_handled: false
     };
+    let mut last_frame: Option<Instant> = None;
     let root = entry.root.clone();
 
     let mut should_recompute = true;
 
     let mut modifiers = Modifiers::default();
-    let focus_path: Option<Vec<WeakNode>> = None;
     let mut mouse_values: HashMap<DeviceId, MouseValue> = HashMap::new();
 
     let res = event_loop.run(move |event, target| match event {
@@ -117,15 +162,13 @@ pub fn run_event_loop(entry: MainEntry) -> Result<(), winit::error::EventLoopErr
                 let mouse_value = mouse_values.get(&device_id)
                     .unwrap_or(&default);
 
-                let path = get_element_at(&root, &context, mouse_value.last_location);
+                let path = get_target_at(&root, &context, mouse_value.last_location);
 
                 if let Some(path) = path {
-                    let target_layout = context.node_layout.get(path.last().unwrap());
-                    let target_layout = match target_layout {
-                        Some(target_layout) => target_layout,
+                    let target_location = match window_location(path.last().unwrap(), &context) {
+                        Some(target_location) => target_location,
                         None => { return; }
                     };
-                    let target_layout = context.taffy.layout(target_layout.to_owned()).unwrap();
                     let event = NodeEvent {
                         target: path.last().unwrap().clone(),
                         path: path.clone(),
@@ -139,14 +182,22 @@ pub fn run_event_loop(entry: MainEntry) -> Result<(), winit::error::EventLoopErr
                                 movement: Location::new(0., 0.),
                                 device: device_id,
                                 modifiers,
-                                offset: mouse_value.last_location - target_layout.location.into()
+                                offset: mouse_value.last_location - target_location
                             }
                         }
                     };
 
-                    run_event_handlers(path, event);
+                    // user scrolling takes precedence over programmatic scrolling
+                    stop_scroll_animations(&mut context);
+                    hide_tooltip(&mut context);
+                    run_event_handlers(path, event, &mut context);
+                    run_queued_events(&mut context);
                     window.request_redraw();
                 }
+                // scrolling moves other nodes under the pointer
+                let hovered = get_target_at(&root, &context, mouse_value.last_location);
+                set_hovered(&mut context, hovered.as_deref());
+                update_tooltip(&mut context, Some(mouse_value.last_location));
             },
             WindowEvent::CursorMoved { device_id, position, .. } => {
                 let mouse_value = mouse_values.get(&device_id);
@@ -172,15 +223,17 @@ pub fn run_event_loop(entry: MainEntry) -> Result<(), winit::error::EventLoopErr
                 let buttons = mouse_value.buttons;
                 mouse_values.insert(device_id, mouse_value);
 
-                let path = get_element_at(&root, &context, location);
+                // hover follows the pointer even while it's captured
+                let hovered = get_target_at(&root, &context, location);
+                set_hovered(&mut context, hovered.as_deref());
+                update_tooltip(&mut context, Some(location));
+                let path = get_mouse_target(&root, &context, location);
 
                 if let Some(path) = path {
-                    let target_layout = context.node_layout.get(path.last().unwrap());
-                    let target_layout = match target_layout {
-                        Some(target_layout) => target_layout,
+                    let target_location = match window_location(path.last().unwrap(), &context) {
+                        Some(target_location) => target_location,
                         None => { return; }
                     };
-                    let target_layout = context.taffy.layout(target_layout.to_owned()).unwrap();
                     let event = NodeEvent {
                         target: path.last().unwrap().clone(),
                         path: path.clone(),
@@ -191,47 +244,79 @@ pub fn run_event_loop(entry: MainEntry) -> Result<(), winit::error::EventLoopErr
                             movement,
                             device: device_id,
                             modifiers,
-                            offset: location - target_layout.location.into()
+                            offset: location - target_location
                         })
                     };
 
-                    run_event_handlers(path, event);
+                    run_event_handlers(path, event, &mut context);
+                    run_queued_events(&mut context);
                     window.request_redraw();
                 }
             },
+            WindowEvent::CursorLeft { .. } => {
+                set_hovered(&mut context, None);
+                update_tooltip(&mut context, None);
+                window.request_redraw();
+            },
             WindowEvent::DroppedFile(_path) => {},
             WindowEvent::HoveredFile(_path) => {},
             WindowEvent::HoveredFileCancelled => {},
             WindowEvent::Focused(focused) => {
-                match &focus_path {
+                match &context.keyboard_focus {
                     Some(path) => {
                         let strong_focus_path: Option<Vec<SharedNode>> = convert_vec_option_to_option_vec(path.iter().map(|weak| weak.upgrade()).collect());
                         if strong_focus_path.is_none() { return; }
                         let strong_focus_path = strong_focus_path.unwrap();
                         if strong_focus_path.is_empty() { return; }
 
-                        let focus_event = NodeEvent {
-                            target: strong_focus_path.last().unwrap().clone(),
-                            path: strong_focus_path.clone(),
-                            event: if focused { events::InnerEvent::Focus } else { events::InnerEvent::Blur }
-                        };
-                        // strong_focus_path.last().unwrap().write().unwrap().on_event(&focus_event);
-                        run_single_event_handlers(strong_focus_path.last().unwrap().clone(), focus_event);
-
-                        let focus_event = NodeEvent {
-                            target: strong_focus_path.last().unwrap().clone(),
-                            path: strong_focus_path.clone(),
-                            event: if focused { events::InnerEvent::FocusIn } else { events::InnerEvent::FocusOut }
-                        };
-
-                        run_event_handlers(strong_focus_path, focus_event);
+                        match focused {
+                            true => send_focus_events(&mut context, strong_focus_path, events::InnerEvent::Focus, events::InnerEvent::FocusIn),
+                            false => send_focus_events(&mut context, strong_focus_path, events::InnerEvent::Blur, events::InnerEvent::FocusOut)
+                        }
+                        run_queued_events(&mut context);
                         window.request_redraw();
                     },
                     None => {}
                 };
             },
+            WindowEvent::ThemeChanged(theme) => set_system_mode(theme_mode(theme)),
             WindowEvent::ModifiersChanged(new_modifiers) => { modifiers = new_modifiers; },
-            WindowEvent::KeyboardInput { device_id: _, event: _, is_synthetic: _ } => {},
+            WindowEvent::KeyboardInput { device_id, event, is_synthetic: _ } => {
+                // keys go to the focused node, or to the root if nothing is focused
+                let path = context.keyboard_focus.as_ref()
+                    .and_then(|path| convert_vec_option_to_option_vec(path.iter().map(|weak| weak.upgrade()).collect()))
+                    .filter(|path| !path.is_empty())
+                    .unwrap_or_else(|| vec![root.clone()]);
+                let keyboard_event = KeyboardEvent {
+                    key: Some(event.logical_key),
+                    code: event.physical_key,
+                    modifiers,
+                    repeat: event.repeat,
+                    device: device_id
+                };
+                let pressed = event.state == winit::event::ElementState::Pressed;
+                let tab = pressed && keyboard_event.is_named(NamedKey::Tab);
+                if pressed && keyboard_event.is_named(NamedKey::Escape) {
+                    hide_tooltip(&mut context);
+                }
+                let backwards = keyboard_event.shift();
+                let event = NodeEvent {
+                    target: path.last().unwrap().clone(),
+                    path: path.clone(),
+                    event: match pressed {
+                        true => events::InnerEvent::KeyDown(keyboard_event),
+                        false => events::InnerEvent::KeyUp(keyboard_event)
+                    }
+                };
+                run_event_handlers(path, event, &mut context);
+                if tab && !context.is_handled() {
+                    let mut roots = vec![root.clone()];
+                    roots.extend(context.overlays.nodes());
+                    focus_next(&mut context, &roots, backwards);
+                }
+                run_queued_events(&mut context);
+                window.request_redraw();
+            },
             WindowEvent::MouseInput { device_id, state, button, .. } => {
                 let mouse_value = mouse_values.get(&device_id);
                 let mut mouse_value = match mouse_value {
@@ -239,17 +324,28 @@ pub fn run_event_loop(entry: MainEntry) -> Result<(), winit::error::EventLoopErr
                     None => { return; } // Mouse move should be fired first
                 };
                 mouse_value.update_buttons(button, state);
+                mouse_values.insert(device_id, mouse_value);
 
                 let location = mouse_value.last_location;
-                let path = get_element_at(&root, &context, location);
+                let path = get_mouse_target(&root, &context, location);
+                if state == winit::event::ElementState::Pressed {
+                    hide_tooltip(&mut context);
+                    dismiss_on_click(&mut context, path.as_deref().unwrap_or_default());
+                    if button == winit::event::MouseButton::Left {
+                        set_pressed(&mut context, path.as_deref());
+                        focus_pressed(&mut context, path.as_deref().unwrap_or_default());
+                    }
+                    window.request_redraw();
+                } else if mouse_value.buttons == 0 && context.pressed.is_some() {
+                    set_pressed(&mut context, None);
+                    window.request_redraw();
+                }
 
                 if let Some(path) = path {
-                    let target_layout = context.node_layout.get(path.last().unwrap());
-                    let target_layout = match target_layout {
-                        Some(target_layout) => target_layout,
+                    let target_location = match window_location(path.last().unwrap(), &context) {
+                        Some(target_location) => target_location,
                         None => { return; }
                     };
-                    let target_layout = context.taffy.layout(target_layout.to_owned()).unwrap();
                     let mevent = MouseEvent {
                         button: Some(button),
                         buttons: mouse_value.buttons,
@@ -257,7 +353,7 @@ pub fn run_event_loop(entry: MainEntry) -> Result<(), winit::error::EventLoopErr
                         movement: Location::new(0., 0.),
                         device: device_id,
                         modifiers,
-                        offset: location - target_layout.location.into()
+                        offset: location - target_location
                     };
                     let event = NodeEvent {
                         target: path.last().unwrap().clone(),
@@ -269,7 +365,11 @@ pub fn run_event_loop(entry: MainEntry) -> Result<(), winit::error::EventLoopErr
                     };
 
                     window.request_redraw();
-                    run_event_handlers(path, event);
+                    run_event_handlers(path, event, &mut context);
+                    if mouse_value.buttons == 0 {
+                        context.pointer_capture = None;
+                    }
+                    run_queued_events(&mut context);
                 }
             },
             WindowEvent::CloseRequested => target.exit(),
@@ -283,11 +383,35 @@ pub fn run_event_loop(entry: MainEntry) -> Result<(), winit::error::EventLoopErr
                 drop(groot);
                 window.request_redraw();
                 context.scale_factor = window.scale_factor() as f32;
+                context.window_size = Size { width: size.width as f32, height: size.height as f32 };
                 should_recompute = true;
             },
             WindowEvent::RedrawRequested => {
-                if should_recompute {
-                    update_taffynode_children(&root, &mut context);
+                let now = Instant::now();
+                context.frame_delta = last_frame.map(|last_frame| now - last_frame).unwrap_or(Duration::ZERO);
+                context.frame_time = now;
+                last_frame = Some(now);
+                context.frame_requested = false;
+                context.next_frame = None;
+                IMAGE_CACHE.lock().unwrap().upload(&mut context.canvas);
+                show_due_tooltip(&mut context);
+                if context.overlays.apply_requests() {
+                    should_recompute = true;
+                }
+                apply_focus_requests(&mut context);
+                run_queued_events(&mut context);
+                if let Some(theme) = apply_theme_changes() {
+                    // tokens can change the layout too (spacing, typography)
+                    context.theme = theme;
+                    should_recompute = true;
+                }
+                prepare_render_recursively(&root, &mut context);
+                for overlay in context.overlays.nodes() {
+                    prepare_render_recursively(&overlay, &mut context);
+                }
+                if should_recompute || context.relayout_requested {
+                    context.layout_uses_parent_size = false;
+                    update_layout_tree(&root, &mut context);
                     let src_nodes = context.node_layout.values().map(|v| v.to_owned()).collect::<Vec<_>>();
                     context.node_layout.remove_expired();
                     let dst_nodes = context.node_layout.values().map(|v| v.to_owned()).collect::<Vec<_>>();
@@ -297,34 +421,16 @@ pub fn run_event_loop(entry: MainEntry) -> Result<(), winit::error::EventLoopErr
                             dbg!("Removed node", src_node);
                         }
                     }
-                    prepare_render_recursively(&root, &mut context);
-                    for (node, taffy_node) in context.node_layout.iter() {
-                        let node = node.lock().unwrap();
-                        let node_style = node.style();
-                        context.taffy.set_style(*taffy_node, node_style.layout.to_owned()).unwrap();
-                    }
                     let size = window.inner_size();
                     let size = Size { width: AvailableSpace::Definite(size.width as f32), height: AvailableSpace::Definite(size.height as f32) };
-                    let RenderContext { taffy, node_layout, canvas, scale_factor, .. } = &mut context;
-                    let mut measure_context = MeasureContext { canvas, scale_factor: *scale_factor };
-                    taffy.compute_layout_with_measure(
-                        *node_layout.get(&root).unwrap(),
-                        size,
-                        |known_dimensions, available_space, _node_id, node_context| {
-                            match node_context {
-                                Some(node) => {
-                                    match node.upgrade() {
-                                        Some(node) => {
-                                            node.lock().unwrap().measure(&mut measure_context, known_dimensions, available_space)
-                                        },
-                                        None => Size::ZERO
-                                    }
-                                },
-                                None => Size::ZERO
-                            }
-                        },
-                    ).unwrap();
+                    compute_layouts(&root, size, &mut context);
+                    if context.layout_uses_parent_size {
+                        // sums with percentages were resolved against the parent sizes of the previous pass
+                        update_layout_tree(&root, &mut context);
+                        compute_layouts(&root, size, &mut context);
+                    }
                     should_recompute = false;
+                    context.relayout_requested = false;
                     // Additional optimizations could be done here
                     // - When setting styles, check that the styles aren't the same (taffy doesn't do that and instead always mark it as dirty)
                     // - taffy seems to always recompute (maybe internally checks dirtyness, I didn't look into it that much)
@@ -333,19 +439,41 @@ pub fn run_event_loop(entry: MainEntry) -> Result<(), winit::error::EventLoopErr
                     // could perhaps be a significant boost regarding memory usage (and performance) during large layout changes
                     // dbg!("recomputed");
                 }
+                run_scroll_requests(&mut context);
+                run_scroll_animations(&mut context);
+                place_overlays(&mut context);
                 // Clear the render queue
                 while entry.render.try_recv().is_ok() {}
                 render(&buffer_context, &surface, &window, &mut context, &root);
+                // free images at the frame boundary, so that they're not deleted while in use
+                unload_images(&mut context.canvas);
+                IMAGE_CACHE.lock().unwrap().end_frame(&mut context.canvas);
+                run_queued_events(&mut context);
+                if context.frame_requested {
+                    window.request_redraw();
+                }
+                target.set_control_flow(match context.next_frame {
+                    Some(time) => ControlFlow::WaitUntil(time),
+                    None => ControlFlow::Wait
+                });
             }
             _ => {}
         },
-        Event::NewEvents(_) => {
+        Event::UserEvent(()) => {
+            window.request_redraw();
+        },
+        Event::NewEvents(cause) => {
+            // frame scheduled with RenderContext::request_frame_at
+            if let StartCause::ResumeTimeReached { .. } = cause {
+                window.request_redraw();
+            }
             // if let Some(monitor) = window.current_monitor() {
             //     if let Some(refresh_rate) = monitor.refresh_rate_millihertz() {
                     // dbg!(refresh_rate);
                     // some leeway before vsync
                     // target.set_control_flow(ControlFlow::wait_duration(Duration::from_millis(1000 / refresh_rate as u64 - 100/refresh_rate as u64)));
                     if entry.render.try_recv().is_ok() {
+                        should_recompute = true;
                         window.request_redraw();
                     }
             //     }
@@ -357,6 +485,70 @@ pub fn run_event_loop(entry: MainEntry) -> Result<(), winit::error::EventLoopErr
     res
 }
 
+fn theme_mode(theme: winit::window::Theme) -> ThemeMode {
+    match theme {
+        winit::window::Theme::Light => ThemeMode::Light,
+        winit::window::Theme::Dark => ThemeMode::Dark
+    }
+}
+
+/// Syncs the taffy tree and the layout styles of the root and overlays with the nodes.
+fn update_layout_tree(root: &SharedNode, context: &mut RenderContext) {
+    let font_size = root.lock().unwrap().style().font_size;
+    let units = UnitContext::root(font_size, context.window_size);
+    update_taffynode_children(root, context, units);
+    for overlay in context.overlays.nodes() {
+        update_taffynode_children(&overlay, context, units);
+    }
+}
+
+/// Computes the layout of the root (filling the available space) and of overlays.
+fn compute_layouts(root: &SharedNode, available_space: Size<AvailableSpace>, context: &mut RenderContext) {
+    compute_layout(root, available_space, context);
+    // overlays are sized by their content, they're kept inside the window when placed
+    for overlay in context.overlays.nodes() {
+        compute_layout(&overlay, Size { width: AvailableSpace::MaxContent, height: AvailableSpace::MaxContent }, context);
+    }
+}
+
+/// Computes the layout of the tree with the root, measuring leaf nodes.
+fn compute_layout(root: &SharedNode, available_space: Size<AvailableSpace>, context: &mut RenderContext) {
+    let RenderContext { taffy, node_layout, canvas, scale_factor, window_size, theme, mouse, pressed, keyboard_focus, .. } = context;
+    let mut measure_context = MeasureContext {
+        canvas,
+        scale_factor: *scale_factor,
+        window_size: *window_size,
+        theme: theme.clone(),
+        state: Default::default()
+    };
+    taffy.compute_layout_with_measure(
+        *node_layout.get(root).unwrap(),
+        available_space,
+        |known_dimensions, available_space, _node_id, node_context| {
+            match node_context {
+                Some(node) => {
+                    match node.upgrade() {
+                        Some(node) => {
+                            measure_context.state = state_of(Arc::as_ptr(&node) as *const (), mouse, pressed, keyboard_focus);
+                            node.lock().unwrap().measure(&mut measure_context, known_dimensions, available_space)
+                        },
+                        None => Size::ZERO
+                    }
+                },
+                None => Size::ZERO
+            }
+        },
+    ).unwrap();
+}
+
+/// Returns path to the node receiving mouse events at the location - either the node capturing the pointer, or the node under it.
+fn get_mouse_target(root: &SharedNode, context: &RenderContext, location: Location) -> Option<Vec<SharedNode>> {
+    if let Some(capture) = context.pointer_capture.as_ref().and_then(|node| node.upgrade()) {
+        return Some(get_path_to(&capture));
+    }
+    get_target_at(root, context, location)
+}
+
 /// I have no idea if there's a better way to do this in rust...
 /// Found via ChatGPT (the only piece of code by chatgpt itself in this whole project as of now)
 fn convert_vec_option_to_option_vec<T>(vec: Vec<Option<T>>) -> Option<Vec<T>> {
@@ -414,7 +606,9 @@ fn render(
     context.canvas.set_size(size.width, size.height, window.scale_factor() as f32);
     context.canvas.clear_rect(0, 0, size.width, size.height, Color::black());
 
+    context.fixed_nodes.clear();
     render_recursively(root_node, context);
+    render_overlays(context);
 
     context.canvas.flush();
     window.pre_present_notify();
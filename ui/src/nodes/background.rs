@@ -0,0 +1,325 @@
+use std::path::PathBuf;
+use femtovg::{Color, ImageFlags, Paint, Path};
+use taffy::{LengthPercentage, Point, Rect, Size};
+use crate::nodes::{ObjectFit, ObjectPosition, RenderContext};
+use crate::nodes::image_cache::{CachedImage, CachedStatus, IMAGE_CACHE};
+
+/// Background of a node. Unlike a [`Paint`], gradients and images are resolved against the size of the node when drawn.
+///
+/// Colors and paints can be converted into a background with `.into()`.
+#[derive(Clone, Debug)]
+pub enum Background {
+    Color(Color),
+    /// Paint with coordinates relative to the top left corner of the node. It's not resized with the node
+    Paint(Paint),
+    LinearGradient(LinearGradient),
+    RadialGradient(RadialGradient),
+    BoxGradient(BoxGradient),
+    Image(BackgroundImage),
+    NineSlice(NineSlice)
+}
+
+/// Color stop of a gradient
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GradientStop {
+    /// Position along the gradient, 0.0 is the start and 1.0 the end
+    pub offset: f32,
+    pub color: Color
+}
+
+impl GradientStop {
+    pub fn new(offset: f32, color: Color) -> GradientStop {
+        GradientStop { offset, color }
+    }
+}
+
+/// Linear gradient over the whole node, like CSS `linear-gradient`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearGradient {
+    /// Direction of the gradient in degrees. 0 goes to the top, 90 to the right and 180 (default) to the bottom
+    pub angle: f32,
+    pub stops: Vec<GradientStop>
+}
+
+impl Default for LinearGradient {
+    fn default() -> Self {
+        LinearGradient { angle: 180., stops: Vec::new() }
+    }
+}
+
+/// Circular gradient, like CSS `radial-gradient`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RadialGradient {
+    /// Percentages are relative to the size of the node. Default is the center of the node
+    pub center: Point<LengthPercentage>,
+    /// Percentages are relative to the distance from the center to the farthest corner. Default is 100%
+    pub radius: LengthPercentage,
+    pub stops: Vec<GradientStop>
+}
+
+impl Default for RadialGradient {
+    fn default() -> Self {
+        RadialGradient {
+            center: Point { x: LengthPercentage::Percent(0.5), y: LengthPercentage::Percent(0.5) },
+            radius: LengthPercentage::Percent(1.),
+            stops: Vec::new()
+        }
+    }
+}
+
+/// Gradient following the edges of the node (a blurred rounded rectangle), useful for glows and inner shadows.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoxGradient {
+    /// Distance from the edges of the node to the rectangle
+    pub inset: f32,
+    /// Corner radius of the rectangle
+    pub radius: f32,
+    /// How far the gradient spreads
+    pub feather: f32,
+    /// Color inside the rectangle
+    pub inner: Color,
+    /// Color outside of the rectangle
+    pub outer: Color
+}
+
+/// How a background image is repeated, like CSS `background-repeat`.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum BackgroundRepeat {
+    #[default]
+    NoRepeat,
+    Repeat,
+    RepeatX,
+    RepeatY
+}
+
+/// Image drawn in the background of a node. Images are shared through the [`IMAGE_CACHE`].
+#[derive(Clone, Debug)]
+pub struct BackgroundImage {
+    pub image: CachedImage,
+    /// How the image is sized. [`ObjectFit::None`] keeps the size of the image (CSS `auto`). Default is [`ObjectFit::Cover`]
+    pub size: ObjectFit,
+    pub position: ObjectPosition,
+    pub repeat: BackgroundRepeat
+}
+
+impl BackgroundImage {
+    /// Loads the image file through the [`IMAGE_CACHE`].
+    pub fn file(path: impl Into<PathBuf>) -> BackgroundImage {
+        BackgroundImage::new(IMAGE_CACHE.lock().unwrap().get_file(path.into(), ImageFlags::REPEAT_X | ImageFlags::REPEAT_Y))
+    }
+    /// The image needs to be loaded with [`ImageFlags::REPEAT_X`] and [`ImageFlags::REPEAT_Y`] to be repeated.
+    pub fn new(image: CachedImage) -> BackgroundImage {
+        BackgroundImage {
+            image,
+            size: ObjectFit::Cover,
+            position: ObjectPosition::default(),
+            repeat: BackgroundRepeat::NoRepeat
+        }
+    }
+    pub fn size(mut self, size: ObjectFit) -> BackgroundImage {
+        self.size = size;
+        self
+    }
+    pub fn position(mut self, position: ObjectPosition) -> BackgroundImage {
+        self.position = position;
+        self
+    }
+    pub fn repeat(mut self, repeat: BackgroundRepeat) -> BackgroundImage {
+        self.repeat = repeat;
+        self
+    }
+}
+
+/// Image split into nine parts by the insets. Corners keep their size, edges and the center are stretched.
+/// Used for frames, speech bubbles and similar decorations which need to scale without distorting their corners.
+#[derive(Clone, Debug)]
+pub struct NineSlice {
+    pub image: CachedImage,
+    /// Insets of the slices in image pixels
+    pub slice: Rect<f32>,
+    /// Size of the corners and edges in the node. Defaults to the slice insets
+    pub widths: Option<Rect<f32>>,
+    /// Whether the center part is drawn. Default is true
+    pub fill_center: bool
+}
+
+impl NineSlice {
+    /// Loads the image file through the [`IMAGE_CACHE`].
+    pub fn file(path: impl Into<PathBuf>, slice: Rect<f32>) -> NineSlice {
+        NineSlice {
+            image: IMAGE_CACHE.lock().unwrap().get_file(path.into(), ImageFlags::empty()),
+            slice,
+            widths: None,
+            fill_center: true
+        }
+    }
+}
+
+impl From<Color> for Background {
+    fn from(color: Color) -> Self {
+        Background::Color(color)
+    }
+}
+
+impl From<Paint> for Background {
+    fn from(paint: Paint) -> Self {
+        Background::Paint(paint)
+    }
+}
+
+impl From<LinearGradient> for Background {
+    fn from(gradient: LinearGradient) -> Self {
+        Background::LinearGradient(gradient)
+    }
+}
+
+impl From<RadialGradient> for Background {
+    fn from(gradient: RadialGradient) -> Self {
+        Background::RadialGradient(gradient)
+    }
+}
+
+impl From<BoxGradient> for Background {
+    fn from(gradient: BoxGradient) -> Self {
+        Background::BoxGradient(gradient)
+    }
+}
+
+impl From<BackgroundImage> for Background {
+    fn from(image: BackgroundImage) -> Self {
+        Background::Image(image)
+    }
+}
+
+impl From<NineSlice> for Background {
+    fn from(nine_slice: NineSlice) -> Self {
+        Background::NineSlice(nine_slice)
+    }
+}
+
+fn resolve(length: LengthPercentage, relative_to: f32) -> f32 {
+    match length {
+        LengthPercentage::Length(length) => length,
+        LengthPercentage::Percent(percent) => percent * relative_to
+    }
+}
+
+fn stops(stops: &[GradientStop]) -> impl Iterator<Item = (f32, Color)> + '_ {
+    stops.iter().map(|stop| (stop.offset, stop.color))
+}
+
+/// Returns the first frame of the image if it's loaded and visible, marking it as used (and loading it again if it was evicted).
+/// Backgrounds scrolled out of view aren't drawn, so they're left to be evicted.
+fn image_frame(image: &CachedImage, context: &RenderContext, size: Size<f32>) -> Option<(femtovg::ImageId, Size<f32>)> {
+    if !context.is_visible(size) {
+        return None;
+    }
+    let mut cache = IMAGE_CACHE.lock().unwrap();
+    cache.touch(image);
+    match cache.status(image) {
+        CachedStatus::Ready(frames) => Some((frames[0].id, cache.size(image)?)),
+        _ => None
+    }
+}
+
+impl Background {
+    /// Returns the paint for the area of the size, or None for images (which aren't drawn with a single paint).
+    pub fn to_paint(&self, size: Size<f32>) -> Option<Paint> {
+        match self {
+            Background::Color(color) => Some(Paint::color(*color)),
+            Background::Paint(paint) => Some(paint.clone()),
+            Background::LinearGradient(gradient) => {
+                let angle = gradient.angle.to_radians();
+                let (dx, dy) = (angle.sin(), -angle.cos());
+                // like CSS, the gradient line is long enough for the corners to get the colors of the first and last stop
+                let half = ((size.width * dx).abs() + (size.height * dy).abs()) / 2.;
+                let (cx, cy) = (size.width / 2., size.height / 2.);
+                Some(Paint::linear_gradient_stops(cx - dx * half, cy - dy * half, cx + dx * half, cy + dy * half, stops(&gradient.stops)))
+            },
+            Background::RadialGradient(gradient) => {
+                let x = resolve(gradient.center.x, size.width);
+                let y = resolve(gradient.center.y, size.height);
+                let farthest_x = x.max(size.width - x);
+                let farthest_y = y.max(size.height - y);
+                let radius = resolve(gradient.radius, (farthest_x * farthest_x + farthest_y * farthest_y).sqrt());
+                Some(Paint::radial_gradient_stops(x, y, 0., radius, stops(&gradient.stops)))
+            },
+            Background::BoxGradient(gradient) => Some(Paint::box_gradient(
+                gradient.inset,
+                gradient.inset,
+                size.width - gradient.inset * 2.,
+                size.height - gradient.inset * 2.,
+                gradient.radius,
+                gradient.feather,
+                gradient.inner,
+                gradient.outer
+            )),
+            Background::Image(_) | Background::NineSlice(_) => None
+        }
+    }
+
+    /// Draws the background into the area of the size at the origin of the canvas, clipped to the path.
+    /// Nine-slice images are drawn over the whole area, as their corners are part of the image.
+    pub fn draw(&self, context: &mut RenderContext, size: Size<f32>, path: &Path) {
+        if let Some(paint) = self.to_paint(size) {
+            context.canvas.fill_path(path, &paint);
+            return;
+        }
+        match self {
+            Background::Image(background) => {
+                let Some((image, image_size)) = image_frame(&background.image, context, size) else { return; };
+                let fitted = background.size.fit(image_size, size);
+                let x = (size.width - fitted.width) * background.position.x;
+                let y = (size.height - fitted.height) * background.position.y;
+                let repeat_x = matches!(background.repeat, BackgroundRepeat::Repeat | BackgroundRepeat::RepeatX);
+                let repeat_y = matches!(background.repeat, BackgroundRepeat::Repeat | BackgroundRepeat::RepeatY);
+                // the image pattern repeats in both directions, clip it on axes which don't repeat
+                context.canvas.save();
+                context.canvas.intersect_scissor(
+                    if repeat_x { 0. } else { x },
+                    if repeat_y { 0. } else { y },
+                    if repeat_x { size.width } else { fitted.width },
+                    if repeat_y { size.height } else { fitted.height }
+                );
+                context.canvas.fill_path(path, &Paint::image(image, x, y, fitted.width, fitted.height, 0., 1.));
+                context.canvas.restore();
+            },
+            Background::NineSlice(nine_slice) => {
+                let Some((image, image_size)) = image_frame(&nine_slice.image, context, size) else { return; };
+                let slice = nine_slice.slice;
+                let widths = nine_slice.widths.unwrap_or(slice);
+                // source and destination positions of the slice edges along each axis
+                let source_x = [0., slice.left, image_size.width - slice.right, image_size.width];
+                let source_y = [0., slice.top, image_size.height - slice.bottom, image_size.height];
+                let target_x = [0., widths.left, size.width - widths.right, size.width];
+                let target_y = [0., widths.top, size.height - widths.bottom, size.height];
+                for row in 0..3 {
+                    for column in 0..3 {
+                        if row == 1 && column == 1 && !nine_slice.fill_center {
+                            continue;
+                        }
+                        let (source_width, source_height) = (source_x[column + 1] - source_x[column], source_y[row + 1] - source_y[row]);
+                        let (width, height) = (target_x[column + 1] - target_x[column], target_y[row + 1] - target_y[row]);
+                        if source_width <= 0. || source_height <= 0. || width <= 0. || height <= 0. {
+                            continue;
+                        }
+                        let (scale_x, scale_y) = (width / source_width, height / source_height);
+                        let paint = Paint::image(
+                            image,
+                            target_x[column] - source_x[column] * scale_x,
+                            target_y[row] - source_y[row] * scale_y,
+                            image_size.width * scale_x,
+                            image_size.height * scale_y,
+                            0.,
+                            1.
+                        );
+                        let mut patch = Path::new();
+                        patch.rect(target_x[column], target_y[row], width, height);
+                        context.canvas.fill_path(&patch, &paint);
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt::{Debug, Formatter};
+use cosmic_text::Metrics;
+use taffy::{AlignItems, JustifyContent, Layout, LengthPercentage, Rect, Size};
+use crate::{nodes::{Node, NodeChildren, Style, TaffyStyle, ToShared}, events::handler::EventHandlerDatabase, WeakNode, SharedNode};
+use crate::events::{InnerEvent, NodeEvent};
+use crate::nodes::primitives::{draw_control_background, draw_focus_ring};
+use crate::nodes::text::Text;
+use crate::nodes::theme::StyleTokens;
+use crate::nodes::interaction::Activation;
+use crate::nodes::variant::StyleCondition;
+use crate::nodes::RenderContext;
+pub use crate::nodes::primitives::DISABLED_ALPHA;
+
+/// Button containing text, icons or any other nodes.
+///
+/// The button is activated by clicking it, or by pressing Enter or Space while it's focused. Activation sends
+/// a single [`InnerEvent::Activate`] event to the handlers instead of the raw mouse and keyboard events.
+/// Disabled buttons (see [`Style::disabled`]) can't be activated and are drawn with [`DISABLED_ALPHA`].
+///
+/// The default style ([`Button::default_style`]) uses the `accent` colors of the theme. It can be replaced, or extended
+/// with variants for the hovered, pressed, focused and disabled states.
+pub struct Button {
+    pub style: Style,
+    pub children: NodeChildren,
+    pub events: EventHandlerDatabase,
+    pub parent: Option<WeakNode>,
+    activation: Activation
+}
+
+impl Default for Button {
+    fn default() -> Self {
+        Button::new(NodeChildren::default())
+    }
+}
+
+impl Button {
+    pub fn new(children: NodeChildren) -> Button {
+        Button {
+            style: Button::default_style(),
+            children,
+            events: EventHandlerDatabase::default(),
+            parent: None,
+            activation: Activation::default()
+        }
+    }
+
+    /// Button with a single text child, colored with the `on-accent` token.
+    pub fn with_label(label: impl Into<String>) -> Button {
+        let text = Text::new(label.into(), Metrics::new(16., 20.))
+            .style(Style {
+                tokens: StyleTokens { text_fill: Some("on-accent"), typography: Some("body"), ..Default::default() },
+                ..Default::default()
+            });
+        Button::new(vec![text.to_shared()])
+    }
+
+    pub fn style(mut self, style: Style) -> Button {
+        self.style = style;
+        self
+    }
+
+    /// Centers the children in a row, with an `accent` background which changes when hovered or pressed.
+    pub fn default_style() -> Style {
+        Style {
+            layout: TaffyStyle {
+                align_items: Some(AlignItems::Center),
+                justify_content: Some(JustifyContent::Center),
+                padding: Rect {
+                    left: LengthPercentage::Length(16.),
+                    right: LengthPercentage::Length(16.),
+                    top: LengthPercentage::Length(8.),
+                    bottom: LengthPercentage::Length(8.)
+                },
+                gap: Size { width: LengthPercentage::Length(8.), height: LengthPercentage::Length(8.) },
+                ..Default::default()
+            },
+            tokens: StyleTokens { background: Some("accent"), border_radius: Some("md"), ..Default::default() },
+            ..Default::default()
+        }
+            .variant(StyleCondition::Hovered, |style| style.tokens.background = Some("accent-hover"))
+            .variant(StyleCondition::Pressed, |style| style.tokens.background = Some("accent-pressed"))
+            .variant(StyleCondition::Disabled, |style| style.tokens.background = Some("accent"))
+    }
+}
+
+impl Debug for Button {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Button")
+            .field("style", &self.style)
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+impl Node for Button {
+    fn style(&self) -> &Style {
+        &self.style
+    }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
+    fn children(&self) -> Option<&NodeChildren> {
+        Some(&self.children)
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: &NodeEvent, context: &mut RenderContext) {
+        if self.activation.handle(event, context, self.style.disabled, true) {
+            context.emit(InnerEvent::Activate);
+        }
+    }
+
+    fn render_pre_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        let style = self.activation.resolve_style(&self.style, context);
+        draw_control_background(&style, layout.size, context);
+    }
+
+    fn render_post_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        if context.style_environment().state.focused {
+            let radius = self.activation.resolve_style(&self.style, context).border_radius;
+            draw_focus_ring(&radius, layout.size, context);
+        }
+    }
+
+    fn add_child_at(&mut self, child: SharedNode, index: usize) -> Result<(), super::ChildAddError> {
+        let mut index = index;
+        if let Some(i) = self.has_child(&child) {
+            self.children.remove(i);
+            if i < index {
+                index -= 1;
+            }
+        }
+        self.children.insert(index, child);
+        Ok(())
+    }
+
+    fn event_handlers(&self) -> Option<crate::events::handler::InnerEventHandlerDataset> {
+        Some(self.events.handlers.clone())
+    }
+    fn set_parent(&mut self, parent: Option<WeakNode>) {
+        self.parent = parent;
+    }
+    fn parent(&self) -> Option<SharedNode> {
+        match &self.parent {
+            Some(parent) => parent.upgrade(),
+            None => None
+        }
+    }
+    fn remove_child(&mut self, child: &SharedNode) -> Result<(), super::ChildAddError> {
+        if let Some(i) = self.has_child(child) {
+            self.children.remove(i);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt::{Debug, Formatter};
+use taffy::{AvailableSpace, Layout, Size};
+use crate::{nodes::{Node, NodeChildren, RenderContext, Style}, events::handler::EventHandlerDatabase, WeakNode, SharedNode};
+use crate::nodes::MeasureContext;
+
+/// Draws the node. Called with the canvas translated to the top left corner of the node.
+/// The canvas state (transform, scissor...) is restored afterwards.
+pub type DrawCallback = dyn FnMut(&mut RenderContext, Layout) + Send;
+/// Returns the size of the node, see [`Node::measure`].
+pub type MeasureCallback = dyn FnMut(&mut MeasureContext, Size<Option<f32>>, Size<AvailableSpace>) -> Size<f32> + Send;
+
+/// Node drawn by a closure, for custom drawing (charts, progress rings...) without implementing [`Node`].
+///
+/// ```rust
+/// let ring = CanvasNode::new(move |context, layout| {
+///     let mut path = Path::new();
+///     path.arc(layout.size.width / 2., layout.size.height / 2., 20., 0., progress * TAU, Solidity::Hole);
+///     context.canvas.stroke_path(&path, &Paint::color(Color::white()));
+/// });
+/// ```
+pub struct CanvasNode {
+    pub style: Style,
+    pub events: EventHandlerDatabase,
+    pub parent: Option<WeakNode>,
+    pub draw: Box<DrawCallback>,
+    /// Without a measure callback, the node is sized by its style only
+    pub measure: Option<Box<MeasureCallback>>
+}
+
+impl CanvasNode {
+    pub fn new(draw: impl FnMut(&mut RenderContext, Layout) + Send + 'static) -> CanvasNode {
+        CanvasNode {
+            style: Style::default(),
+            events: EventHandlerDatabase::default(),
+            parent: None,
+            draw: Box::new(draw),
+            measure: None
+        }
+    }
+    pub fn style(mut self, style: Style) -> CanvasNode {
+        self.style = style;
+        self
+    }
+    pub fn measure(mut self, measure: impl FnMut(&mut MeasureContext, Size<Option<f32>>, Size<AvailableSpace>) -> Size<f32> + Send + 'static) -> CanvasNode {
+        self.measure = Some(Box::new(measure));
+        self
+    }
+}
+
+impl Debug for CanvasNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CanvasNode")
+            .field("style", &self.style)
+            .field("measure", &self.measure.is_some())
+            .finish()
+    }
+}
+
+impl Node for CanvasNode {
+    fn style(&self) -> &Style {
+        &self.style
+    }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
+    fn children(&self) -> Option<&NodeChildren> {
+        None
+    }
+
+    fn render_pre_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        context.canvas.save();
+        (self.draw)(context, layout);
+        context.canvas.restore();
+    }
+
+    fn measure(&mut self, context: &mut MeasureContext, known_dimensions: Size<Option<f32>>, available_space: Size<AvailableSpace>) -> Size<f32> {
+        match &mut self.measure {
+            Some(measure) => measure(context, known_dimensions, available_space),
+            None => Size::ZERO
+        }
+    }
+
+    fn event_handlers(&self) -> Option<crate::events::handler::InnerEventHandlerDataset> {
+        Some(self.events.handlers.clone())
+    }
+    fn set_parent(&mut self, parent: Option<WeakNode>) {
+        self.parent = parent;
+    }
+    fn parent(&self) -> Option<SharedNode> {
+        match &self.parent {
+            Some(parent) => parent.upgrade(),
+            None => None
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::fmt::{Debug, Formatter};
+use cosmic_text::Metrics;
+use femtovg::{LineCap, LineJoin, Path};
+use taffy::{AlignItems, Dimension, Layout, LengthPercentage, LengthPercentageAuto, Rect, Size};
+use crate::{nodes::{Node, NodeChildren, Style, TaffyStyle, ToShared}, events::handler::EventHandlerDatabase, WeakNode, SharedNode};
+use crate::events::{ControlValue, InnerEvent, NodeEvent};
+use crate::nodes::interaction::Activation;
+use crate::nodes::primitives::{draw_control_background, draw_focus_ring};
+use crate::nodes::text::Text;
+use crate::nodes::theme::{StyleTokens, ThemePaint};
+use crate::nodes::{BorderRadius, RenderContext};
+pub use crate::events::CheckState;
+
+/// Size of the box of checkboxes and the circle of radio buttons, in pixels
+pub const INDICATOR_SIZE: f32 = 18.;
+/// Space between the indicator of a control and its label, in pixels
+pub const LABEL_GAP: f32 = 8.;
+/// Width of the outline of unchecked indicators, in pixels
+const BORDER_WIDTH: f32 = 1.5;
+
+/// Paints of checkboxes, radio buttons and switches. The defaults use the theme tokens.
+#[derive(Clone, Debug)]
+pub struct ToggleColors {
+    /// Fill of the indicator when checked. Default is `accent`
+    pub checked: ThemePaint,
+    /// Fill of the indicator when unchecked. Default is `surface`
+    pub unchecked: ThemePaint,
+    /// Outline of the indicator when unchecked. Default is `border`, the checked paint is used while hovered
+    pub border: ThemePaint,
+    /// Check mark, radio dot and switch thumb. Default is `on-accent`
+    pub mark: ThemePaint
+}
+
+impl Default for ToggleColors {
+    fn default() -> Self {
+        ToggleColors {
+            checked: "accent".into(),
+            unchecked: "surface".into(),
+            border: "border".into(),
+            mark: "on-accent".into()
+        }
+    }
+}
+
+/// Style of controls drawing an indicator of the width on the left, followed by the label (their children).
+pub(crate) fn indicator_style(indicator_width: f32) -> Style {
+    Style {
+        layout: TaffyStyle {
+            align_items: Some(AlignItems::Center),
+            padding: Rect {
+                left: LengthPercentage::Length(indicator_width),
+                right: LengthPercentage::Length(0.),
+                top: LengthPercentage::Length(0.),
+                bottom: LengthPercentage::Length(0.)
+            },
+            min_size: Size { width: Dimension::Length(indicator_width), height: Dimension::Length(INDICATOR_SIZE) },
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Text placed after the indicator of a control.
+pub(crate) fn label(label: String) -> SharedNode {
+    Text::new(label, Metrics::new(16., 20.))
+        .style(Style {
+            layout: TaffyStyle {
+                margin: Rect {
+                    left: LengthPercentageAuto::Length(LABEL_GAP),
+                    right: LengthPercentageAuto::Length(0.),
+                    top: LengthPercentageAuto::Length(0.),
+                    bottom: LengthPercentageAuto::Length(0.)
+                },
+                ..Default::default()
+            },
+            tokens: StyleTokens { text_fill: Some("text"), typography: Some("body"), ..Default::default() },
+            ..Default::default()
+        })
+        .to_shared()
+}
+
+/// Translates the canvas to the top left corner of an indicator of the size, vertically centered in the node.
+/// Call between `canvas.save()` and `canvas.restore()`.
+pub(crate) fn translate_to_indicator(indicator_size: Size<f32>, layout: &Layout, context: &mut RenderContext) {
+    context.canvas.translate(0., ((layout.size.height - indicator_size.height) / 2.).max(0.));
+}
+
+/// Checkbox with an optional label (its children). Clicking it or pressing Space while it's focused toggles it
+/// and sends [`InnerEvent::Change`] with [`ControlValue::Check`].
+pub struct Checkbox {
+    pub style: Style,
+    pub children: NodeChildren,
+    pub events: EventHandlerDatabase,
+    pub parent: Option<WeakNode>,
+    pub state: CheckState,
+    pub colors: ToggleColors,
+    activation: Activation
+}
+
+impl Default for Checkbox {
+    fn default() -> Self {
+        Checkbox::new(CheckState::Unchecked)
+    }
+}
+
+impl Checkbox {
+    pub fn new(state: CheckState) -> Checkbox {
+        Checkbox {
+            style: indicator_style(INDICATOR_SIZE),
+            children: NodeChildren::default(),
+            events: EventHandlerDatabase::default(),
+            parent: None,
+            state,
+            colors: ToggleColors::default(),
+            activation: Activation::default()
+        }
+    }
+
+    /// Checkbox followed by a text label, colored with the `text` token.
+    pub fn with_label(state: CheckState, text: impl Into<String>) -> Checkbox {
+        let mut checkbox = Checkbox::new(state);
+        checkbox.children.push(label(text.into()));
+        checkbox
+    }
+
+    pub fn style(mut self, style: Style) -> Checkbox {
+        self.style = style;
+        self
+    }
+
+    pub fn colors(mut self, colors: ToggleColors) -> Checkbox {
+        self.colors = colors;
+        self
+    }
+}
+
+impl Debug for Checkbox {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Checkbox")
+            .field("style", &self.style)
+            .field("state", &self.state)
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+impl Node for Checkbox {
+    fn style(&self) -> &Style {
+        &self.style
+    }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
+    fn children(&self) -> Option<&NodeChildren> {
+        Some(&self.children)
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: &NodeEvent, context: &mut RenderContext) {
+        if self.activation.handle(event, context, self.style.disabled, false) {
+            self.state = match self.state {
+                CheckState::Checked => CheckState::Unchecked,
+                CheckState::Unchecked | CheckState::Indeterminate => CheckState::Checked
+            };
+            context.emit(InnerEvent::Change(ControlValue::Check(self.state)));
+            context.request_frame();
+        }
+    }
+
+    fn render_pre_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        let style = self.activation.resolve_style(&self.style, context);
+        draw_control_background(&style, layout.size, context);
+        let state = self.activation.state(context);
+        let size = Size { width: INDICATOR_SIZE, height: INDICATOR_SIZE };
+        let radius = BorderRadius::all(context.theme.get_radius("sm").unwrap_or(4.));
+        context.canvas.save();
+        translate_to_indicator(size, &layout, context);
+        if self.state == CheckState::Unchecked {
+            context.canvas.fill_path(&radius.to_path(size), &self.colors.unchecked.paint(&context.theme));
+            let mut outline = Path::new();
+            radius.add_to_path(&mut outline, BORDER_WIDTH / 2., BORDER_WIDTH / 2., size.width - BORDER_WIDTH, size.height - BORDER_WIDTH);
+            let border = if state.hovered { &self.colors.checked } else { &self.colors.border };
+            context.canvas.stroke_path(&outline, &border.paint(&context.theme).with_line_width(BORDER_WIDTH));
+        } else {
+            context.canvas.fill_path(&radius.to_path(size), &self.colors.checked.paint(&context.theme));
+            let mut mark = Path::new();
+            if self.state == CheckState::Checked {
+                mark.move_to(size.width * 0.25, size.height * 0.5);
+                mark.line_to(size.width * 0.42, size.height * 0.67);
+                mark.line_to(size.width * 0.75, size.height * 0.33);
+            } else {
+                mark.move_to(size.width * 0.28, size.height * 0.5);
+                mark.line_to(size.width * 0.72, size.height * 0.5);
+            }
+            let paint = self.colors.mark.paint(&context.theme)
+                .with_line_width(2.)
+                .with_line_cap(LineCap::Round)
+                .with_line_join(LineJoin::Round);
+            context.canvas.stroke_path(&mark, &paint);
+        }
+        if state.focused {
+            draw_focus_ring(&radius, size, context);
+        }
+        context.canvas.restore();
+    }
+
+    fn add_child_at(&mut self, child: SharedNode, index: usize) -> Result<(), super::ChildAddError> {
+        let mut index = index;
+        if let Some(i) = self.has_child(&child) {
+            self.children.remove(i);
+            if i < index {
+                index -= 1;
+            }
+        }
+        self.children.insert(index, child);
+        Ok(())
+    }
+
+    fn event_handlers(&self) -> Option<crate::events::handler::InnerEventHandlerDataset> {
+        Some(self.events.handlers.clone())
+    }
+    fn set_parent(&mut self, parent: Option<WeakNode>) {
+        self.parent = parent;
+    }
+    fn parent(&self) -> Option<SharedNode> {
+        match &self.parent {
+            Some(parent) => parent.upgrade(),
+            None => None
+        }
+    }
+    fn remove_child(&mut self, child: &SharedNode) -> Result<(), super::ChildAddError> {
+        if let Some(i) = self.has_child(child) {
+            self.children.remove(i);
+        }
+        Ok(())
+    }
+}
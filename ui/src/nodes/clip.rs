@@ -0,0 +1,147 @@
+use femtovg::{Color, CompositeOperation, FillRule, ImageFlags, ImageId, Paint, Path, PixelFormat, RenderTarget};
+use taffy::{Overflow, Rect, Size};
+use crate::nodes::{BorderRadius, CanvasRenderer, RenderContext, Style};
+
+/// Shape the node (including its children) is clipped to, like CSS `clip-path`.
+/// Parts of the node outside the shape are neither drawn nor hit by the mouse.
+#[derive(Clone, Debug)]
+pub enum ClipPath {
+    /// Ellipse filling the node, a circle for square nodes
+    Ellipse,
+    /// Rectangle inset from the edges of the node, with rounded corners (insets, radius)
+    Inset(Rect<f32>, BorderRadius),
+    /// Path in the coordinates of the node (with 0, 0 at its top left corner)
+    Path(Path)
+}
+
+impl ClipPath {
+    /// Returns the clip path for a node of the size.
+    pub fn to_path(&self, size: Size<f32>) -> Path {
+        match self {
+            ClipPath::Ellipse => {
+                let mut path = Path::new();
+                path.ellipse(size.width / 2., size.height / 2., size.width / 2., size.height / 2.);
+                path
+            },
+            ClipPath::Inset(inset, radius) => {
+                let mut path = Path::new();
+                radius.add_to_path(
+                    &mut path,
+                    inset.left,
+                    inset.top,
+                    (size.width - inset.left - inset.right).max(0.),
+                    (size.height - inset.top - inset.bottom).max(0.)
+                );
+                path
+            },
+            ClipPath::Path(path) => path.clone()
+        }
+    }
+}
+
+/// Returns true if content overflowing the node on the axis is hidden.
+pub(crate) fn clips_overflow(overflow: Overflow) -> bool {
+    matches!(overflow, Overflow::Hidden | Overflow::Clip | Overflow::Scroll)
+}
+
+/// Returns the path children of the node are clipped to, if they can't be clipped with a scissor rectangle
+/// (the node has rounded corners and hides its overflow on both axes).
+pub(crate) fn overflow_clip_path(style: &Style, size: Size<f32>) -> Option<Path> {
+    if style.border_radius.is_zero() || !clips_overflow(style.layout.overflow.x) || !clips_overflow(style.layout.overflow.y) {
+        return None;
+    }
+    Some(style.border_radius.to_path(size))
+}
+
+/// Returns true if the point (relative to the node) is inside the path.
+/// Expects the canvas to have no transform, which is the case outside of rendering.
+pub(crate) fn path_contains(canvas: &CanvasRenderer, path: &Path, x: f32, y: f32) -> bool {
+    canvas.contains_point(path, x, y, FillRule::NonZero)
+}
+
+/// Offscreen images used for clipping to paths. Each level of nested clipping needs its own pair of images
+/// (content and mask), which are reused between nodes and frames.
+#[derive(Default, Debug)]
+pub(crate) struct ClipLayers {
+    layers: Vec<(ImageId, ImageId)>,
+    size: (usize, usize),
+    depth: usize
+}
+
+impl ClipLayers {
+    fn target(&self) -> RenderTarget {
+        match self.depth {
+            0 => RenderTarget::Screen,
+            depth => RenderTarget::Image(self.layers[depth - 1].0)
+        }
+    }
+}
+
+/// Redirects rendering to an offscreen layer until [`pop_clip_layer`] is called. Returns false if the layer
+/// couldn't be created, in which case the content is rendered unclipped and [`pop_clip_layer`] must not be called.
+pub(crate) fn push_clip_layer(context: &mut RenderContext) -> bool {
+    let size = (context.canvas.width() as usize, context.canvas.height() as usize);
+    if size.0 == 0 || size.1 == 0 {
+        return false;
+    }
+    let layers = &mut context.clip_layers;
+    if layers.size != size {
+        // the window was resized, all layers need to be recreated
+        for (content, mask) in layers.layers.drain(..) {
+            context.canvas.delete_image(content);
+            context.canvas.delete_image(mask);
+        }
+        layers.size = size;
+    }
+    if layers.layers.len() <= layers.depth {
+        // rendered images are upside down and have premultiplied alpha
+        let flags = ImageFlags::FLIP_Y | ImageFlags::PREMULTIPLIED;
+        let Ok(content) = context.canvas.create_image_empty(size.0, size.1, PixelFormat::Rgba8, flags) else { return false; };
+        let Ok(mask) = context.canvas.create_image_empty(size.0, size.1, PixelFormat::Rgba8, flags) else {
+            context.canvas.delete_image(content);
+            return false;
+        };
+        layers.layers.push((content, mask));
+    }
+    let (content, _) = layers.layers[layers.depth];
+    layers.depth += 1;
+    context.canvas.set_render_target(RenderTarget::Image(content));
+    context.canvas.clear_rect(0, 0, size.0 as u32, size.1 as u32, Color::rgba(0, 0, 0, 0));
+    true
+}
+
+/// Draws the content rendered since [`push_clip_layer`] into the previous render target, clipped to the path.
+/// The canvas needs to have the same transform as when the layer was pushed.
+pub(crate) fn pop_clip_layer(context: &mut RenderContext, path: &Path, size: Size<f32>) {
+    let layers = &mut context.clip_layers;
+    layers.depth -= 1;
+    let (content, mask) = layers.layers[layers.depth];
+    let layer_size = layers.size;
+    let (width, height) = (layer_size.0 as f32, layer_size.1 as f32);
+    let parent = layers.target();
+    // only the area covered by the node needs to be copied
+    let bounds = context.window_bounds(size);
+    let mut area = Path::new();
+    area.rect(
+        bounds[0].max(0.),
+        bounds[1].max(0.),
+        (bounds[2].min(width) - bounds[0].max(0.)).max(0.),
+        (bounds[3].min(height) - bounds[1].max(0.)).max(0.)
+    );
+
+    // the mask is the (antialiased) clip path, the content is then kept only where the mask is drawn
+    context.canvas.set_render_target(RenderTarget::Image(mask));
+    context.canvas.clear_rect(0, 0, layer_size.0 as u32, layer_size.1 as u32, Color::rgba(0, 0, 0, 0));
+    context.canvas.fill_path(path, &Paint::color(Color::white()));
+    context.canvas.save();
+    context.canvas.reset_transform();
+    context.canvas.global_composite_operation(CompositeOperation::SourceIn);
+    context.canvas.fill_path(&area, &Paint::image(content, 0., 0., width, height, 0., 1.));
+    context.canvas.restore();
+
+    context.canvas.set_render_target(parent);
+    context.canvas.save();
+    context.canvas.reset_transform();
+    context.canvas.fill_path(&area, &Paint::image(mask, 0., 0., width, height, 0., 1.));
+    context.canvas.restore();
+}
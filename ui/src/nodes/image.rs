@@ -1,40 +1,285 @@
-use std::{fmt::Debug, mem, path::PathBuf};
-use std::sync::Mutex;
+use std::{fmt::Debug, io::Cursor, mem, path::PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
 use femtovg::{Color, ErrorKind, ImageFlags, ImageId, Paint, Path};
+use femtovg::imgref::ImgVec;
+use femtovg::rgb::{FromSlice, RGBA8};
+use image::{AnimationDecoder, ImageFormat, RgbaImage};
+use image::codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder};
 use taffy::{AvailableSpace, Size};
-use crate::{events::handler::EventHandlerDatabase, SharedNode, WeakNode};
-use super::{MeasureContext, Node, NodeChildren, RenderContext, Style};
+use crate::{events::{handler::EventHandlerDatabase, InnerEvent}, SharedNode, WeakNode};
+use super::{CanvasRenderer, MeasureContext, Node, NodeChildren, ObjectFit, RenderContext, Style};
+use super::image_cache::{CachedImage, CachedStatus, IMAGE_CACHE};
+
+/// Decoded pixels of an image, ready to be uploaded to the GPU.
+pub type DecodedImage = ImgVec<RGBA8>;
+
+/// Decoded frame of an animated image (GIF, APNG or animated WebP).
+#[derive(Debug, Clone)]
+pub struct AnimationFrame {
+    pub image: DecodedImage,
+    /// How long the frame is shown for
+    pub delay: Duration
+}
+
+/// Uploaded frame of an image. Still images have a single frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageFrame {
+    pub id: ImageId,
+    pub delay: Duration
+}
 
 #[derive(Debug, Default)]
 /// Status of the image - when rendering, image node attempts to load the image and sets this status accordingly.
 /// Changes this if you want to change the image. If the previous status was loaded, free the image.
 /// In case the loading fails, image load status changes to Error and the node doesn't render.
+///
+/// Files and buffers are decoded on a worker thread (the status changes to [`ImageLoad::Loading`] in the meantime)
+/// and uploaded in the next frame. To decode the image yourself (for example in an async task), use [`ImageLoad::pending`].
+///
+/// Files are shared through the [`IMAGE_CACHE`], so nodes showing the same file use a single texture.
+/// Use [`ImageLoad::Cached`] with [`crate::nodes::image_cache::ImageCache::get`] to share images from other sources.
 pub enum ImageLoad {
+    /// Loaded through the [`IMAGE_CACHE`], the status changes to [`ImageLoad::Cached`]
     LoadFile(PathBuf, ImageFlags),
     // LoadArray(&[u8]),
     LoadVec(Vec<u8>, ImageFlags),
+    /// Waiting for the image to be decoded
+    Loading(PendingImage),
     Loaded(ImageHandle),
+    /// Image owned by the [`IMAGE_CACHE`]
+    Cached(CachedImage),
     Error(ErrorKind),
     #[default]
     Empty
 }
 
+impl ImageLoad {
+    /// Creates an image which is loaded through the returned [`ImageSender`].
+    ///
+    /// ```rust
+    /// let (image, sender) = ImageLoad::pending(ImageFlags::empty());
+    /// tokio::spawn(async move {
+    ///     let bytes = download().await;
+    ///     sender.send_encoded(&bytes);
+    /// });
+    /// ```
+    pub fn pending(flags: ImageFlags) -> (ImageLoad, ImageSender) {
+        let (sender, receiver) = image_channel();
+        (ImageLoad::Loading(PendingImage { receiver, flags }), sender)
+    }
+}
+
+pub(crate) fn image_channel() -> (ImageSender, Receiver<Result<Vec<AnimationFrame>, String>>) {
+    let (sender, receiver) = channel();
+    (ImageSender { sender }, receiver)
+}
+
+/// Image being decoded, see [`ImageLoad::pending`].
+#[derive(Debug)]
+pub struct PendingImage {
+    receiver: Receiver<Result<Vec<AnimationFrame>, String>>,
+    flags: ImageFlags
+}
+
+/// Sends a decoded image to the node, see [`ImageLoad::pending`].
+/// Dropping the sender without sending anything makes the image fail to load.
+#[derive(Debug)]
+pub struct ImageSender {
+    sender: Sender<Result<Vec<AnimationFrame>, String>>
+}
+
+impl ImageSender {
+    /// Sends decoded pixels (or an error message) and schedules a frame to show them.
+    pub fn send(self, image: Result<DecodedImage, String>) {
+        self.send_frames(image.map(|image| vec![AnimationFrame { image, delay: Duration::ZERO }]));
+    }
+
+    /// Sends the decoded frames of an animated image (or an error message) and schedules a frame to show them.
+    pub fn send_frames(self, frames: Result<Vec<AnimationFrame>, String>) {
+        let frames = frames.and_then(|frames| match frames.is_empty() {
+            true => Err("Image has no frames".to_owned()),
+            false => Ok(frames)
+        });
+        // the node was dropped if this fails, nothing to do then
+        let _ = self.sender.send(frames);
+        crate::request_frame();
+    }
+
+    /// Decodes an encoded image (png, jpeg, gif...) on the current thread and sends it.
+    /// Animated images keep all their frames.
+    pub fn send_encoded(self, data: &[u8]) {
+        self.send_frames(decode_frames(data));
+    }
+}
+
+type DecodeJob = Box<dyn FnOnce() + Send>;
+
+lazy_static::lazy_static! {
+    /// Queue of the decoding workers, see [`decode_in_background`]
+    static ref DECODE_QUEUE: Sender<DecodeJob> = start_decode_workers();
+}
+
+/// Starts one worker per available core, taking jobs from a shared queue.
+fn start_decode_workers() -> Sender<DecodeJob> {
+    let (sender, receiver) = channel::<DecodeJob>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    let workers = std::thread::available_parallelism().map_or(2, |count| count.get());
+    for _ in 0..workers {
+        let receiver = receiver.clone();
+        std::thread::spawn(move || {
+            loop {
+                // the queue is unlocked before the job runs, so that other workers can take the next one
+                let job = receiver.lock().unwrap().recv();
+                let Ok(job) = job else { break; };
+                // a job which panics drops its sender, failing its image without killing the worker
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(job));
+            }
+        });
+    }
+    sender
+}
+
+/// Runs the job on a pool of worker threads (one per core), which images are loaded and decoded on.
+/// Jobs wait in a queue while all workers are busy, so that only a few full-size images are decoded at a time.
+/// Send the result through an [`ImageSender`].
+pub fn decode_in_background(job: impl FnOnce() + Send + 'static) {
+    // the workers never stop, so the queue stays open
+    let _ = DECODE_QUEUE.send(Box::new(job));
+}
+
+fn to_decoded(image: RgbaImage) -> DecodedImage {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    ImgVec::new(image.as_raw().as_rgba().to_vec(), width, height)
+}
+
+/// Decodes an encoded image (png, jpeg...) to pixels. Only the first frame of animated images is decoded.
+pub fn decode_image(data: &[u8]) -> Result<DecodedImage, String> {
+    let image = image::load_from_memory(data).map_err(|e| e.to_string())?.to_rgba8();
+    Ok(to_decoded(image))
+}
+
+/// Decodes all frames of an animated image (GIF, APNG or animated WebP).
+/// Other images are decoded to a single frame.
+pub fn decode_frames(data: &[u8]) -> Result<Vec<AnimationFrame>, String> {
+    let error = |e: image::ImageError| e.to_string();
+    let cursor = Cursor::new(data);
+    let frames = match image::guess_format(data) {
+        Ok(ImageFormat::Gif) => GifDecoder::new(cursor).map_err(error)?.into_frames(),
+        Ok(ImageFormat::Png) => {
+            let decoder = PngDecoder::new(cursor).map_err(error)?;
+            if !decoder.is_apng().map_err(error)? {
+                return decode_image(data).map(|image| vec![AnimationFrame { image, delay: Duration::ZERO }]);
+            }
+            decoder.apng().map_err(error)?.into_frames()
+        },
+        Ok(ImageFormat::WebP) => {
+            let decoder = WebPDecoder::new(cursor).map_err(error)?;
+            if !decoder.has_animation() {
+                return decode_image(data).map(|image| vec![AnimationFrame { image, delay: Duration::ZERO }]);
+            }
+            decoder.into_frames()
+        },
+        _ => return decode_image(data).map(|image| vec![AnimationFrame { image, delay: Duration::ZERO }])
+    };
+    Ok(frames.collect_frames().map_err(error)?.into_iter().map(|frame| {
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        AnimationFrame {
+            delay: Duration::from_secs_f64(numer as f64 / denom.max(1) as f64 / 1000.),
+            image: to_decoded(frame.into_buffer())
+        }
+    }).collect())
+}
+
+/// Uploads all frames to the GPU. Frames uploaded before an error are freed.
+pub(crate) fn upload_frames(canvas: &mut CanvasRenderer, frames: Vec<AnimationFrame>, flags: ImageFlags) -> Result<Arc<[ImageFrame]>, ErrorKind> {
+    let mut uploaded = Vec::with_capacity(frames.len());
+    for frame in frames {
+        match canvas.create_image(frame.image.as_ref(), flags) {
+            Ok(id) => uploaded.push(ImageFrame { id, delay: frame.delay }),
+            Err(e) => {
+                for frame in uploaded {
+                    canvas.delete_image(frame.id);
+                }
+                return Err(e);
+            }
+        }
+    }
+    Ok(uploaded.into())
+}
+
 #[derive(Debug)]
 pub struct ImageHandle {
-    image: ImageId
+    frames: Arc<[ImageFrame]>
 }
 
 impl ImageHandle {
-    fn new(image: ImageId) -> ImageHandle {
+    fn new(frames: Arc<[ImageFrame]>) -> ImageHandle {
         ImageHandle {
-            image
+            frames
         }
     }
 }
 
 impl Drop for ImageHandle {
     fn drop(&mut self) {
-        IMAGES_TO_UNLOAD.lock().unwrap().push(self.image);
+        IMAGES_TO_UNLOAD.lock().unwrap().extend(self.frames.iter().map(|frame| frame.id));
+    }
+}
+
+/// Playback state of an animated image
+#[derive(Debug, Clone)]
+pub struct Playback {
+    /// Whether the animation advances. Default is true
+    pub playing: bool,
+    /// Whether the animation starts over after the last frame, otherwise it stops there. Default is true
+    pub looping: bool,
+    /// Index of the shown frame
+    pub frame: usize,
+    /// How long the current frame was shown for
+    pub elapsed: Duration
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Playback {
+            playing: true,
+            looping: true,
+            frame: 0,
+            elapsed: Duration::ZERO
+        }
+    }
+}
+
+impl Playback {
+    /// Moves the animation forward by the time, returns the time left until the next frame (if it's playing).
+    fn advance(&mut self, frames: &[ImageFrame], time: Duration) -> Option<Duration> {
+        if frames.len() < 2 {
+            return None;
+        }
+        self.frame %= frames.len();
+        if !self.playing {
+            return None;
+        }
+        // like browsers, very short delays are treated as 100 ms
+        let delay = |frame: &ImageFrame| if frame.delay <= Duration::from_millis(10) { Duration::from_millis(100) } else { frame.delay };
+        let total: Duration = frames.iter().map(delay).sum();
+        // don't go through all the frames after a long time without rendering
+        self.elapsed = (self.elapsed + time).min(total);
+        while self.elapsed >= delay(&frames[self.frame]) {
+            self.elapsed -= delay(&frames[self.frame]);
+            if self.frame + 1 < frames.len() {
+                self.frame += 1;
+            } else if self.looping {
+                self.frame = 0;
+            } else {
+                self.playing = false;
+                self.elapsed = Duration::ZERO;
+                return None;
+            }
+        }
+        Some(delay(&frames[self.frame]) - self.elapsed)
     }
 }
 
@@ -46,7 +291,11 @@ pub struct Image {
     /// The image to be rendered.
     pub image: ImageLoad,
     pub events: EventHandlerDatabase,
-    pub parent: Option<WeakNode>
+    pub parent: Option<WeakNode>,
+    /// Shown while the image is loading. Defaults to the background
+    pub placeholder: Option<Paint>,
+    /// Playback of animated images. Animations only advance while the node is visible
+    pub playback: Playback
 }
 
 impl Image {
@@ -60,50 +309,140 @@ impl Image {
         self.style = style;
         self
     }
+
+    /// Resumes the animation, starting over if it stopped at the last frame.
+    pub fn play(&mut self) {
+        if !self.playback.playing && !self.playback.looping && self.frame_count() > 0 && self.playback.frame + 1 >= self.frame_count() {
+            self.playback.frame = 0;
+        }
+        self.playback.playing = true;
+        crate::request_frame();
+    }
+
+    pub fn pause(&mut self) {
+        self.playback.playing = false;
+    }
+
+    /// Number of frames of the loaded image, zero while it's loading.
+    pub fn frame_count(&self) -> usize {
+        self.frames().map_or(0, |frames| frames.len())
+    }
 }
 
 lazy_static::lazy_static! {
     pub static ref IMAGES_TO_UNLOAD: Mutex<Vec<ImageId>> = Mutex::new(Vec::new());
 }
 
+/// Frees images of dropped [`ImageHandle`]s. Called at the end of each frame.
+pub(crate) fn unload_images(canvas: &mut CanvasRenderer) {
+    for image in IMAGES_TO_UNLOAD.lock().unwrap().drain(..) {
+        canvas.delete_image(image);
+    }
+}
+
+impl Image {
+    /// Returns the frames of the image, if it's loaded.
+    fn frames(&self) -> Option<Arc<[ImageFrame]>> {
+        match &self.image {
+            ImageLoad::Loaded(image) => Some(image.frames.clone()),
+            ImageLoad::Cached(image) => match IMAGE_CACHE.lock().unwrap().status(image) {
+                CachedStatus::Ready(frames) => Some(frames),
+                _ => None
+            },
+            _ => None
+        }
+    }
+
+    fn draw_placeholder(&self, style: &Style, context: &mut RenderContext, size: Size<f32>, path: &Path) {
+        match (&self.placeholder, &style.background) {
+            (Some(placeholder), _) => context.canvas.fill_path(path, placeholder),
+            (None, Some(background)) => background.draw(context, size, path),
+            (None, None) => {}
+        }
+    }
+
+    /// Returns the size of the image in pixels, if it's loaded.
+    fn image_size(&self, canvas: &CanvasRenderer) -> Option<Size<f32>> {
+        match &self.image {
+            ImageLoad::Loaded(image) => canvas.image_size(image.frames[0].id).ok()
+                .map(|(width, height)| Size { width: width as f32, height: height as f32 }),
+            ImageLoad::Cached(image) => IMAGE_CACHE.lock().unwrap().size(image),
+            _ => None
+        }
+    }
+}
+
 impl Node for Image {
     fn style(&self) -> &Style {
         &self.style
     }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
 
     fn children(&self) -> Option<&NodeChildren> {
         None
     }
 
     fn prepare_render(&mut self, context: &mut RenderContext) {
-        if let Ok(mut images_to_unload) = IMAGES_TO_UNLOAD.try_lock() {
-            for image in images_to_unload.iter() {
-                context.canvas.delete_image(*image);
-            }
-            images_to_unload.clear();
-        }
-        match &self.image {
-            ImageLoad::LoadFile(_, _) => {
-                let image = mem::replace(&mut self.image, ImageLoad::Error(ErrorKind::UnknownError));
-                if let ImageLoad::LoadFile(path, flags) = image {
-                    match context.canvas.load_image_file(path, flags) {
-                        Ok(image) => {
-                            self.image = ImageLoad::Loaded(ImageHandle::new(image));
-                        },
-                        Err(e) => {
-                            self.image = ImageLoad::Error(e);
-                        }
-                    }
+        match &mut self.image {
+            ImageLoad::LoadFile(path, flags) => {
+                let image = IMAGE_CACHE.lock().unwrap().get_file(mem::take(path), *flags);
+                self.image = ImageLoad::Cached(image);
+                // the image may be loaded already
+                self.prepare_render(context);
+            },
+            ImageLoad::Cached(image) => {
+                if image.notified {
+                    return;
+                }
+                match IMAGE_CACHE.lock().unwrap().status(image) {
+                    CachedStatus::Loading | CachedStatus::Evicted => return,
+                    CachedStatus::Ready(_) => context.emit(InnerEvent::Load),
+                    CachedStatus::Error(message) => context.emit(InnerEvent::LoadError(message))
                 }
+                image.notified = true;
+                context.request_relayout();
             },
-            ImageLoad::LoadVec(data, flags) => {
-                match context.canvas.load_image_mem(data, *flags) {
-                    Ok(image) => {
-                        self.image = ImageLoad::Loaded(ImageHandle::new(image));
-                    },
-                    Err(e) => {
-                        self.image = ImageLoad::Error(e);
+            ImageLoad::LoadVec(_, _) => {
+                let image = mem::replace(&mut self.image, ImageLoad::Empty);
+                let (loading, sender) = match &image {
+                    ImageLoad::LoadVec(_, flags) => ImageLoad::pending(*flags),
+                    _ => unreachable!()
+                };
+                self.image = loading;
+                decode_in_background(move || {
+                    if let ImageLoad::LoadVec(data, _) = image {
+                        sender.send_encoded(&data);
                     }
+                });
+            },
+            ImageLoad::Loading(pending) => {
+                match pending.receiver.try_recv() {
+                    Ok(Ok(decoded)) => {
+                        match upload_frames(&mut context.canvas, decoded, pending.flags) {
+                            Ok(frames) => {
+                                self.image = ImageLoad::Loaded(ImageHandle::new(frames));
+                                context.emit(InnerEvent::Load);
+                            },
+                            Err(e) => {
+                                context.emit(InnerEvent::LoadError(e.to_string()));
+                                self.image = ImageLoad::Error(e);
+                            }
+                        }
+                        // size of the node most likely changed
+                        context.request_relayout();
+                    },
+                    Ok(Err(message)) => {
+                        context.emit(InnerEvent::LoadError(message.clone()));
+                        self.image = ImageLoad::Error(ErrorKind::GeneralError(message));
+                    },
+                    Err(TryRecvError::Disconnected) => {
+                        let message = "Image sender was dropped".to_owned();
+                        context.emit(InnerEvent::LoadError(message.clone()));
+                        self.image = ImageLoad::Error(ErrorKind::GeneralError(message));
+                    },
+                    Err(TryRecvError::Empty) => {}
                 }
             },
             _ => {}
@@ -111,27 +450,52 @@ impl Node for Image {
     }
 
     fn render_pre_children(&mut self, context: &mut super::RenderContext, layout: taffy::prelude::Layout) {
-        let mut path = Path::new();
-        path.rounded_rect(
-            0.,
-            0.,
-            layout.size.width,
-            layout.size.height,
-            self.style.border_radius
-        );
+        let style = context.resolve_style(&self.style);
+        let path = style.border_radius.to_path(layout.size);
+        if let ImageLoad::Cached(image) = &self.image {
+            if let CachedStatus::Error(_) = IMAGE_CACHE.lock().unwrap().status(image) {
+                context.canvas.fill_path(&path, &Paint::color(Color::rgb(255, 0, 0)));
+                return;
+            }
+        }
         match &self.image {
-            ImageLoad::Loaded(image) => {
-                if let Some(background) = &self.style.background {
-                    context.canvas.fill_path(&path, background);
+            ImageLoad::Loaded(_) | ImageLoad::Cached(_) => {
+                // images scrolled out of view aren't drawn (nor animated), and can be evicted
+                if !context.is_visible(layout.size) {
+                    return;
+                }
+                if let ImageLoad::Cached(image) = &self.image {
+                    IMAGE_CACHE.lock().unwrap().touch(image);
+                }
+                let Some(frames) = self.frames() else {
+                    // evicted from the cache or still loading
+                    self.draw_placeholder(&style, context, layout.size, &path);
+                    return;
+                };
+                if let Some(background) = &style.background {
+                    background.draw(context, layout.size, &path);
                 }
+                let Some(image_size) = self.image_size(&context.canvas) else { return; };
+                if let Some(next) = self.playback.advance(&frames, context.frame_delta) {
+                    context.request_frame_at(context.frame_time + next);
+                }
+                let image = frames[self.playback.frame % frames.len()].id;
+                let size = style.object_fit.fit(image_size, layout.size);
+                let x = (layout.size.width - size.width) * style.object_position.x;
+                let y = (layout.size.height - size.height) * style.object_position.y;
+                // the image pattern would otherwise smear its edges over the rest of the node
+                context.canvas.save();
+                context.canvas.intersect_scissor(x, y, size.width, size.height);
                 context.canvas.fill_path(
                     &path,
-                    &Paint::image(image.image, 0., 0., layout.size.width, layout.size.height, 0., 1.)
+                    &Paint::image(image, x, y, size.width, size.height, 0., 1.)
                 );
+                context.canvas.restore();
             },
             ImageLoad::Error(_) => {
                 context.canvas.fill_path(&path, &Paint::color(Color::rgb(255, 0, 0)))
             },
+            ImageLoad::Loading(_) => self.draw_placeholder(&style, context, layout.size, &path),
             _ => {
                 // this shouldn't happen as the image should be loaded earlier during the render pass,
                 // but someone can still change the image in another thread
@@ -140,23 +504,22 @@ impl Node for Image {
     }
 
     fn measure(&mut self, context: &mut MeasureContext, known_dimensions: Size<Option<f32>>, _available_space: Size<AvailableSpace>) -> Size<f32> {
-        match &self.image {
-            ImageLoad::Loaded(image) => {
-                match context.canvas.image_size(image.image) {
-                    Ok((img_width, img_height)) => {
-                        let img_width = img_width as f32;
-                        let img_height = img_height as f32;
-                        match (known_dimensions.width, known_dimensions.height) {
-                            (Some(width), Some(height)) => Size { width, height },
-                            (Some(width), None) => Size { width, height: (width / img_width) * img_height },
-                            (None, Some(height)) => Size { width: (height / img_height) * img_width, height },
-                            (None, None) => Size { width: img_width, height: img_height },
-                        }
-                    },
-                    _ => Size::ZERO
-                }
-            },
-            _ => Size::ZERO
+        let Some(Size { width: img_width, height: img_height }) = self.image_size(context.canvas) else {
+            return Size::ZERO;
+        };
+        // with one dimension known, the other one is the size of the fitted image
+        // (which keeps the aspect ratio, unless it's not resized at all)
+        let object_fit = context.resolve_style(&self.style).object_fit;
+        let fit = |size: f32, img_size: f32| match object_fit {
+            ObjectFit::None => img_size,
+            ObjectFit::ScaleDown => size.min(img_size),
+            ObjectFit::Fill | ObjectFit::Contain | ObjectFit::Cover => size
+        };
+        match (known_dimensions.width, known_dimensions.height) {
+            (Some(width), Some(height)) => Size { width, height },
+            (Some(width), None) => Size { width, height: (fit(width, img_width) / img_width) * img_height },
+            (None, Some(height)) => Size { width: (fit(height, img_height) / img_height) * img_width, height },
+            (None, None) => Size { width: img_width, height: img_height },
         }
     }
 
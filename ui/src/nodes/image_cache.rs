@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use femtovg::ImageFlags;
+use taffy::Size;
+use super::CanvasRenderer;
+use super::image::{decode_in_background, image_channel, upload_frames, AnimationFrame, ImageFrame, ImageSender};
+
+/// Default memory budget of the [`IMAGE_CACHE`] in bytes
+const DEFAULT_BUDGET: usize = 256 * 1024 * 1024;
+
+lazy_static::lazy_static! {
+    /// Images shared between all image nodes. See [`ImageCache`].
+    pub static ref IMAGE_CACHE: Mutex<ImageCache> = Mutex::new(ImageCache::default());
+}
+
+/// Identifies the source of an image in the [`ImageCache`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ImageKey {
+    /// Image file, loaded on a worker thread
+    File(PathBuf),
+    /// Any other source, like an URL. Needs a loader, see [`ImageCache::get`]
+    Url(String)
+}
+
+/// Starts loading an image, which is then sent through the [`ImageSender`].
+/// Called while the cache is locked - don't block or use the cache here, use [`decode_in_background`] or a task instead.
+/// May be called again if the image was evicted from the cache.
+pub type ImageLoader = dyn Fn(ImageSender) + Send + Sync;
+
+/// State of an image in the [`ImageCache`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum CachedStatus {
+    Loading,
+    /// Uploaded frames, still images have a single frame
+    Ready(Arc<[ImageFrame]>),
+    /// Texture was freed to stay within the budget, [`ImageCache::touch`] loads it again
+    Evicted,
+    Error(String)
+}
+
+/// Reference to an image in the [`ImageCache`].
+/// The texture is freed at the end of the frame after all references (and their clones) are dropped.
+#[derive(Clone, Debug)]
+pub struct CachedImage {
+    /// The strong count of the key is used as the reference count
+    key: Arc<(ImageKey, u32)>,
+    /// Whether the node holding this reference already sent the load event
+    pub(crate) notified: bool
+}
+
+enum EntryState {
+    Loading(Receiver<Result<Vec<AnimationFrame>, String>>),
+    Uploaded(Arc<[ImageFrame]>),
+    /// Texture was freed to stay within the budget, will be loaded again when needed
+    Evicted,
+    Error(String)
+}
+
+struct CacheEntry {
+    key: Arc<(ImageKey, u32)>,
+    flags: ImageFlags,
+    loader: Arc<ImageLoader>,
+    state: EntryState,
+    /// Size of the image in pixels, known after the first load
+    size: Option<Size<f32>>,
+    /// Frame in which the image was last rendered
+    last_used: u64
+}
+
+impl CacheEntry {
+    fn memory(&self) -> usize {
+        match (&self.state, self.size) {
+            (EntryState::Uploaded(frames), Some(size)) => size.width as usize * size.height as usize * 4 * frames.len(),
+            _ => 0
+        }
+    }
+
+    fn unload(&mut self, canvas: &mut CanvasRenderer) {
+        if let EntryState::Uploaded(frames) = &self.state {
+            for frame in frames.iter() {
+                canvas.delete_image(frame.id);
+            }
+        }
+    }
+
+    fn load(&mut self) {
+        let (sender, receiver) = image_channel();
+        (self.loader)(sender);
+        self.state = EntryState::Loading(receiver);
+    }
+}
+
+/// Deduplicates images by their source and keeps their textures within a memory budget.
+///
+/// Images are reference counted through [`CachedImage`] and freed at the end of the frame once unused.
+/// When the uploaded textures exceed the budget, textures which weren't rendered in the last frame are evicted,
+/// least recently used first. Evicted images are loaded again when they're needed.
+///
+/// [`crate::nodes::image::ImageLoad::LoadFile`] uses this cache automatically.
+pub struct ImageCache {
+    entries: HashMap<(ImageKey, u32), CacheEntry>,
+    /// Memory budget for textures in bytes
+    budget: usize,
+    frame: u64
+}
+
+impl Default for ImageCache {
+    fn default() -> Self {
+        ImageCache {
+            entries: HashMap::new(),
+            budget: DEFAULT_BUDGET,
+            frame: 0
+        }
+    }
+}
+
+impl ImageCache {
+    /// Returns a reference to the image, starting to load it with the loader if it isn't in the cache.
+    pub fn get(&mut self, key: ImageKey, flags: ImageFlags, loader: Arc<ImageLoader>) -> CachedImage {
+        let map_key = (key, flags.bits());
+        if let Some(entry) = self.entries.get(&map_key) {
+            return CachedImage { key: entry.key.clone(), notified: false };
+        }
+        let mut entry = CacheEntry {
+            key: Arc::new(map_key.clone()),
+            flags,
+            loader,
+            state: EntryState::Evicted,
+            size: None,
+            last_used: self.frame
+        };
+        entry.load();
+        let image = CachedImage { key: entry.key.clone(), notified: false };
+        self.entries.insert(map_key, entry);
+        image
+    }
+
+    /// Returns a reference to the image file, loading it on a worker thread if it isn't in the cache.
+    pub fn get_file(&mut self, path: PathBuf, flags: ImageFlags) -> CachedImage {
+        let loader_path = path.clone();
+        self.get(ImageKey::File(path), flags, Arc::new(move |sender: ImageSender| {
+            let path = loader_path.clone();
+            decode_in_background(move || match std::fs::read(&path) {
+                Ok(data) => sender.send_encoded(&data),
+                Err(e) => sender.send(Err(format!("{}: {}", path.display(), e)))
+            });
+        }))
+    }
+
+    /// Returns the state of the image.
+    pub fn status(&self, image: &CachedImage) -> CachedStatus {
+        let Some(entry) = self.entries.get(&*image.key) else {
+            return CachedStatus::Error("Image is not in the cache".to_owned());
+        };
+        match &entry.state {
+            EntryState::Loading(_) => CachedStatus::Loading,
+            EntryState::Uploaded(frames) => CachedStatus::Ready(frames.clone()),
+            EntryState::Error(message) => CachedStatus::Error(message.clone()),
+            EntryState::Evicted => CachedStatus::Evicted
+        }
+    }
+
+    /// Returns the size of the image in pixels, if it was loaded already.
+    pub fn size(&self, image: &CachedImage) -> Option<Size<f32>> {
+        self.entries.get(&*image.key).and_then(|entry| entry.size)
+    }
+
+    /// Marks the image as rendered in the current frame, so that it isn't evicted, and loads it again if it was.
+    /// Only call this for images which are visible, otherwise images over the budget are loaded and evicted on every frame.
+    pub fn touch(&mut self, image: &CachedImage) {
+        if let Some(entry) = self.entries.get_mut(&*image.key) {
+            entry.last_used = self.frame;
+            if let EntryState::Evicted = entry.state {
+                entry.load();
+            }
+        }
+    }
+
+    /// Sets the memory budget for textures in bytes. Default is 256 MiB.
+    /// Images rendered in the last frame are never evicted, so the budget can be exceeded.
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+    }
+
+    /// Returns the memory used by uploaded textures in bytes (approximately).
+    pub fn memory_usage(&self) -> usize {
+        self.entries.values().map(CacheEntry::memory).sum()
+    }
+
+    /// Uploads images decoded since the last frame. Called at the start of each frame.
+    pub(crate) fn upload(&mut self, canvas: &mut CanvasRenderer) {
+        for entry in self.entries.values_mut() {
+            let EntryState::Loading(receiver) = &entry.state else { continue; };
+            entry.state = match receiver.try_recv() {
+                Ok(Ok(frames)) => {
+                    let first = &frames[0].image;
+                    entry.size = Some(Size { width: first.width() as f32, height: first.height() as f32 });
+                    match upload_frames(canvas, frames, entry.flags) {
+                        Ok(frames) => EntryState::Uploaded(frames),
+                        Err(e) => EntryState::Error(e.to_string())
+                    }
+                },
+                Ok(Err(message)) => EntryState::Error(message),
+                Err(TryRecvError::Disconnected) => EntryState::Error("Image sender was dropped".to_owned()),
+                Err(TryRecvError::Empty) => continue
+            };
+        }
+    }
+
+    /// Frees unused images and evicts textures over the budget. Called at the end of each frame.
+    pub(crate) fn end_frame(&mut self, canvas: &mut CanvasRenderer) {
+        self.entries.retain(|_, entry| {
+            // only the cache holds the key
+            if Arc::strong_count(&entry.key) > 1 {
+                return true;
+            }
+            entry.unload(canvas);
+            false
+        });
+
+        let mut usage = self.memory_usage();
+        if usage > self.budget {
+            let mut candidates: Vec<_> = self.entries.values_mut()
+                .filter(|entry| entry.last_used < self.frame && matches!(entry.state, EntryState::Uploaded(_)))
+                .collect();
+            candidates.sort_by_key(|entry| entry.last_used);
+            for entry in candidates {
+                if usage <= self.budget { break; }
+                usage -= entry.memory();
+                entry.unload(canvas);
+                entry.state = EntryState::Evicted;
+            }
+        }
+
+        self.frame += 1;
+    }
+}
@@ -0,0 +1,276 @@
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+use crate::events::{InnerEvent, MouseButton, NamedKey, NodeEvent};
+use crate::nodes::{get_path_to, run_event_handlers, run_single_event_handlers, RenderContext, Style, Visibility};
+use crate::nodes::variant::StyleEnvironment;
+use crate::nodes::scroll_view::{scroll_into_view, ScrollIntoViewOptions};
+use crate::{NodePtr, SharedNode, WeakNode};
+
+lazy_static::lazy_static! {
+    static ref FOCUS_REQUESTS: Mutex<Vec<Option<WeakNode>>> = Mutex::new(Vec::new());
+}
+
+/// Interaction state of a node, maintained by the event loop. Styles react to it with variants,
+/// see [`crate::nodes::variant::StyleCondition::Hovered`] and the others.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct InteractionState {
+    /// The pointer is over the node or one of its children
+    pub hovered: bool,
+    /// The primary button was pressed on the node or one of its children and wasn't released yet
+    pub pressed: bool,
+    /// The node has the keyboard focus
+    pub focused: bool,
+    /// The node is disabled with [`crate::nodes::Style::disabled`]. Disabled nodes are never pressed or focused
+    pub disabled: bool
+}
+
+fn contains(path: &NodePtr, node: *const ()) -> bool {
+    path.as_ref().is_some_and(|path| path.iter().any(|item| std::ptr::addr_eq(item.as_ptr(), node)))
+}
+
+fn is_target(path: &NodePtr, node: *const ()) -> bool {
+    path.as_ref().and_then(|path| path.last()).is_some_and(|item| std::ptr::addr_eq(item.as_ptr(), node))
+}
+
+/// Returns the state of the node (a pointer to it) in the hovered, pressed and focused paths.
+pub(crate) fn state_of(node: *const (), mouse: &NodePtr, pressed: &NodePtr, focus: &NodePtr) -> InteractionState {
+    InteractionState {
+        hovered: contains(mouse, node),
+        pressed: contains(pressed, node),
+        focused: is_target(focus, node),
+        disabled: false
+    }
+}
+
+fn same_path(a: &NodePtr, b: &NodePtr) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.ptr_eq(b)),
+        (None, None) => true,
+        _ => false
+    }
+}
+
+fn to_weak(path: Option<&[SharedNode]>) -> NodePtr {
+    path.map(|path| path.iter().map(Arc::downgrade).collect())
+}
+
+/// Restyles the nodes whose state changed from one path to the other. The layout is recomputed only if they
+/// have variants depending on their state.
+fn restyle(context: &mut RenderContext, previous: &NodePtr, current: &NodePtr) {
+    let changed = previous.iter().chain(current.iter()).flatten()
+        .filter(|node| contains(previous, node.as_ptr() as *const ()) != contains(current, node.as_ptr() as *const ())
+            || is_target(previous, node.as_ptr() as *const ()) != is_target(current, node.as_ptr() as *const ()));
+    for node in changed.filter_map(|node| node.upgrade()) {
+        if node.lock().unwrap().style().variants.iter().any(|variant| variant.condition.depends_on_state()) {
+            context.request_relayout();
+            break;
+        }
+    }
+    context.request_frame();
+}
+
+/// Sets the path of nodes under the pointer.
+pub(crate) fn set_hovered(context: &mut RenderContext, path: Option<&[SharedNode]>) {
+    let hovered = to_weak(path);
+    if same_path(&hovered, &context.mouse) {
+        return;
+    }
+    let previous = std::mem::replace(&mut context.mouse, hovered);
+    let current = context.mouse.clone();
+    restyle(context, &previous, &current);
+}
+
+/// Sets the path of nodes the primary button was pressed on. Disabled nodes are excluded from the path.
+pub(crate) fn set_pressed(context: &mut RenderContext, path: Option<&[SharedNode]>) {
+    let path = path.map(|path| path.iter().filter(|node| !node.lock().unwrap().style().disabled).cloned().collect::<Vec<_>>());
+    let previous = std::mem::replace(&mut context.pressed, to_weak(path.as_deref()));
+    let current = context.pressed.clone();
+    restyle(context, &previous, &current);
+}
+
+/// Moves the keyboard focus to the last node of the path (or removes it), sending blur and focus events.
+pub(crate) fn set_focus(context: &mut RenderContext, path: Option<Vec<SharedNode>>) {
+    let current = to_weak(path.as_deref());
+    if same_path(&current, &context.keyboard_focus) {
+        return;
+    }
+    let previous = std::mem::replace(&mut context.keyboard_focus, current.clone());
+    if let Some(previous_path) = previous.as_ref().and_then(|path| path.iter().map(|node| node.upgrade()).collect::<Option<Vec<_>>>()) {
+        send_focus_events(context, previous_path, InnerEvent::Blur, InnerEvent::FocusOut);
+    }
+    if let Some(path) = path.filter(|path| !path.is_empty()) {
+        send_focus_events(context, path, InnerEvent::Focus, InnerEvent::FocusIn);
+    }
+    restyle(context, &previous, &current);
+}
+
+/// Sends a focus or blur event to the last node of the path, followed by the bubbling variant.
+pub(crate) fn send_focus_events(context: &mut RenderContext, path: Vec<SharedNode>, event: InnerEvent, bubbling: InnerEvent) {
+    let Some(target) = path.last().cloned() else { return; };
+    let focus_event = NodeEvent {
+        target: target.clone(),
+        path: path.clone(),
+        event
+    };
+    run_single_event_handlers(target.clone(), focus_event, context);
+    let focus_event = NodeEvent {
+        target,
+        path: path.clone(),
+        event: bubbling
+    };
+    run_event_handlers(path, focus_event, context);
+}
+
+/// Focuses the innermost focusable node of the path pressed by the mouse, or removes the focus if there's none.
+pub(crate) fn focus_pressed(context: &mut RenderContext, path: &[SharedNode]) {
+    let focusable = path.iter().rposition(|node| {
+        let node = node.lock().unwrap();
+        node.focusable() && !node.style().disabled
+    });
+    set_focus(context, focusable.map(|index| path[..=index].to_vec()));
+}
+
+/// Moves the keyboard focus to the node. Can be called from any thread, the focus moves before the next frame.
+pub fn focus(node: &SharedNode) {
+    FOCUS_REQUESTS.lock().unwrap().push(Some(Arc::downgrade(node)));
+    crate::request_frame();
+}
+
+/// Removes the keyboard focus from the focused node.
+pub fn blur() {
+    FOCUS_REQUESTS.lock().unwrap().push(None);
+    crate::request_frame();
+}
+
+/// Applies focus changes requested by [`focus`] and [`blur`].
+pub(crate) fn apply_focus_requests(context: &mut RenderContext) {
+    let requests = std::mem::take(&mut *FOCUS_REQUESTS.lock().unwrap());
+    for request in requests {
+        match request {
+            Some(node) => {
+                let Some(node) = node.upgrade() else { continue; };
+                if node.lock().unwrap().style().disabled {
+                    continue;
+                }
+                set_focus(context, Some(get_path_to(&node)));
+            },
+            None => set_focus(context, None)
+        }
+    }
+}
+
+/// Adds the paths of the focusable nodes of the subtree to `paths`, in tree order. Disabled and hidden subtrees are skipped.
+fn collect_focusable(path: &mut Vec<SharedNode>, paths: &mut Vec<Vec<SharedNode>>) {
+    let node = path.last().unwrap().clone();
+    let locked = node.lock().unwrap();
+    if locked.style().disabled || locked.style().visibility == Visibility::Hidden {
+        return;
+    }
+    if locked.focusable() {
+        paths.push(path.clone());
+    }
+    let children = locked.children().cloned().unwrap_or_default();
+    drop(locked);
+    for child in children {
+        path.push(child);
+        collect_focusable(path, paths);
+        path.pop();
+    }
+}
+
+/// Moves the keyboard focus to the next (or previous) focusable node in tree order, wrapping around.
+/// Used for Tab and Shift+Tab. The newly focused node is scrolled into view.
+pub(crate) fn focus_next(context: &mut RenderContext, roots: &[SharedNode], backwards: bool) {
+    let mut paths = Vec::new();
+    for root in roots {
+        collect_focusable(&mut vec![root.clone()], &mut paths);
+    }
+    if paths.is_empty() {
+        return;
+    }
+    let current = paths.iter().position(|path| is_target(&context.keyboard_focus, Arc::as_ptr(path.last().unwrap()) as *const ()));
+    let index = match (current, backwards) {
+        (Some(index), false) => (index + 1) % paths.len(),
+        (Some(index), true) => (index + paths.len() - 1) % paths.len(),
+        (None, false) => 0,
+        (None, true) => paths.len() - 1
+    };
+    let path = paths.swap_remove(index);
+    scroll_into_view(path.last().unwrap(), ScrollIntoViewOptions::default());
+    set_focus(context, Some(path));
+}
+
+/// Turns presses of a focusable control by the primary mouse button or the keyboard into activations.
+/// Used by [`crate::nodes::button::Button`] and the other controls activated like it.
+#[derive(Default, Debug)]
+pub(crate) struct Activation {
+    /// The primary mouse button was pressed on the control and wasn't released yet
+    mouse_pressed: bool,
+    /// Space was pressed while the control was focused and wasn't released yet
+    key_pressed: bool
+}
+
+impl Activation {
+    /// Handles an event passing through the control, returns true if the control was activated.
+    /// The control is activated by a click, Space, or Enter if `enter` is set (buttons, but not checkboxes).
+    pub(crate) fn handle(&mut self, event: &NodeEvent, context: &mut RenderContext, disabled: bool, enter: bool) -> bool {
+        if disabled {
+            self.mouse_pressed = false;
+            self.key_pressed = false;
+            return false;
+        }
+        let state = context.style_environment().state;
+        match &event.event {
+            InnerEvent::MouseDown(mouse) if mouse.button == Some(MouseButton::Left) => {
+                if context.is_handled() { return false; }
+                self.mouse_pressed = true;
+                context.capture_pointer();
+                context.mark_handled();
+            },
+            InnerEvent::MouseUp(mouse) if mouse.button == Some(MouseButton::Left) => {
+                if !self.mouse_pressed { return false; }
+                self.mouse_pressed = false;
+                context.release_pointer();
+                context.mark_handled();
+                // releasing the pointer outside of the control cancels the activation
+                return state.hovered;
+            },
+            // key events bubble from the focused node, only handle them when the control itself is focused
+            InnerEvent::KeyDown(key) if state.focused => {
+                if enter && key.is_named(NamedKey::Enter) {
+                    context.mark_handled();
+                    return !key.repeat;
+                } else if key.is_named(NamedKey::Space) {
+                    self.key_pressed = true;
+                    context.request_frame();
+                    context.mark_handled();
+                }
+            },
+            InnerEvent::KeyUp(key) if state.focused && key.is_named(NamedKey::Space) => {
+                context.mark_handled();
+                if self.key_pressed {
+                    self.key_pressed = false;
+                    context.request_frame();
+                    return true;
+                }
+            },
+            InnerEvent::Blur => {
+                self.key_pressed = false;
+            },
+            _ => {}
+        }
+        false
+    }
+
+    /// Returns the interaction state of the control, which is also pressed while Space is held.
+    pub(crate) fn state(&self, context: &RenderContext) -> InteractionState {
+        let mut state = context.style_environment().state;
+        state.pressed |= self.key_pressed;
+        state
+    }
+
+    /// Resolves the style of the control with [`Activation::state`].
+    pub(crate) fn resolve_style<'s>(&self, style: &'s Style, context: &RenderContext) -> Cow<'s, Style> {
+        style.resolve(&StyleEnvironment { state: self.state(context), ..context.style_environment() })
+    }
+}
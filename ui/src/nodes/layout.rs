@@ -1,7 +1,7 @@
 use std::fmt::{Debug, Formatter};
 use crate::{nodes::{Node, NodeChildren, Style}, events::handler::EventHandlerDatabase, WeakNode, SharedNode};
 use taffy::style::Dimension;
-use crate::nodes::primitives::draw_rect;
+use crate::nodes::primitives::draw_background;
 use crate::nodes::RenderContext;
 
 /// A simple layout node which contains children.
@@ -50,6 +50,9 @@ impl Node for Layout {
     fn style(&self) -> &Style {
         &self.style
     }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
     fn children(&self) -> Option<&NodeChildren> {
         Some(&self.children)
     }
@@ -59,7 +62,7 @@ impl Node for Layout {
     }
     
     fn render_pre_children(&mut self, context: &mut RenderContext, layout: taffy::Layout) {
-        if let Some(background) = &self.style.background { draw_rect(layout.size, background, self.style.border_radius, &mut context.canvas); }
+        draw_background(&context.resolve_style(&self.style), layout.size, context);
     }
 
     fn add_child_at(&mut self, child: crate::SharedNode, index: usize) -> Result<(), super::ChildAddError> {
@@ -1,19 +1,48 @@
 pub mod layout;
 // pub mod empty;
 pub mod primitives;
+pub mod background;
+pub mod clip;
 pub mod image;
+pub mod image_cache;
+pub mod interaction;
 pub mod text;
+pub mod shape;
+pub mod canvas;
+pub mod overlay;
+pub mod tooltip;
+pub mod svg;
 pub mod text_render_cache;
+pub mod theme;
+pub mod units;
+pub mod variant;
+pub mod scroll_view;
+pub mod virtual_list;
+pub mod button;
+pub mod checkbox;
+pub mod radio;
+pub mod switch;
+pub mod slider;
+pub mod select;
 
+use std::borrow::Cow;
 use std::fmt::Debug;
 use std::sync::{Arc, Mutex, RwLock};
-use femtovg::{Canvas, Color, Paint};
-use crate::events::Location;
+use std::time::{Duration, Instant};
+use femtovg::{Canvas, Color, Paint, Path};
+use background::Background;
+use clip::{ClipLayers, ClipPath, clips_overflow, overflow_clip_path, path_contains, pop_clip_layer, push_clip_layer};
+use units::{apply_lengths, LayoutLengths, UnitContext};
+use theme::{StyleTokens, Theme};
+use interaction::{state_of, InteractionState};
+use variant::{StyleEnvironment, StyleVariant};
+use crate::events::{InnerEvent, Location, NodeEvent};
 use crate::events::handler::InnerEventHandlerDataset;
 use crate::{NodeLayoutMap, NodePtr, CurrentRenderer, SharedNode, WeakNode};
+use weak_table::PtrWeakKeyHashMap;
 
 pub use taffy::style::Style as TaffyStyle;
-use taffy::{Layout, Overflow, Point, Size, TaffyTree};
+use taffy::{Layout, LengthPercentageAuto, Point, Position, Rect, Size, TaffyTree};
 
 pub type CanvasRenderer = Canvas<CurrentRenderer>;
 
@@ -21,15 +50,90 @@ pub struct RenderContext {
     pub canvas: CanvasRenderer,
     pub node_layout: NodeLayoutMap,
     pub taffy: TaffyTree<WeakNode>,
+    /// Path of nodes under the pointer
     pub mouse: NodePtr,
+    /// Path to the focused node, see [`interaction::focus`]
     pub keyboard_focus: NodePtr,
+    /// Path of nodes the primary button was pressed on
+    pub pressed: NodePtr,
     pub scale_factor: f32,
-    pub window_size: Size<f32>
+    pub window_size: Size<f32>,
+    /// Theme styles are resolved with, see [`theme::set_themes`]
+    pub theme: Arc<Theme>,
+    /// Time at which the current frame started. Use this for animations instead of [`Instant::now`].
+    pub frame_time: Instant,
+    /// Time elapsed since the previous frame. Zero on the first frame.
+    pub frame_delta: Duration,
+    /// Node currently being prepared or handling an event. Used by [`RenderContext::emit`] and [`RenderContext::capture_pointer`].
+    pub(crate) current_node: Option<WeakNode>,
+    /// Node receiving all mouse events until the pointer capture is released.
+    pub(crate) pointer_capture: Option<WeakNode>,
+    /// Events emitted by nodes, dispatched by the event loop after the current event or frame.
+    pub(crate) queued_events: Vec<(WeakNode, InnerEvent)>,
+    /// Running smooth scroll animations, see [`scroll_view::scroll_to`]
+    pub(crate) scroll_animations: Vec<scroll_view::ScrollAnimation>,
+    pub(crate) frame_requested: bool,
+    /// Earliest time for which a frame was requested, see [`RenderContext::request_frame_at`]
+    pub(crate) next_frame: Option<Instant>,
+    /// Bounds of the area the current node is clipped to (by its ancestors), in window coordinates
+    pub(crate) clip: Option<[f32; 4]>,
+    /// Offscreen layers used for clipping to paths
+    pub(crate) clip_layers: ClipLayers,
+    /// Overlays shown above the root node, see [`overlay::open_overlay`]
+    pub(crate) overlays: overlay::OverlayLayer,
+    /// Tooltip waited for or shown, see [`tooltip::set_tooltip`]
+    pub(crate) tooltips: tooltip::TooltipState,
+    /// Offsets of fixed and sticky nodes from their layout position, in the coordinates of their parents
+    pub(crate) position_offsets: PositionOffsetMap,
+    /// Fixed nodes in the order they were rendered, hit tested before the rest of the tree
+    pub(crate) fixed_nodes: Vec<WeakNode>,
+    /// Areas sticky nodes are kept in
+    pub(crate) sticky_container: StickyContainer,
+    pub(crate) relayout_requested: bool,
+    /// Set when relative units were resolved against parent sizes from the previous layout pass
+    pub(crate) layout_uses_parent_size: bool,
+    pub(crate) event_handled: bool
+}
+
+pub(crate) type PositionOffsetMap = PtrWeakKeyHashMap<WeakNode, Point<f32>>;
+
+/// Bounds (in window coordinates) used to position sticky nodes
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct StickyContainer {
+    /// Visible area of the nearest ancestor hiding its overflow
+    scrollport: Option<[f32; 4]>,
+    /// Area of the parent, sticky nodes can't leave it
+    containing_block: [f32; 4]
+}
+
+impl Default for StickyContainer {
+    fn default() -> Self {
+        StickyContainer {
+            scrollport: None,
+            containing_block: [f32::NEG_INFINITY, f32::NEG_INFINITY, f32::INFINITY, f32::INFINITY]
+        }
+    }
 }
 
 pub struct MeasureContext<'a> {
     pub canvas: &'a mut CanvasRenderer,
-    pub scale_factor: f32
+    pub scale_factor: f32,
+    pub window_size: Size<f32>,
+    pub theme: Arc<Theme>,
+    /// Interaction state of the node being measured
+    pub state: InteractionState
+}
+
+impl MeasureContext<'_> {
+    /// Returns the style with the matching variants and theme tokens applied, see [`Style::resolve`].
+    pub fn resolve_style<'s>(&self, style: &'s Style) -> Cow<'s, Style> {
+        style.resolve(&StyleEnvironment {
+            window_size: self.window_size,
+            scale_factor: self.scale_factor,
+            theme: self.theme.clone(),
+            state: self.state
+        })
+    }
 }
 
 impl RenderContext {
@@ -43,6 +147,109 @@ impl RenderContext {
         let height = transform[1] * width + transform[3] * height as f32;
         self.canvas.clear_rect(x as u32, y as u32, width as u32, height as u32, color);
     }
+
+    /// Returns the state of the window style variants are resolved against, with the interaction state of the current node.
+    pub fn style_environment(&self) -> StyleEnvironment {
+        StyleEnvironment {
+            window_size: self.window_size,
+            scale_factor: self.scale_factor,
+            theme: self.theme.clone(),
+            state: self.current_node.as_ref().map_or(InteractionState::default(), |node| self.state_of(node.as_ptr() as *const ()))
+        }
+    }
+
+    fn state_of(&self, node: *const ()) -> InteractionState {
+        state_of(node, &self.mouse, &self.pressed, &self.keyboard_focus)
+    }
+
+    /// Returns the interaction state of the node. The disabled flag comes from the style and is set by [`Style::resolve`].
+    pub fn interaction_state(&self, node: &SharedNode) -> InteractionState {
+        self.state_of(Arc::as_ptr(node) as *const ())
+    }
+
+    /// Returns the style with the matching variants and theme tokens applied, see [`Style::resolve`].
+    /// Nodes should read their style through this when rendering or handling events.
+    pub fn resolve_style<'s>(&self, style: &'s Style) -> Cow<'s, Style> {
+        style.resolve(&self.style_environment())
+    }
+
+    /// Like [`RenderContext::resolve_style`], for the style of a node other than the current one.
+    pub fn resolve_node_style<'s>(&self, node: &SharedNode, style: &'s Style) -> Cow<'s, Style> {
+        style.resolve(&StyleEnvironment {
+            state: self.interaction_state(node),
+            ..self.style_environment()
+        })
+    }
+
+    /// Schedules another frame after the current one. Call this on each frame while an animation is running.
+    pub fn request_frame(&mut self) {
+        self.frame_requested = true;
+    }
+
+    /// Schedules a frame at the given time, for animations which don't need to render every frame.
+    pub fn request_frame_at(&mut self, time: Instant) {
+        if self.next_frame.is_none_or(|next_frame| time < next_frame) {
+            self.next_frame = Some(time);
+        }
+    }
+
+    /// Returns the bounding box (min x, min y, max x, max y) of a rectangle at the origin of the canvas in window coordinates.
+    fn window_bounds(&self, size: Size<f32>) -> [f32; 4] {
+        let transform = self.canvas.transform();
+        [(0., 0.), (size.width, 0.), (0., size.height), (size.width, size.height)].iter()
+            .map(|(x, y)| transform.transform_point(*x, *y))
+            .fold([f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY], |bounds, (x, y)| {
+                [bounds[0].min(x), bounds[1].min(y), bounds[2].max(x), bounds[3].max(y)]
+            })
+    }
+
+    /// Returns true if a rectangle of the size at the origin of the canvas is at least partially inside the window
+    /// and not clipped by ancestors. Use this during rendering to skip work for nodes which are scrolled away.
+    pub fn is_visible(&self, size: Size<f32>) -> bool {
+        let bounds = self.window_bounds(size);
+        let clip = self.clip.unwrap_or([0., 0., self.window_size.width, self.window_size.height]);
+        bounds[0] < clip[2].min(self.window_size.width) && bounds[2] > clip[0].max(0.)
+            && bounds[1] < clip[3].min(self.window_size.height) && bounds[3] > clip[1].max(0.)
+    }
+
+    /// Recomputes the layout before the next frame is rendered. Needed after changing children or layout styles
+    /// outside of the `render` channel of [`crate::MainEntry`].
+    /// When called from [`Node::prepare_render`], the layout is recomputed in the same frame.
+    pub fn request_relayout(&mut self) {
+        self.relayout_requested = true;
+        self.frame_requested = true;
+    }
+
+    /// Queues an event for the node currently being prepared or handling an event.
+    /// Queued events are dispatched once the current event or frame is done, so handlers are free to lock the node.
+    /// Does nothing when called outside of [`Node::prepare_render`], [`Node::on_event`] or the render functions.
+    pub fn emit(&mut self, event: InnerEvent) {
+        if let Some(node) = &self.current_node {
+            self.queued_events.push((node.clone(), event));
+        }
+    }
+
+    /// Sends all mouse events to the current node (see [`RenderContext::emit`]) until [`RenderContext::release_pointer`] is called
+    /// or all mouse buttons are released. Useful for dragging.
+    pub fn capture_pointer(&mut self) {
+        self.pointer_capture = self.current_node.clone();
+    }
+
+    /// Releases the pointer capture set by [`RenderContext::capture_pointer`].
+    pub fn release_pointer(&mut self) {
+        self.pointer_capture = None;
+    }
+
+    /// Marks the currently dispatched event as handled by a built-in behaviour, such as scrolling.
+    /// Nodes up the path can check [`RenderContext::is_handled`] to avoid handling the same event twice.
+    pub fn mark_handled(&mut self) {
+        self.event_handled = true;
+    }
+
+    /// Returns `true` if a node deeper in the path already handled the currently dispatched event.
+    pub fn is_handled(&self) -> bool {
+        self.event_handled
+    }
 }
 
 #[derive(Copy, Clone, Default, Debug)]
@@ -62,20 +269,237 @@ pub struct Transform {
     pub rotation: f32
 }
 
+/// Radius of a rounded corner. The corner is elliptical when the radii differ
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct CornerRadius {
+    /// Horizontal radius in pixels
+    pub x: f32,
+    /// Vertical radius in pixels
+    pub y: f32
+}
+
+impl CornerRadius {
+    pub fn new(radius: f32) -> CornerRadius {
+        CornerRadius { x: radius, y: radius }
+    }
+    pub fn elliptical(x: f32, y: f32) -> CornerRadius {
+        CornerRadius { x, y }
+    }
+    pub fn is_zero(&self) -> bool {
+        self.x <= 0. || self.y <= 0.
+    }
+}
+
+impl From<f32> for CornerRadius {
+    fn from(radius: f32) -> Self {
+        CornerRadius::new(radius)
+    }
+}
+
+/// Radii of the corners of a node. Mirrors CSS `border-radius`.
+///
+/// A single radius can be converted into a border radius with `.into()`.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct BorderRadius {
+    pub top_left: CornerRadius,
+    pub top_right: CornerRadius,
+    pub bottom_right: CornerRadius,
+    pub bottom_left: CornerRadius
+}
+
+impl BorderRadius {
+    /// Same radius for all corners
+    pub fn all(radius: f32) -> BorderRadius {
+        BorderRadius::corners(radius, radius, radius, radius)
+    }
+    /// Circular corners, in the CSS order (top left, top right, bottom right, bottom left)
+    pub fn corners(top_left: f32, top_right: f32, bottom_right: f32, bottom_left: f32) -> BorderRadius {
+        BorderRadius {
+            top_left: CornerRadius::new(top_left),
+            top_right: CornerRadius::new(top_right),
+            bottom_right: CornerRadius::new(bottom_right),
+            bottom_left: CornerRadius::new(bottom_left)
+        }
+    }
+    pub fn is_zero(&self) -> bool {
+        self.top_left.is_zero() && self.top_right.is_zero() && self.bottom_right.is_zero() && self.bottom_left.is_zero()
+    }
+
+    /// Adds a rectangle with the rounded corners to the path.
+    /// Like in CSS, all radii are scaled down when the corners on one side would overlap.
+    pub fn add_to_path(&self, path: &mut Path, x: f32, y: f32, width: f32, height: f32) {
+        if self.is_zero() {
+            path.rect(x, y, width, height);
+            return;
+        }
+        let corner = |corner: CornerRadius| if corner.is_zero() { CornerRadius::default() } else { corner };
+        let (top_left, top_right) = (corner(self.top_left), corner(self.top_right));
+        let (bottom_right, bottom_left) = (corner(self.bottom_right), corner(self.bottom_left));
+        let scale = [
+            width / (top_left.x + top_right.x),
+            width / (bottom_left.x + bottom_right.x),
+            height / (top_left.y + bottom_left.y),
+            height / (top_right.y + bottom_right.y)
+        ].into_iter().filter(|scale| scale.is_finite()).fold(1., f32::min).max(0.);
+        let scaled = |corner: CornerRadius| (corner.x * scale, corner.y * scale);
+        let (top_left, top_right) = (scaled(top_left), scaled(top_right));
+        let (bottom_right, bottom_left) = (scaled(bottom_right), scaled(bottom_left));
+        // distance of bezier control points from the corner to approximate quarter ellipses
+        const KAPPA: f32 = 1. - 0.5522847;
+        let (right, bottom) = (x + width, y + height);
+        path.move_to(x + top_left.0, y);
+        path.line_to(right - top_right.0, y);
+        path.bezier_to(right - top_right.0 * KAPPA, y, right, y + top_right.1 * KAPPA, right, y + top_right.1);
+        path.line_to(right, bottom - bottom_right.1);
+        path.bezier_to(right, bottom - bottom_right.1 * KAPPA, right - bottom_right.0 * KAPPA, bottom, right - bottom_right.0, bottom);
+        path.line_to(x + bottom_left.0, bottom);
+        path.bezier_to(x + bottom_left.0 * KAPPA, bottom, x, bottom - bottom_left.1 * KAPPA, x, bottom - bottom_left.1);
+        path.line_to(x, y + top_left.1);
+        path.bezier_to(x, y + top_left.1 * KAPPA, x + top_left.0 * KAPPA, y, x + top_left.0, y);
+        path.close();
+    }
+
+    /// Returns a path of the node of the size, with the rounded corners.
+    pub fn to_path(&self, size: Size<f32>) -> Path {
+        let mut path = Path::new();
+        self.add_to_path(&mut path, 0., 0., size.width, size.height);
+        path
+    }
+}
+
+impl From<f32> for BorderRadius {
+    fn from(radius: f32) -> Self {
+        BorderRadius::all(radius)
+    }
+}
+
+/// Positioning which taffy doesn't support, applied as an offset to the layout when rendering and hit testing.
+/// Insets are in pixels or percentages of the window (fixed) or of the scrolling ancestor (sticky).
+#[derive(Clone, Default, Debug, PartialEq)]
+pub enum Positioning {
+    /// Positioned by the layout only
+    #[default]
+    Static,
+    /// Positioned against the window with the insets, like CSS `position: fixed`. The node is taken out of the flow
+    /// (laid out as absolute) and isn't moved, transformed or clipped by its ancestors. Axes with both insets
+    /// set to auto keep the position from the layout. Percentage sizes are still relative to the parent
+    Fixed(Rect<LengthPercentageAuto>),
+    /// Stays in the flow, but doesn't scroll past the insets from the edges of the nearest ancestor hiding its overflow,
+    /// like CSS `position: sticky`. The node is kept inside its parent (or the scrolled content if the parent is the scrolling ancestor)
+    Sticky(Rect<LengthPercentageAuto>)
+}
+
+impl Positioning {
+    /// Sticks to the top edge of the scrolling ancestor
+    pub fn sticky_top(top: f32) -> Positioning {
+        Positioning::Sticky(Rect {
+            top: LengthPercentageAuto::Length(top),
+            ..Rect::auto()
+        })
+    }
+}
+
+/// Whether the node is painted. Mirrors CSS `visibility`, but isn't inherited: hiding a node hides its children too.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum Visibility {
+    #[default]
+    Visible,
+    /// The node keeps its space in the layout, but it and its children aren't painted or hit by the mouse
+    Hidden
+}
+
+/// Whether the node receives mouse events. Mirrors CSS `pointer-events`.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum PointerEvents {
+    #[default]
+    Auto,
+    /// The node and its children are ignored by hit testing, mouse events go to the nodes below them.
+    /// Use for decorations drawn over other nodes, like fades over a list
+    None
+}
+
+/// How replaced content (like an image) is resized to fit the node. Mirrors CSS `object-fit`.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum ObjectFit {
+    /// Stretched to fill the node, ignoring the aspect ratio
+    #[default]
+    Fill,
+    /// Scaled to fit inside the node, keeping the aspect ratio
+    Contain,
+    /// Scaled to cover the whole node, keeping the aspect ratio. Overflowing parts are clipped
+    Cover,
+    /// Not resized
+    None,
+    /// Same as [`ObjectFit::None`] or [`ObjectFit::Contain`], whichever is smaller
+    ScaleDown
+}
+
+impl ObjectFit {
+    /// Returns the size of the content when fitted into the given size
+    pub fn fit(&self, content: Size<f32>, size: Size<f32>) -> Size<f32> {
+        let contain = (size.width / content.width).min(size.height / content.height);
+        let scale = match self {
+            ObjectFit::Fill => return size,
+            ObjectFit::Contain => contain,
+            ObjectFit::Cover => (size.width / content.width).max(size.height / content.height),
+            ObjectFit::None => 1.,
+            ObjectFit::ScaleDown => contain.min(1.)
+        };
+        Size { width: content.width * scale, height: content.height * scale }
+    }
+}
+
+/// Alignment of replaced content (like an image) inside the node. Mirrors CSS `object-position`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ObjectPosition {
+    /// 0.0 aligns the left edges, 1.0 the right edges. Default is 0.5 (centered)
+    pub x: f32,
+    /// 0.0 aligns the top edges, 1.0 the bottom edges. Default is 0.5 (centered)
+    pub y: f32
+}
+
+impl Default for ObjectPosition {
+    fn default() -> Self {
+        ObjectPosition { x: 0.5, y: 0.5 }
+    }
+}
+
 /// Styles for the node. Note that the styles aren't inherited (yet?)
 #[derive(Clone, Default, Debug)]
 pub struct Style {
     pub layout: TaffyStyle,
+    /// Layout properties in em, rem, vw and vh units or sums of units, overriding the ones in `layout`
+    pub lengths: LayoutLengths,
+    /// Overrides applied when their conditions (like the window size or hover) are met, see [`RenderContext::resolve_style`]
+    pub variants: Vec<StyleVariant>,
+    /// Theme tokens used instead of literal colors, spacing, radii and typography
+    pub tokens: StyleTokens,
+    /// Disabled nodes can't be pressed or focused, and match [`variant::StyleCondition::Disabled`]
+    pub disabled: bool,
     pub cursor: Cursor,
-    pub background: Option<Paint>,
+    pub background: Option<Background>,
     /// defaults to black
     pub text_fill: Option<Paint>,
     /// font size in pixels. Default is 16
     pub font_size: Option<f32>,
     /// multiplier of line height in relation to font size. Default is 1.2
     pub line_height: Option<f32>,
-    /// border radius in pixels
-    pub border_radius: f32,
+    /// radii of the corners. Children of nodes hiding their overflow are clipped to the rounded corners
+    pub border_radius: BorderRadius,
+    /// Shape the node and its children are clipped to
+    pub clip_path: Option<ClipPath>,
+    /// Fixed and sticky positioning, applied on top of the layout
+    pub position: Positioning,
+    /// Hidden nodes keep their layout, but aren't painted or hit
+    pub visibility: Visibility,
+    /// Whether the node and its children are hit by the mouse
+    pub pointer_events: PointerEvents,
+    /// Stacking order, honoured for absolutely positioned, fixed and sticky nodes. Higher values are painted above
+    /// and hit first. Positioned and transformed nodes form stacking contexts: their descendants are ordered among each other,
+    /// never outside of the order of the stacking context. Nodes with a z-index are ordered among all descendants of their
+    /// stacking context, so a badge inside a plain layout can overlap the siblings of the layout. Negative values go below
+    /// the descendants without a z-index. Among siblings, positioned and transformed nodes are painted above the others
+    pub z_index: Option<i32>,
     /// Various transformation (position, scale and rotation)
     pub transform: Option<Transform>,
     /// sets scroll offset for x-axis
@@ -86,6 +510,10 @@ pub struct Style {
     /// 0.0 is the default value
     /// you cannot scroll outside the layout - render function will clip the value in that case
     pub scroll_y: f32,
+    /// How images are resized to fit the node
+    pub object_fit: ObjectFit,
+    /// Alignment of images inside the node
+    pub object_position: ObjectPosition,
 }
 
 type NodeChildren = Vec<SharedNode>;
@@ -120,6 +548,7 @@ pub enum ChildAddError {
 /// # Events
 ///
 /// If you need to handle events, implement [`Node::event_handlers`].
+/// Built-in behaviour of the node itself (scrolling, dragging...) should be implemented in [`Node::on_event`] instead.
 ///
 /// # Function call order
 ///
@@ -129,10 +558,11 @@ pub enum ChildAddError {
 ///
 /// During rendering, the order is following:
 ///
-/// - parents are changed ([`Node::set_parent`]) to new state according to read-only functions
 /// - [`Node::prepare_render`] is called on each node
-/// - [`Node::style`] is read
-/// - [`Node::measure`] is called on some nodes (depends on taffy); can be called multiple times
+/// - if the layout needs to be recomputed:
+///    - parents are changed ([`Node::set_parent`]) to new state according to read-only functions
+///    - [`Node::style`] is read
+///    - [`Node::measure`] is called on some nodes (depends on taffy); can be called multiple times
 /// - nodes are rendered, i.e. on each node, starting from the root node, the following is called:
 ///    - [`Node::render_pre_children`] is called
 ///    - children are rendered
@@ -145,6 +575,14 @@ pub trait Node: Debug + Send {
     /// fn style(&self) -> &Style { &self.style }
     /// ```
     fn style(&self) -> &Style;
+    /// Return mutable style. Used when the UI lib itself changes the style, like when scrolling to a node
+    /// (see [`scroll_view::scroll_into_view`]). Nodes which return None can't be scrolled that way.
+    ///
+    /// If you're using [`Style`] in your struct directly, your implementation can be as simple as:
+    ///```rust
+    /// fn style_mut(&mut self) -> Option<&mut Style> { Some(&mut self.style) }
+    /// ```
+    fn style_mut(&mut self) -> Option<&mut Style> { None }
     /// Returns the children of the node. If the node has no children, return None (empty Vec also works, None is mainly for nodes without children support).
     ///
     /// If you're using [`NodeChildren`] in your struct directly, your implementation can be as simple as:
@@ -266,8 +704,21 @@ pub trait Node: Debug + Send {
 
     /// Called on each redraw. Use this to prepare for rendering. Called before any layouting or rendering happens.
     /// Order between nodes is not guaranteed.
+    ///
+    /// Layout isn't recomputed on every frame - call [`RenderContext::request_relayout`] if you change children or layout styles here.
     fn prepare_render(&mut self, _context: &mut RenderContext) {}
 
+    /// Called for each event passing through this node (see [`crate::events::InnerEvent::bubbles`]), before the event handlers of the node are run.
+    /// Use this for built-in behaviour of the node. Other nodes and handlers are not locked during this call,
+    /// but this node is - do not lock it again (directly or through the event's path).
+    /// Use [`RenderContext::emit`] to send events from the node.
+    fn on_event(&mut self, _event: &NodeEvent, _context: &mut RenderContext) {}
+
+    /// Returns true if the node can receive the keyboard focus. Pressing a focusable node focuses it.
+    fn focusable(&self) -> bool {
+        false
+    }
+
     /// Called before rendering the node to measure it's size.
     /// The calling of this method is managed by taffy, and as such:
     /// - It may be called multiple times (with same or different arguments) during the same render pass
@@ -316,30 +767,124 @@ impl<T: Node + 'static> ToShared for T {
 
 /// Runs event handlers for the given path
 /// The target element should be the last one in path (event handlers are ran in reverse order)
-pub(crate) fn run_event_handlers(path: Vec<SharedNode>, event: crate::events::NodeEvent) {
+pub(crate) fn run_event_handlers(path: Vec<SharedNode>, event: NodeEvent, context: &mut RenderContext) {
+    context.event_handled = false;
     for node in path.iter().rev() {
-        let node = node.lock().unwrap();
-        if let Some(handlers) = node.event_handlers() {
-            drop(node);
-            for handler in handlers.lock().unwrap().values_mut() {
-                handler.lock().unwrap()(&event);
+        run_node_event_handlers(node, &event, context);
+    }
+}
+
+pub(crate) fn run_single_event_handlers(node: SharedNode, event: NodeEvent, context: &mut RenderContext) {
+    context.event_handled = false;
+    run_node_event_handlers(&node, &event, context);
+}
+
+fn run_node_event_handlers(node: &SharedNode, event: &NodeEvent, context: &mut RenderContext) {
+    let mut locked = node.lock().unwrap();
+    context.current_node = Some(Arc::downgrade(node));
+    locked.on_event(event, context);
+    context.current_node = None;
+    let handlers = locked.event_handlers();
+    drop(locked);
+    if let Some(handlers) = handlers {
+        for handler in handlers.lock().unwrap().values_mut() {
+            handler.lock().unwrap()(event);
+        }
+    }
+}
+
+/// Dispatches events queued by [`RenderContext::emit`]. Events emitted while dispatching are dispatched as well.
+pub(crate) fn run_queued_events(context: &mut RenderContext) {
+    // limit the number of rounds so that two nodes emitting events in response to each other can't hang the event loop
+    for _ in 0..16 {
+        if context.queued_events.is_empty() { return; }
+        let queued = std::mem::take(&mut context.queued_events);
+        for (target, event) in queued {
+            let Some(target) = target.upgrade() else { continue; };
+            let bubbles = event.bubbles();
+            let path = get_path_to(&target);
+            let event = NodeEvent {
+                target: target.clone(),
+                path: path.clone(),
+                event
+            };
+            if bubbles {
+                run_event_handlers(path, event, context);
+            } else {
+                run_single_event_handlers(target, event, context);
             }
         }
     }
 }
 
-pub(crate) fn run_single_event_handlers(node: SharedNode, event: crate::events::NodeEvent) {
-    let node = node.lock().unwrap();
-    if let Some(handlers) = node.event_handlers() {
-        drop(node);
-        for handler in handlers.lock().unwrap().values_mut() {
-            handler.lock().unwrap()(&event);
+/// Returns path from the root to the given node (inclusive) by walking the parents.
+pub fn get_path_to(node: &SharedNode) -> Vec<SharedNode> {
+    let mut path = vec![node.clone()];
+    let mut parent = node.lock().unwrap().parent();
+    while let Some(node) = parent {
+        parent = node.lock().unwrap().parent();
+        path.push(node);
+    }
+    path.reverse();
+    path
+}
+
+/// Returns the scroll offset of a node, clipped to the scrollable area.
+pub fn scroll_offset(style: &Style, layout: &Layout) -> Point<f32> {
+    Point {
+        x: style.scroll_x.min(layout.content_size.width - layout.size.width).max(0.),
+        y: style.scroll_y.min(layout.content_size.height - layout.size.height).max(0.)
+    }
+}
+
+/// Returns the location of the top left corner of the node relative to the window.
+/// Includes transform positions, fixed and sticky offsets and scroll offsets of the ancestors, and the position of the overlay containing the node.
+/// Scale and rotation are ignored.
+/// Returns None if the node wasn't laid out yet.
+pub fn window_location(node: &SharedNode, context: &RenderContext) -> Option<Location> {
+    let mut location = Location::new(0., 0.);
+    let mut current = Some(node.clone());
+    let mut is_target = true;
+    while let Some(node) = current {
+        let locked = node.lock().unwrap();
+        let layout = context.taffy.layout(*context.node_layout.get(&node)?).unwrap();
+        let style = context.resolve_node_style(&node, locked.style());
+        let layout_location: Location = layout.location.into();
+        location += layout_location;
+        if let Some(transform) = &style.transform {
+            location += Location::from(transform.position);
+        }
+        if style.position != Positioning::Static {
+            if let Some(offset) = context.position_offsets.get(&node) {
+                location += Location::from(*offset);
+            }
+        }
+        if !is_target {
+            location -= Location::from(scroll_offset(&style, layout));
+        }
+        drop(style);
+        is_target = false;
+        current = locked.parent();
+        if current.is_none() {
+            // roots of overlays are placed by the overlay layer
+            if let Some(position) = context.overlays.position_of(&node) {
+                location += position;
+            }
         }
     }
+    Some(location)
 }
 
 /// Attempts to get path to the element at the target location. Assumes elements are always inside their parents.
+/// Clip paths and rounded corners of nodes hiding their overflow are respected.
+/// When children overlap, only the topmost one (see [`Style::z_index`]) is part of the path.
+/// Hidden nodes and nodes ignoring pointer events are skipped, along with their children.
 pub(crate) fn get_element_at(node: &SharedNode, context: &RenderContext, location: Location) -> Option<Vec<SharedNode>> {
+    hit_node(node, context, location, None)
+}
+
+/// Hit tests the node as part of the pass over its stacking context, see [`get_element_at`].
+fn hit_node(node: &SharedNode, context: &RenderContext, location: Location, pass: Option<StackingPass>) -> Option<Vec<SharedNode>> {
     let node_borrowed = node.lock().unwrap();
     let children = node_borrowed.children();
     let taffy_node = context.node_layout.get(node);
@@ -348,49 +893,85 @@ pub(crate) fn get_element_at(node: &SharedNode, context: &RenderContext, locatio
         None => { return None }
     };
     let layout = *context.taffy.layout(*taffy_node).unwrap();
+    let style = context.resolve_node_style(node, node_borrowed.style());
+    if style.visibility == Visibility::Hidden || style.pointer_events == PointerEvents::None {
+        return None;
+    }
+    let role = stacking_role(&style, pass)?;
+    let offset = style.transform.as_ref().map(|t| Location::from(t.position)).unwrap_or_default();
+    let position_offset = match style.position {
+        Positioning::Static => Location::default(),
+        _ => context.position_offsets.get(node).map(|offset| Location::from(*offset)).unwrap_or_default()
+    };
+    // location relative to the node itself
+    let location = location - Location::from(layout.location) - offset - position_offset;
 
-    if layout.location.x <= location.x && layout.location.y <= location.y && layout.location.x + layout.size.width >= location.x && layout.location.y + layout.size.height >= location.y {
-        match children {
-            None => {
-                Some(vec![node.clone()])
-            },
-            Some(children) => {
-                let mut result = vec![node.clone()];
-                for child in children {
-                    if let Some(mut path) = get_element_at(child, context, location) {
+    let inside = location.x >= 0. && location.y >= 0. && layout.size.width >= location.x && layout.size.height >= location.y;
+    let clipped = style.clip_path.as_ref()
+        .is_some_and(|clip_path| !path_contains(&context.canvas, &clip_path.to_path(layout.size), location.x, location.y));
+    if inside && !clipped {
+        // children are hidden outside of rounded corners
+        let children = children.filter(|_| overflow_clip_path(&style, layout.size)
+            .is_none_or(|path| path_contains(&context.canvas, &path, location.x, location.y)));
+        if let Some(children) = children {
+            let location = location + Location::from(scroll_offset(&style, &layout));
+            // the topmost child is painted last
+            for pass in stacking_passes(role, children, context).into_iter().rev() {
+                for child in paint_order(children, context).into_iter().rev() {
+                    if let Some(mut path) = hit_node(child, context, location, Some(pass)) {
+                        let mut result = vec![node.clone()];
                         result.append(&mut path);
+                        return Some(result);
                     }
                 }
-                Some(result)
             }
         }
+        // nodes which only place their descendants aren't hit themselves
+        match role {
+            StackingRole::Place(_) => None,
+            _ => Some(vec![node.clone()])
+        }
     } else {
         None
     }
 }
 
-pub(crate) fn update_taffynode_children(node: &SharedNode, context: &mut RenderContext) -> taffy::tree::NodeId {
-    let taffy_node = context.node_layout.get(node);
-    let taffy_node = match taffy_node {
-        Some(taffy_node) => taffy_node,
+/// Syncs the taffy tree with the node tree and updates the layout styles, resolving relative units with `units`.
+pub(crate) fn update_taffynode_children(node: &SharedNode, context: &mut RenderContext, mut units: UnitContext) -> taffy::tree::NodeId {
+    let node_ref = node.lock().unwrap();
+    let style = context.resolve_node_style(node, node_ref.style());
+    units.font_size = style.font_size.unwrap_or(units.font_size);
+    let layout = taffy_style(&style, &mut units);
+    drop(style);
+    drop(node_ref);
+    context.layout_uses_parent_size |= units.uses_parent_size;
+    let taffy_node = match context.node_layout.get(node) {
+        Some(taffy_node) => {
+            let taffy_node = taffy_node.to_owned();
+            context.taffy.set_style(taffy_node, layout).unwrap();
+            taffy_node
+        },
         None => {
-            let taffy_node = context.taffy.new_leaf_with_context(
-                node.lock().unwrap().style().layout.to_owned(),
-                Arc::downgrade(node)
-            ).unwrap();
+            let taffy_node = context.taffy.new_leaf_with_context(layout, Arc::downgrade(node)).unwrap();
             context.node_layout.insert(node.clone(), taffy_node);
-            context.node_layout.get(node).unwrap()
+            taffy_node
         }
     };
 
-    let taffy_node = taffy_node.to_owned();
+    // percentages of children refer to the content box from the previous layout pass
+    let previous = context.taffy.layout(taffy_node).unwrap();
+    let parent_size = Size {
+        width: (previous.size.width - previous.padding.left - previous.padding.right - previous.border.left - previous.border.right).max(0.),
+        height: (previous.size.height - previous.padding.top - previous.padding.bottom - previous.border.top - previous.border.bottom).max(0.)
+    };
 
     match node.lock().unwrap().children() {
         None => {},
         Some(children) => {
             let mut t_children = Vec::with_capacity(children.len());
             for child in children {
-                t_children.push(update_taffynode_children(child, context).to_owned());
+                let child_units = UnitContext { parent_size, uses_parent_size: false, ..units };
+                t_children.push(update_taffynode_children(child, context, child_units));
                 child.lock().unwrap().set_parent(Some(Arc::downgrade(node)));
             }
             context.taffy.set_children(taffy_node, t_children.as_slice()).unwrap();
@@ -400,54 +981,304 @@ pub(crate) fn update_taffynode_children(node: &SharedNode, context: &mut RenderC
     taffy_node
 }
 
+/// Returns the key children are sorted by when painting, see [`Style::z_index`]: the z-index, and whether the node is a stacking context.
+fn stacking_order(style: &Style) -> (i32, bool) {
+    let positioned = style.layout.position == Position::Absolute || style.position != Positioning::Static;
+    let z_index = if positioned { style.z_index.unwrap_or(0) } else { 0 };
+    (z_index, positioned || style.transform.is_some())
+}
+
+/// Pass over the descendants of a stacking context, see [`Style::z_index`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum StackingPass {
+    /// Nodes without a z-index. Nodes with one are left to their own pass, along with their children
+    Flow,
+    /// Nodes with the z-index. Nodes between them and the stacking context only place and clip them
+    Layer(i32)
+}
+
+/// What a pass does with a node, see [`stacking_role`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum StackingRole {
+    /// Painted, along with the passes over its descendants
+    Context,
+    /// Painted, along with its children without a z-index
+    Flow,
+    /// Not painted, but its descendants with the z-index are
+    Place(i32)
+}
+
+/// Returns what the pass does with the node, or None if the node (and its children) isn't part of it.
+/// Roots (None pass) are always stacking contexts.
+fn stacking_role(style: &Style, pass: Option<StackingPass>) -> Option<StackingRole> {
+    let (z_index, stacking_context) = stacking_order(style);
+    match pass {
+        None => Some(StackingRole::Context),
+        Some(StackingPass::Flow) if z_index != 0 => None,
+        Some(StackingPass::Flow) if stacking_context => Some(StackingRole::Context),
+        Some(StackingPass::Flow) => Some(StackingRole::Flow),
+        Some(StackingPass::Layer(layer)) if z_index != 0 => (z_index == layer).then_some(StackingRole::Context),
+        Some(StackingPass::Layer(_)) if stacking_context => None,
+        Some(StackingPass::Layer(layer)) => Some(StackingRole::Place(layer))
+    }
+}
+
+/// Returns the passes over the children of a node with the role, back to front.
+/// Descendants of a stacking context with a negative z-index are painted below the others, positive ones above.
+fn stacking_passes(role: StackingRole, children: &NodeChildren, context: &RenderContext) -> Vec<StackingPass> {
+    match role {
+        StackingRole::Context => {
+            let mut layers = Vec::new();
+            stacking_layers(children, context, &mut layers);
+            layers.sort_unstable();
+            layers.dedup();
+            let below = layers.iter().filter(|layer| **layer < 0).map(|layer| StackingPass::Layer(*layer));
+            let above = layers.iter().filter(|layer| **layer > 0).map(|layer| StackingPass::Layer(*layer));
+            below.chain(std::iter::once(StackingPass::Flow)).chain(above).collect()
+        },
+        StackingRole::Flow => vec![StackingPass::Flow],
+        StackingRole::Place(layer) => vec![StackingPass::Layer(layer)]
+    }
+}
+
+/// Collects the z-indices of the descendants ordered by the stacking context of the children.
+/// Descendants of nested stacking contexts are ordered by them instead.
+fn stacking_layers(children: &NodeChildren, context: &RenderContext, layers: &mut Vec<i32>) {
+    for child in children {
+        let locked = child.lock().unwrap();
+        let (z_index, stacking_context) = stacking_order(&context.resolve_node_style(child, locked.style()));
+        if z_index != 0 {
+            layers.push(z_index);
+        } else if let (false, Some(children)) = (stacking_context, locked.children()) {
+            stacking_layers(children, context, layers);
+        }
+    }
+}
+
+/// Returns the children in the order they are painted (back to front). Hit testing goes in reverse.
+pub(crate) fn paint_order<'a>(children: &'a NodeChildren, context: &RenderContext) -> Vec<&'a SharedNode> {
+    let mut ordered: Vec<_> = children.iter()
+        .map(|child| (stacking_order(&context.resolve_node_style(child, child.lock().unwrap().style())), child))
+        .collect();
+    // stable, so siblings with the same order keep the tree order
+    ordered.sort_by_key(|(order, _)| *order);
+    ordered.into_iter().map(|(_, child)| child).collect()
+}
+
+/// Returns the layout style of the node given to taffy. Fixed nodes are taken out of the flow.
+pub(crate) fn taffy_style(style: &Style, units: &mut UnitContext) -> TaffyStyle {
+    let mut layout = style.layout.to_owned();
+    apply_lengths(&mut layout, &style.lengths, units);
+    if let Positioning::Fixed(_) = style.position {
+        layout.position = Position::Absolute;
+        layout.inset = Rect::auto();
+    }
+    layout
+}
+
+fn resolve_inset(inset: LengthPercentageAuto, relative_to: f32) -> Option<f32> {
+    match inset {
+        LengthPercentageAuto::Length(length) => Some(length),
+        LengthPercentageAuto::Percent(percent) => Some(percent * relative_to),
+        LengthPercentageAuto::Auto => None
+    }
+}
+
+/// Returns the window position of a fixed node with the insets. Axes without insets keep the layout position.
+fn fixed_location(inset: &Rect<LengthPercentageAuto>, size: Size<f32>, window: Size<f32>, layout_location: (f32, f32)) -> (f32, f32) {
+    let axis = |start: LengthPercentageAuto, end: LengthPercentageAuto, length: f32, window: f32, layout: f32| {
+        resolve_inset(start, window)
+            .or_else(|| resolve_inset(end, window).map(|end| window - end - length))
+            .unwrap_or(layout)
+    };
+    (
+        axis(inset.left, inset.right, size.width, window.width, layout_location.0),
+        axis(inset.top, inset.bottom, size.height, window.height, layout_location.1)
+    )
+}
+
+/// Returns the offset keeping a sticky node with the bounds (in window coordinates) inside the scrollport.
+fn sticky_offset(inset: &Rect<LengthPercentageAuto>, bounds: [f32; 4], container: StickyContainer) -> Point<f32> {
+    let Some(scrollport) = container.scrollport else { return Point { x: 0., y: 0. }; };
+    let block = container.containing_block;
+    let axis = |start: LengthPercentageAuto, end: LengthPercentageAuto, axis: usize| {
+        let length = scrollport[axis + 2] - scrollport[axis];
+        let mut offset = 0f32;
+        // like CSS, the start edge wins when both don't fit
+        if let Some(end) = resolve_inset(end, length) {
+            offset = offset.min(scrollport[axis + 2] - end - bounds[axis + 2]);
+        }
+        if let Some(start) = resolve_inset(start, length) {
+            offset = offset.max(scrollport[axis] + start - bounds[axis]);
+        }
+        // the node can't leave its containing block
+        if offset > 0. {
+            offset.min((block[axis + 2] - bounds[axis + 2]).max(0.))
+        } else {
+            offset.max((block[axis] - bounds[axis]).min(0.))
+        }
+    };
+    Point { x: axis(inset.left, inset.right, 0), y: axis(inset.top, inset.bottom, 1) }
+}
+
+/// Size used for scissors on axes which aren't clipped
+const UNCLIPPED: f32 = 1e7;
+
+/// Intersects the clip bounds (in window coordinates) with the bounds of a node on the clipped axes.
+fn intersect_clip(clip: Option<[f32; 4]>, bounds: [f32; 4], clip_width: bool, clip_height: bool) -> [f32; 4] {
+    let clip = clip.unwrap_or([f32::NEG_INFINITY, f32::NEG_INFINITY, f32::INFINITY, f32::INFINITY]);
+    [
+        if clip_width { clip[0].max(bounds[0]) } else { clip[0] },
+        if clip_height { clip[1].max(bounds[1]) } else { clip[1] },
+        if clip_width { clip[2].min(bounds[2]) } else { clip[2] },
+        if clip_height { clip[3].min(bounds[3]) } else { clip[3] }
+    ]
+}
+
+/// Renders the node and its descendants, as the root of a stacking context (see [`Style::z_index`]).
 pub(crate) fn render_recursively(node: &SharedNode, context: &mut RenderContext) {
+    render_node(node, context, None);
+}
+
+/// Renders the node as part of the pass over its stacking context.
+fn render_node(node: &SharedNode, context: &mut RenderContext, pass: Option<StackingPass>) {
     let read_node = node.lock().unwrap();
-    let styles = read_node.style();
+    let styles = context.resolve_node_style(node, read_node.style());
+    if styles.visibility == Visibility::Hidden {
+        return;
+    }
+    let Some(role) = stacking_role(&styles, pass) else { return; };
+    // nodes which only place their descendants are transformed and clipped, but not rendered
+    let paints = !matches!(role, StackingRole::Place(_));
     let taffy_node = context.node_layout.get(node).unwrap();
     let layout = *context.taffy.layout(*taffy_node).unwrap();
     let sself = node.clone();
     context.canvas.save();
+    let previous_clip = context.clip;
     let offset = styles.transform.as_ref().map(|t| (t.position.x, t.position.y)).unwrap_or((0., 0.));
-    let scroll_offset = (styles.scroll_x, styles.scroll_y);
-    let content_size = layout.content_size;
-    let visible_size = layout.size;
-    let scroll_offset = (scroll_offset.0.min(content_size.width - visible_size.width).max(0.), scroll_offset.1.min(content_size.height - visible_size.height).max(0.));
-    context.canvas.translate(
-        layout.location.x + offset.0 - scroll_offset.0,
-        layout.location.y + offset.1 - scroll_offset.1
-    );
+    let scroll = scroll_offset(&styles, &layout);
+    let position_offset = match &styles.position {
+        Positioning::Static => None,
+        Positioning::Fixed(inset) => {
+            let layout_location = context.canvas.transform().transform_point(layout.location.x, layout.location.y);
+            let location = fixed_location(inset, layout.size, context.window_size, layout_location);
+            // fixed nodes are placed in the window, ancestors don't move or clip them
+            context.canvas.reset_transform();
+            context.canvas.reset_scissor();
+            context.clip = None;
+            context.canvas.translate(location.0 + offset.0, location.1 + offset.1);
+            context.fixed_nodes.push(Arc::downgrade(node));
+            // for hit testing, which ignores scale and rotation of ancestors
+            Some(Point { x: location.0 - layout_location.0, y: location.1 - layout_location.1 })
+        },
+        Positioning::Sticky(inset) => {
+            context.canvas.translate(layout.location.x + offset.0, layout.location.y + offset.1);
+            let sticky = sticky_offset(inset, context.window_bounds(layout.size), context.sticky_container);
+            context.canvas.translate(sticky.x, sticky.y);
+            Some(sticky)
+        }
+    };
+    match position_offset {
+        Some(position_offset) => { context.position_offsets.insert(node.clone(), position_offset); },
+        None => {
+            context.canvas.translate(layout.location.x + offset.0, layout.location.y + offset.1);
+            if !context.position_offsets.is_empty() {
+                context.position_offsets.remove(node);
+            }
+        }
+    }
     if let Some(transform) = &styles.transform {
         context.canvas.scale(transform.scale.width, transform.scale.height);
         context.canvas.rotate(transform.rotation);
     }
-    let clip_width = matches!(styles.layout.overflow.x, Overflow::Hidden | Overflow::Clip | Overflow::Scroll);
-    let clip_height = matches!(styles.layout.overflow.y, Overflow::Hidden | Overflow::Clip | Overflow::Scroll);
+    let clip_width = clips_overflow(styles.layout.overflow.x);
+    let clip_height = clips_overflow(styles.layout.overflow.y);
+    let inherited_clip = context.clip;
+    let clip_path = styles.clip_path.as_ref().map(|clip_path| clip_path.to_path(layout.size));
+    let mut overflow_path = overflow_clip_path(&styles, layout.size);
+    if overflow_path.is_none() && inherited_clip.is_some() && (clip_width || clip_height) {
+        // scissors can only be intersected exactly when they are rotated the same way, which is the case
+        // when the node isn't rotated (ancestors clipping the node are axis aligned too, or would be clipped by a path)
+        let transform = context.canvas.transform();
+        if transform[1].abs() > f32::EPSILON || transform[2].abs() > f32::EPSILON {
+            let mut path = Path::new();
+            path.rect(0., 0., layout.size.width, layout.size.height);
+            overflow_path = Some(path);
+        }
+    }
+    if clip_path.is_some() {
+        context.clip = Some(intersect_clip(inherited_clip, context.window_bounds(layout.size), true, true));
+    }
+    let clip_path_layer = clip_path.is_some() && push_clip_layer(context);
+    let outer_clip = context.clip;
     if clip_width || clip_height {
-        context.canvas.scissor(
-            0.,
-            0.,
-            if clip_width { layout.size.width } else { f32::INFINITY },
-            if clip_height { layout.size.height } else { f32::INFINITY },
-        );
+        context.clip = Some(intersect_clip(outer_clip, context.window_bounds(layout.size), clip_width, clip_height));
+        if overflow_path.is_none() {
+            context.canvas.intersect_scissor(
+                if clip_width { 0. } else { -UNCLIPPED },
+                if clip_height { 0. } else { -UNCLIPPED },
+                if clip_width { layout.size.width } else { UNCLIPPED * 2. },
+                if clip_height { layout.size.height } else { UNCLIPPED * 2. }
+            );
+        }
     }
+    drop(styles);
     drop(read_node);
     let mut locked = sself.lock().unwrap();
-    locked.render_pre_children(context, layout);
+    if paints {
+        context.current_node = Some(Arc::downgrade(node));
+        locked.render_pre_children(context, layout);
+        context.current_node = None;
+    }
     if let Some(children) = locked.children() {
-        for child in children {
-            render_recursively(child, context);
+        let overflow_layer = overflow_path.is_some() && push_clip_layer(context);
+        let previous_sticky = context.sticky_container;
+        let scrollport = (clip_width || clip_height).then(|| context.window_bounds(layout.size));
+        // only the children are scrolled, the node itself (background, scrollbars...) stays in place
+        context.canvas.translate(-scroll.x, -scroll.y);
+        let containing_block = match scrollport {
+            // children of the scrolling node are kept inside the scrolled content
+            Some(_) => Size {
+                width: layout.content_size.width.max(layout.size.width),
+                height: layout.content_size.height.max(layout.size.height)
+            },
+            None => layout.size
+        };
+        context.sticky_container = StickyContainer {
+            scrollport: scrollport.or(previous_sticky.scrollport),
+            containing_block: context.window_bounds(containing_block)
+        };
+        for pass in stacking_passes(role, children, context) {
+            for child in paint_order(children, context) {
+                render_node(child, context, Some(pass));
+            }
+        }
+        context.sticky_container = previous_sticky;
+        context.canvas.translate(scroll.x, scroll.y);
+        if let (true, Some(path)) = (overflow_layer, &overflow_path) {
+            pop_clip_layer(context, path, layout.size);
         }
     }
-    locked.render_post_children(context, layout);
+    if paints {
+        context.current_node = Some(Arc::downgrade(node));
+        locked.render_post_children(context, layout);
+        context.current_node = None;
+    }
+    if let (true, Some(path)) = (clip_path_layer, &clip_path) {
+        pop_clip_layer(context, path, layout.size);
+    }
+    context.clip = previous_clip;
     context.canvas.restore();
 }
 
 pub(crate) fn prepare_render_recursively(node: &SharedNode, context: &mut RenderContext) {
     let mut write_node = node.lock().unwrap();
+    context.current_node = Some(Arc::downgrade(node));
     write_node.prepare_render(context);
+    context.current_node = None;
     if let Some(children) = write_node.children() {
         for child in children {
             prepare_render_recursively(child, context);
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,329 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use taffy::Size;
+use crate::events::Location;
+use crate::nodes::{get_element_at, get_path_to, render_recursively, scroll_offset, window_location, RenderContext};
+use crate::{SharedNode, WeakSharedNode};
+
+lazy_static::lazy_static! {
+    static ref OVERLAY_REQUESTS: Mutex<Vec<OverlayRequest>> = Mutex::new(Vec::new());
+}
+
+static NEXT_OVERLAY_ID: AtomicU64 = AtomicU64::new(0);
+
+/// What an overlay is positioned against.
+#[derive(Clone, Debug, Default)]
+pub enum Anchor {
+    /// Centered in the window, for dialogs
+    #[default]
+    Window,
+    /// The on-screen rectangle of the node (follows it when it's scrolled or moved)
+    Node(WeakSharedNode),
+    /// A point in window coordinates, like the pointer location
+    Point(Location)
+}
+
+/// Side of the anchor the overlay is placed on.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    #[default]
+    Bottom,
+    Left,
+    Right
+}
+
+/// Alignment of the overlay along the side of the anchor.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PlacementAlign {
+    /// Left or top edges are aligned
+    #[default]
+    Start,
+    Center,
+    /// Right or bottom edges are aligned
+    End
+}
+
+/// Placement of an overlay relative to its anchor. Default is below the anchor, aligned to its left edge.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Placement {
+    pub side: Side,
+    pub align: PlacementAlign
+}
+
+impl Placement {
+    pub fn new(side: Side, align: PlacementAlign) -> Placement {
+        Placement { side, align }
+    }
+}
+
+/// Node shown above the whole UI, like popups, menus and tooltips. Overlays aren't clipped or laid out by the main tree:
+/// each one is laid out on its own (sized to its content unless its style says otherwise) and positioned against its anchor.
+///
+/// ```rust
+/// let handle = open_overlay(Overlay::new(menu).anchor(Anchor::Node(Arc::downgrade(&button))).dismiss_on_click_outside(|| {}));
+/// // later
+/// handle.close();
+/// ```
+pub struct Overlay {
+    pub node: SharedNode,
+    pub anchor: Anchor,
+    pub placement: Placement,
+    /// Distance between the anchor and the overlay in pixels
+    pub offset: f32,
+    /// Moves the overlay to the opposite side of the anchor if it doesn't fit on its side. Default is true
+    pub flip: bool,
+    /// Closes the overlay when the mouse is pressed outside of it (and outside of the anchor node)
+    pub dismiss_on_click_outside: bool,
+    /// Called when the overlay is closed by the user (not when closed through [`OverlayHandle::close`])
+    pub on_dismiss: Option<Box<dyn FnMut() + Send>>
+}
+
+impl Overlay {
+    pub fn new(node: SharedNode) -> Overlay {
+        Overlay {
+            node,
+            anchor: Anchor::Window,
+            placement: Placement::default(),
+            offset: 0.,
+            flip: true,
+            dismiss_on_click_outside: false,
+            on_dismiss: None
+        }
+    }
+    pub fn anchor(mut self, anchor: Anchor) -> Overlay {
+        self.anchor = anchor;
+        self
+    }
+    pub fn placement(mut self, placement: Placement) -> Overlay {
+        self.placement = placement;
+        self
+    }
+    pub fn offset(mut self, offset: f32) -> Overlay {
+        self.offset = offset;
+        self
+    }
+    pub fn flip(mut self, flip: bool) -> Overlay {
+        self.flip = flip;
+        self
+    }
+    /// Closes the overlay on clicks outside of it, calling the callback.
+    pub fn dismiss_on_click_outside(mut self, on_dismiss: impl FnMut() + Send + 'static) -> Overlay {
+        self.dismiss_on_click_outside = true;
+        self.on_dismiss = Some(Box::new(on_dismiss));
+        self
+    }
+}
+
+impl Debug for Overlay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Overlay")
+            .field("node", &self.node)
+            .field("anchor", &self.anchor)
+            .field("placement", &self.placement)
+            .field("offset", &self.offset)
+            .field("flip", &self.flip)
+            .field("dismiss_on_click_outside", &self.dismiss_on_click_outside)
+            .finish()
+    }
+}
+
+/// Handle of an open overlay, returned by [`open_overlay`]. Dropping the handle doesn't close the overlay.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OverlayHandle(u64);
+
+impl OverlayHandle {
+    /// Closes the overlay in the next frame. Does nothing if it's closed already.
+    pub fn close(&self) {
+        OVERLAY_REQUESTS.lock().unwrap().push(OverlayRequest::Close(self.0));
+        crate::request_frame();
+    }
+}
+
+enum OverlayRequest {
+    Open(u64, Overlay),
+    Close(u64)
+}
+
+/// Shows the node above the UI, starting with the next frame. Can be called from any thread.
+/// Overlays opened later are shown above the earlier ones.
+pub fn open_overlay(overlay: Overlay) -> OverlayHandle {
+    let id = NEXT_OVERLAY_ID.fetch_add(1, Ordering::Relaxed);
+    OVERLAY_REQUESTS.lock().unwrap().push(OverlayRequest::Open(id, overlay));
+    crate::request_frame();
+    OverlayHandle(id)
+}
+
+struct OpenOverlay {
+    id: u64,
+    overlay: Overlay,
+    /// Top left corner in window coordinates, updated on each frame
+    position: Location
+}
+
+/// Overlays shown above the root node, owned by the event loop.
+#[derive(Default)]
+pub(crate) struct OverlayLayer {
+    overlays: Vec<OpenOverlay>
+}
+
+impl OverlayLayer {
+    /// Handles requests from [`open_overlay`] and [`OverlayHandle::close`]. Returns true if the overlays changed
+    /// (and the layout needs to be recomputed).
+    pub(crate) fn apply_requests(&mut self) -> bool {
+        let requests = std::mem::take(&mut *OVERLAY_REQUESTS.lock().unwrap());
+        let changed = !requests.is_empty();
+        for request in requests {
+            match request {
+                OverlayRequest::Open(id, overlay) => self.overlays.push(OpenOverlay { id, overlay, position: Location::new(0., 0.) }),
+                OverlayRequest::Close(id) => self.overlays.retain(|open| open.id != id)
+            }
+        }
+        changed
+    }
+
+    /// Root nodes of the overlays, from the bottom one to the top one.
+    pub(crate) fn nodes(&self) -> Vec<SharedNode> {
+        self.overlays.iter().map(|open| open.overlay.node.clone()).collect()
+    }
+
+    /// Returns the window position of the overlay with the root node.
+    pub(crate) fn position_of(&self, node: &SharedNode) -> Option<Location> {
+        self.overlays.iter()
+            .find(|open| Arc::ptr_eq(&open.overlay.node, node))
+            .map(|open| open.position)
+    }
+}
+
+/// Returns the rectangle (top left corner and size) of the anchor in window coordinates.
+fn anchor_rect(anchor: &Anchor, context: &RenderContext) -> Option<(Location, Size<f32>)> {
+    match anchor {
+        Anchor::Window => Some((Location::new(0., 0.), context.window_size)),
+        Anchor::Point(point) => Some((*point, Size::ZERO)),
+        Anchor::Node(node) => {
+            let node = node.upgrade()?;
+            let location = window_location(&node, context)?;
+            let layout = context.taffy.layout(*context.node_layout.get(&node)?).ok()?;
+            Some((location, layout.size))
+        }
+    }
+}
+
+/// Returns the position of an overlay of the size, placed against the anchor rectangle and kept inside the window.
+fn place(overlay: &Overlay, anchor: (Location, Size<f32>), size: Size<f32>, window: Size<f32>) -> Location {
+    let (anchor, anchor_size) = anchor;
+    if let Anchor::Window = overlay.anchor {
+        return Location::new((window.width - size.width) / 2., (window.height - size.height) / 2.);
+    }
+    let mut side = overlay.placement.side;
+    if overlay.flip {
+        // space on each side of the anchor
+        let (available, opposite, opposite_available) = match side {
+            Side::Top => (anchor.y, Side::Bottom, window.height - anchor.y - anchor_size.height),
+            Side::Bottom => (window.height - anchor.y - anchor_size.height, Side::Top, anchor.y),
+            Side::Left => (anchor.x, Side::Right, window.width - anchor.x - anchor_size.width),
+            Side::Right => (window.width - anchor.x - anchor_size.width, Side::Left, anchor.x)
+        };
+        let needed = match side {
+            Side::Top | Side::Bottom => size.height,
+            Side::Left | Side::Right => size.width
+        } + overlay.offset;
+        if available < needed && opposite_available > available {
+            side = opposite;
+        }
+    }
+    let align = |start: f32, anchor_length: f32, length: f32| match overlay.placement.align {
+        PlacementAlign::Start => start,
+        PlacementAlign::Center => start + (anchor_length - length) / 2.,
+        PlacementAlign::End => start + anchor_length - length
+    };
+    let position = match side {
+        Side::Top => Location::new(align(anchor.x, anchor_size.width, size.width), anchor.y - overlay.offset - size.height),
+        Side::Bottom => Location::new(align(anchor.x, anchor_size.width, size.width), anchor.y + anchor_size.height + overlay.offset),
+        Side::Left => Location::new(anchor.x - overlay.offset - size.width, align(anchor.y, anchor_size.height, size.height)),
+        Side::Right => Location::new(anchor.x + anchor_size.width + overlay.offset, align(anchor.y, anchor_size.height, size.height))
+    };
+    Location::new(
+        position.x.min(window.width - size.width).max(0.),
+        position.y.min(window.height - size.height).max(0.)
+    )
+}
+
+/// Positions the overlays against their anchors. Needs to be called after the layout is computed.
+pub(crate) fn place_overlays(context: &mut RenderContext) {
+    let mut overlays = std::mem::take(&mut context.overlays.overlays);
+    for open in overlays.iter_mut() {
+        let Some(taffy_node) = context.node_layout.get(&open.overlay.node) else { continue; };
+        let size = context.taffy.layout(*taffy_node).unwrap().size;
+        // an anchor node which was removed keeps the overlay where it was
+        if let Some(anchor) = anchor_rect(&open.overlay.anchor, context) {
+            open.position = place(&open.overlay, anchor, size, context.window_size);
+        }
+    }
+    // overlays opened while placing (there shouldn't be any) are kept above
+    overlays.append(&mut context.overlays.overlays);
+    context.overlays.overlays = overlays;
+}
+
+/// Renders the overlays above the main tree.
+pub(crate) fn render_overlays(context: &mut RenderContext) {
+    let overlays: Vec<_> = context.overlays.overlays.iter().map(|open| (open.overlay.node.clone(), open.position)).collect();
+    for (node, position) in overlays {
+        context.canvas.save();
+        context.canvas.translate(position.x, position.y);
+        render_recursively(&node, context);
+        context.canvas.restore();
+    }
+}
+
+/// Returns the path to the node at the location, looking at the overlays (from the top one) and fixed nodes before the main tree.
+pub(crate) fn get_target_at(root: &SharedNode, context: &RenderContext, location: Location) -> Option<Vec<SharedNode>> {
+    for open in context.overlays.overlays.iter().rev() {
+        if let Some(path) = get_element_at(&open.overlay.node, context, location - open.position) {
+            return Some(path);
+        }
+    }
+    // fixed nodes can be outside of their parents, which hit testing otherwise assumes not to happen
+    for fixed in context.fixed_nodes.iter().rev().filter_map(|node| node.upgrade()) {
+        let Some(parent) = fixed.lock().unwrap().parent() else { continue; };
+        let Some(parent_location) = window_location(&parent, context) else { continue; };
+        let Some(parent_layout) = context.node_layout.get(&parent).and_then(|node| context.taffy.layout(*node).ok()) else { continue; };
+        let scroll = Location::from(scroll_offset(&context.resolve_node_style(&parent, parent.lock().unwrap().style()), parent_layout));
+        if let Some(path) = get_element_at(&fixed, context, location - parent_location + scroll) {
+            let mut ancestors = get_path_to(&parent);
+            ancestors.extend(path);
+            return Some(ancestors);
+        }
+    }
+    get_element_at(root, context, location)
+}
+
+/// Closes overlays dismissed by a mouse press on the path (empty when nothing was hit), calling their `on_dismiss` callbacks.
+/// Overlays containing the path, or whose anchor node does, stay open.
+pub(crate) fn dismiss_on_click(context: &mut RenderContext, path: &[SharedNode]) {
+    let contains = |node: &SharedNode| path.iter().any(|ancestor| Arc::ptr_eq(ancestor, node));
+    let mut dismissed = Vec::new();
+    let count = context.overlays.overlays.len();
+    context.overlays.overlays.retain_mut(|open| {
+        let overlay = &open.overlay;
+        let anchor_hit = match &overlay.anchor {
+            Anchor::Node(anchor) => anchor.upgrade().is_some_and(|anchor| contains(&anchor)),
+            _ => false
+        };
+        if !overlay.dismiss_on_click_outside || contains(&overlay.node) || anchor_hit {
+            return true;
+        }
+        if let Some(on_dismiss) = open.overlay.on_dismiss.take() {
+            dismissed.push(on_dismiss);
+        }
+        false
+    });
+    if context.overlays.overlays.len() != count {
+        context.request_relayout();
+    }
+    // called after the overlays are removed, so that callbacks can open new ones
+    for mut on_dismiss in dismissed {
+        on_dismiss();
+    }
+}
@@ -1,7 +1,7 @@
 use femtovg::{Color, Paint, Path};
 use taffy::{Layout, Size};
 use crate::{nodes::{Node, NodeChildren, RenderContext, Style}, events::handler::EventHandlerDatabase, WeakNode, SharedNode};
-use crate::nodes::CanvasRenderer;
+use crate::nodes::{BorderRadius, CanvasRenderer};
 
 #[derive(Default, Debug)]
 pub struct Rectangle {
@@ -24,11 +24,18 @@ impl Node for Rectangle {
     fn style(&self) -> &Style {
         &self.style
     }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
     fn children(&self) -> Option<&NodeChildren> {
         None
     }
     fn render_pre_children(&mut self, context: &mut RenderContext, layout: Layout) {
-        draw_rect(layout.size, self.style.background.as_ref().unwrap_or(&Paint::color(Color::black())), self.style.border_radius, &mut context.canvas);
+        let style = context.resolve_style(&self.style);
+        match &style.background {
+            Some(_) => draw_background(&style, layout.size, context),
+            None => draw_rect(layout.size, &Paint::color(Color::black()), style.border_radius, &mut context.canvas)
+        }
     }
     fn event_handlers(&self) -> Option<crate::events::handler::InnerEventHandlerDataset> {
         Some(self.events.handlers.clone())
@@ -44,17 +51,40 @@ impl Node for Rectangle {
     }
 }
 
-pub fn draw_rect(size: Size<f32>, fill: &Paint, radius: f32, canvas: &mut CanvasRenderer) {
+/// Draws the background of the node (if it has any), clipped to its border radius.
+pub fn draw_background(style: &Style, size: Size<f32>, context: &mut RenderContext) {
+    if let Some(background) = &style.background {
+        background.draw(context, size, &style.border_radius.to_path(size));
+    }
+}
+
+/// Width of the ring drawn around focused controls, in pixels
+pub const FOCUS_RING_WIDTH: f32 = 2.;
+/// Opacity of disabled controls and their content
+pub const DISABLED_ALPHA: f32 = 0.5;
+
+/// Draws a ring in the `focus` color of the theme around a node of the size, following its border radius.
+/// The ring is drawn outside of the node, so it doesn't cover its content.
+pub fn draw_focus_ring(radius: &BorderRadius, size: Size<f32>, context: &mut RenderContext) {
     let mut path = Path::new();
-    path.rounded_rect(
-        0.,
-        0.,
-        size.width,
-        size.height,
-        radius
-    );
+    let offset = FOCUS_RING_WIDTH;
+    radius.add_to_path(&mut path, -offset, -offset, size.width + offset * 2., size.height + offset * 2.);
+    let paint = Paint::color(context.theme.get_color("focus")).with_line_width(FOCUS_RING_WIDTH);
+    context.canvas.stroke_path(&path, &paint);
+}
+
+/// Draws the background of a control. Disabled controls are drawn with [`DISABLED_ALPHA`], along with their children:
+/// the opacity stays set on the canvas until its state is restored after the node is rendered.
+pub(crate) fn draw_control_background(style: &Style, size: Size<f32>, context: &mut RenderContext) {
+    if style.disabled {
+        context.canvas.set_global_alpha(DISABLED_ALPHA);
+    }
+    draw_background(style, size, context);
+}
+
+pub fn draw_rect(size: Size<f32>, fill: &Paint, radius: BorderRadius, canvas: &mut CanvasRenderer) {
     canvas.fill_path(
-        &path,
+        &radius.to_path(size),
         fill
     );
 }
\ No newline at end of file
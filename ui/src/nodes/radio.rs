@@ -0,0 +1,338 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use femtovg::Path;
+use taffy::{FlexDirection, Layout, LengthPercentage, Size};
+use crate::{nodes::{Node, NodeChildren, Style, TaffyStyle}, events::handler::EventHandlerDatabase, WeakNode, SharedNode};
+use crate::events::{ControlValue, InnerEvent, MouseButton, NamedKey, NodeEvent};
+use crate::nodes::checkbox::{indicator_style, label, translate_to_indicator, ToggleColors, INDICATOR_SIZE};
+use crate::nodes::primitives::{draw_control_background, draw_focus_ring};
+use crate::nodes::{BorderRadius, RenderContext};
+
+/// Width of the outline of unselected radio buttons, in pixels
+const BORDER_WIDTH: f32 = 1.5;
+
+/// Option of a [`RadioGroup`], with an optional label (its children). Selection is handled by the group.
+pub struct Radio {
+    pub style: Style,
+    pub children: NodeChildren,
+    pub events: EventHandlerDatabase,
+    pub parent: Option<WeakNode>,
+    /// Value sent with the change event of the group when the radio button is selected
+    pub value: String,
+    pub colors: ToggleColors,
+    /// Set by the group
+    checked: bool,
+    /// Set by the group when the focus ring should be drawn around this radio button
+    focused: bool
+}
+
+impl Radio {
+    pub fn new(value: impl Into<String>) -> Radio {
+        Radio {
+            style: indicator_style(INDICATOR_SIZE),
+            children: NodeChildren::default(),
+            events: EventHandlerDatabase::default(),
+            parent: None,
+            value: value.into(),
+            colors: ToggleColors::default(),
+            checked: false,
+            focused: false
+        }
+    }
+
+    /// Radio button followed by a text label, colored with the `text` token.
+    pub fn with_label(value: impl Into<String>, text: impl Into<String>) -> Radio {
+        let mut radio = Radio::new(value);
+        radio.children.push(label(text.into()));
+        radio
+    }
+
+    pub fn style(mut self, style: Style) -> Radio {
+        self.style = style;
+        self
+    }
+
+    pub fn colors(mut self, colors: ToggleColors) -> Radio {
+        self.colors = colors;
+        self
+    }
+
+    /// Returns true if the radio button is selected in its group.
+    pub fn checked(&self) -> bool {
+        self.checked
+    }
+}
+
+impl Debug for Radio {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Radio")
+            .field("style", &self.style)
+            .field("value", &self.value)
+            .field("checked", &self.checked)
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+impl Node for Radio {
+    fn style(&self) -> &Style {
+        &self.style
+    }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
+    fn children(&self) -> Option<&NodeChildren> {
+        Some(&self.children)
+    }
+
+    fn render_pre_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        let style = context.resolve_style(&self.style);
+        draw_control_background(&style, layout.size, context);
+        let hovered = context.style_environment().state.hovered;
+        let size = Size { width: INDICATOR_SIZE, height: INDICATOR_SIZE };
+        let radius = INDICATOR_SIZE / 2.;
+        context.canvas.save();
+        translate_to_indicator(size, &layout, context);
+        let mut circle = Path::new();
+        circle.circle(radius, radius, radius);
+        if self.checked {
+            context.canvas.fill_path(&circle, &self.colors.checked.paint(&context.theme));
+            let mut dot = Path::new();
+            dot.circle(radius, radius, radius * 0.4);
+            context.canvas.fill_path(&dot, &self.colors.mark.paint(&context.theme));
+        } else {
+            context.canvas.fill_path(&circle, &self.colors.unchecked.paint(&context.theme));
+            let mut outline = Path::new();
+            outline.circle(radius, radius, radius - BORDER_WIDTH / 2.);
+            let border = if hovered { &self.colors.checked } else { &self.colors.border };
+            context.canvas.stroke_path(&outline, &border.paint(&context.theme).with_line_width(BORDER_WIDTH));
+        }
+        if self.focused {
+            draw_focus_ring(&BorderRadius::all(radius), size, context);
+        }
+        context.canvas.restore();
+    }
+
+    fn add_child_at(&mut self, child: SharedNode, index: usize) -> Result<(), super::ChildAddError> {
+        let mut index = index;
+        if let Some(i) = self.has_child(&child) {
+            self.children.remove(i);
+            if i < index {
+                index -= 1;
+            }
+        }
+        self.children.insert(index, child);
+        Ok(())
+    }
+
+    fn event_handlers(&self) -> Option<crate::events::handler::InnerEventHandlerDataset> {
+        Some(self.events.handlers.clone())
+    }
+    fn set_parent(&mut self, parent: Option<WeakNode>) {
+        self.parent = parent;
+    }
+    fn parent(&self) -> Option<SharedNode> {
+        match &self.parent {
+            Some(parent) => parent.upgrade(),
+            None => None
+        }
+    }
+    fn remove_child(&mut self, child: &SharedNode) -> Result<(), super::ChildAddError> {
+        if let Some(i) = self.has_child(child) {
+            self.children.remove(i);
+        }
+        Ok(())
+    }
+}
+
+/// Group of [`Radio`] buttons of which at most one is selected. The group is a single focus stop:
+/// arrow keys move the selection between the enabled radio buttons, Space selects the focused one.
+/// Selecting a radio button sends [`InnerEvent::Change`] with [`ControlValue::Selected`] from the group.
+pub struct RadioGroup {
+    pub style: Style,
+    pub events: EventHandlerDatabase,
+    pub parent: Option<WeakNode>,
+    /// Index of the selected radio button. Can be changed by the application, no change event is sent then.
+    pub selected: Option<usize>,
+    radios: Vec<Arc<Mutex<Radio>>>,
+    /// Same nodes as `radios`, the group doesn't support adding other children
+    children: NodeChildren,
+    /// Radio button the primary mouse button was pressed on
+    pressed: Option<usize>
+}
+
+impl RadioGroup {
+    pub fn new(radios: Vec<Radio>) -> RadioGroup {
+        let radios: Vec<_> = radios.into_iter().map(|radio| Arc::new(Mutex::new(radio))).collect();
+        RadioGroup {
+            style: RadioGroup::default_style(),
+            events: EventHandlerDatabase::default(),
+            parent: None,
+            selected: None,
+            children: radios.iter().map(|radio| radio.clone() as SharedNode).collect(),
+            radios,
+            pressed: None
+        }
+    }
+
+    pub fn selected(mut self, selected: Option<usize>) -> RadioGroup {
+        self.selected = selected;
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> RadioGroup {
+        self.style = style;
+        self
+    }
+
+    /// Stacks the radio buttons in a column.
+    pub fn default_style() -> Style {
+        Style {
+            layout: TaffyStyle {
+                flex_direction: FlexDirection::Column,
+                gap: Size { width: LengthPercentage::Length(8.), height: LengthPercentage::Length(8.) },
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    pub fn radios(&self) -> &[Arc<Mutex<Radio>>] {
+        &self.radios
+    }
+
+    fn is_enabled(&self, index: usize) -> bool {
+        !self.radios[index].lock().unwrap().style.disabled
+    }
+
+    /// Returns the index of the radio button in the path of the event.
+    fn radio_in_path(&self, path: &[SharedNode]) -> Option<usize> {
+        self.children.iter().position(|child| path.iter().any(|node| Arc::ptr_eq(node, child)))
+    }
+
+    /// Radio button which shows the focus ring: the selected one, or the first enabled one.
+    fn focus_index(&self) -> Option<usize> {
+        self.selected.or_else(|| (0..self.radios.len()).find(|index| self.is_enabled(*index)))
+    }
+
+    fn select(&mut self, index: usize, context: &mut RenderContext) {
+        context.request_frame();
+        if self.selected == Some(index) {
+            return;
+        }
+        self.selected = Some(index);
+        let value = self.radios[index].lock().unwrap().value.clone();
+        context.emit(InnerEvent::Change(ControlValue::Selected { index, value }));
+    }
+
+    /// Selects the next (or previous) enabled radio button, wrapping around.
+    fn select_next(&mut self, backwards: bool, context: &mut RenderContext) {
+        let count = self.radios.len();
+        if count == 0 {
+            return;
+        }
+        let start = self.focus_index().unwrap_or(0);
+        let next = (1..=count)
+            .map(|offset| if backwards { (start + count * 2 - offset) % count } else { (start + offset) % count })
+            .find(|index| self.is_enabled(*index));
+        if let Some(index) = next {
+            self.select(index, context);
+        }
+    }
+}
+
+impl Debug for RadioGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RadioGroup")
+            .field("style", &self.style)
+            .field("selected", &self.selected)
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+impl Node for RadioGroup {
+    fn style(&self) -> &Style {
+        &self.style
+    }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
+    fn children(&self) -> Option<&NodeChildren> {
+        Some(&self.children)
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn prepare_render(&mut self, context: &mut RenderContext) {
+        let focused = context.style_environment().state.focused;
+        let focus_index = self.focus_index();
+        for (index, radio) in self.radios.iter().enumerate() {
+            let mut radio = radio.lock().unwrap();
+            radio.checked = self.selected == Some(index);
+            radio.focused = focused && focus_index == Some(index);
+        }
+    }
+
+    fn on_event(&mut self, event: &NodeEvent, context: &mut RenderContext) {
+        if self.style.disabled {
+            self.pressed = None;
+            return;
+        }
+        let state = context.style_environment().state;
+        match &event.event {
+            InnerEvent::MouseDown(mouse) if mouse.button == Some(MouseButton::Left) => {
+                if context.is_handled() { return; }
+                let Some(index) = self.radio_in_path(&event.path) else { return; };
+                if !self.is_enabled(index) { return; }
+                self.pressed = Some(index);
+                context.capture_pointer();
+                context.mark_handled();
+            },
+            InnerEvent::MouseUp(mouse) if mouse.button == Some(MouseButton::Left) => {
+                let Some(index) = self.pressed.take() else { return; };
+                context.release_pointer();
+                context.mark_handled();
+                if context.interaction_state(&self.children[index]).hovered {
+                    self.select(index, context);
+                }
+            },
+            InnerEvent::KeyDown(key) if state.focused => {
+                if key.is_named(NamedKey::ArrowDown) || key.is_named(NamedKey::ArrowRight) {
+                    self.select_next(false, context);
+                } else if key.is_named(NamedKey::ArrowUp) || key.is_named(NamedKey::ArrowLeft) {
+                    self.select_next(true, context);
+                } else if key.is_named(NamedKey::Space) {
+                    if let Some(index) = self.focus_index() {
+                        self.select(index, context);
+                    }
+                } else {
+                    return;
+                }
+                context.mark_handled();
+            },
+            InnerEvent::Focus | InnerEvent::Blur => context.request_frame(),
+            _ => {}
+        }
+    }
+
+    fn render_pre_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        let style = context.resolve_style(&self.style);
+        draw_control_background(&style, layout.size, context);
+    }
+
+    fn event_handlers(&self) -> Option<crate::events::handler::InnerEventHandlerDataset> {
+        Some(self.events.handlers.clone())
+    }
+    fn set_parent(&mut self, parent: Option<WeakNode>) {
+        self.parent = parent;
+    }
+    fn parent(&self) -> Option<SharedNode> {
+        match &self.parent {
+            Some(parent) => parent.upgrade(),
+            None => None
+        }
+    }
+}
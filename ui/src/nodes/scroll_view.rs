@@ -0,0 +1,567 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use femtovg::{Color, Paint, Path};
+use taffy::{Layout, Overflow, Point};
+use crate::{nodes::{Node, NodeChildren, Style}, events::handler::EventHandlerDatabase, WeakNode, SharedNode};
+use crate::events::{InnerEvent, Location, MouseButton, MouseScrollDelta, NodeEvent, TouchPhase};
+use crate::nodes::primitives::draw_background;
+use crate::nodes::{scroll_offset, RenderContext};
+
+/// Space between the scrollbar thumb and the edge of the scroll view, in pixels
+const SCROLLBAR_MARGIN: f32 = 2.;
+/// Minimal length of the scrollbar thumb, in pixels
+const MIN_THUMB_LENGTH: f32 = 20.;
+/// Kinetic scrolling stops when the velocity drops below this value (pixels per second)
+const MIN_VELOCITY: f32 = 20.;
+/// Touchpad samples older than this (in seconds) are not used to start kinetic scrolling
+const MAX_SAMPLE_AGE: f32 = 0.05;
+/// Duration of [`ScrollBehavior::Smooth`] scrolling
+const SMOOTH_SCROLL_DURATION: Duration = Duration::from_millis(250);
+
+/// How to align a node when scrolling it into view, along one axis.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum ScrollAlignment {
+    /// Align the start of the node with the start of the scrolled area
+    Start,
+    /// Center the node in the scrolled area
+    Center,
+    /// Align the end of the node with the end of the scrolled area
+    End,
+    /// Scroll as little as possible - don't scroll if the node is visible already.
+    /// Nodes larger than the scrolled area are aligned to the start.
+    #[default]
+    Nearest
+}
+
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum ScrollBehavior {
+    /// Jump to the new offset in the next frame
+    #[default]
+    Instant,
+    /// Animate to the new offset
+    Smooth
+}
+
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct ScrollIntoViewOptions {
+    /// Alignment along the y-axis
+    pub block: ScrollAlignment,
+    /// Alignment along the x-axis
+    pub inline: ScrollAlignment,
+    pub behavior: ScrollBehavior
+}
+
+enum ScrollRequest {
+    IntoView(WeakNode, ScrollIntoViewOptions),
+    To(WeakNode, Point<f32>, ScrollBehavior)
+}
+
+pub(crate) struct ScrollAnimation {
+    node: WeakNode,
+    from: Point<f32>,
+    to: Point<f32>,
+    start: Instant
+}
+
+lazy_static::lazy_static! {
+    static ref SCROLL_REQUESTS: Mutex<Vec<ScrollRequest>> = Mutex::new(Vec::new());
+}
+
+/// Scrolls all scrollable ancestors (with [`Overflow::Scroll`]) of the node so that the node is visible.
+///
+/// Scrolling happens in the next frame, after the layout is computed. Can be called from any thread, but a node
+/// which was just added from another thread needs a message to the `render` channel of [`crate::MainEntry`] to be laid out.
+pub fn scroll_into_view(node: &SharedNode, options: ScrollIntoViewOptions) {
+    SCROLL_REQUESTS.lock().unwrap().push(ScrollRequest::IntoView(Arc::downgrade(node), options));
+    crate::request_frame();
+}
+
+/// Sets the scroll offset of the node, clamped to the scrollable area. The node needs to support [`Node::style_mut`].
+///
+/// Like [`scroll_into_view`], scrolling happens in the next frame.
+pub fn scroll_to(node: &SharedNode, offset: Point<f32>, behavior: ScrollBehavior) {
+    SCROLL_REQUESTS.lock().unwrap().push(ScrollRequest::To(Arc::downgrade(node), offset, behavior));
+    crate::request_frame();
+}
+
+/// Handles requests from [`scroll_into_view`] and [`scroll_to`]. Needs to be called after the layout is computed.
+pub(crate) fn run_scroll_requests(context: &mut RenderContext) {
+    let requests = std::mem::take(&mut *SCROLL_REQUESTS.lock().unwrap());
+    for request in requests {
+        match request {
+            ScrollRequest::IntoView(node, options) => {
+                if let Some(node) = node.upgrade() {
+                    scroll_into_view_now(&node, options, context);
+                }
+            },
+            ScrollRequest::To(node, offset, behavior) => {
+                let Some(node) = node.upgrade() else { continue; };
+                let Some(taffy_node) = context.node_layout.get(&node) else { continue; };
+                let layout = *context.taffy.layout(*taffy_node).unwrap();
+                let mut locked = node.lock().unwrap();
+                let from = scroll_offset(locked.style(), &layout);
+                let to = Point {
+                    x: offset.x.min(layout.content_size.width - layout.size.width).max(0.),
+                    y: offset.y.min(layout.content_size.height - layout.size.height).max(0.)
+                };
+                start_scroll(&node, &mut *locked, from, to, behavior, context);
+            }
+        }
+    }
+}
+
+/// Advances smooth scrolling animations. Needs to be called after the layout is computed.
+pub(crate) fn run_scroll_animations(context: &mut RenderContext) {
+    let animations = std::mem::take(&mut context.scroll_animations);
+    for animation in animations {
+        let Some(node) = animation.node.upgrade() else { continue; };
+        let progress = (context.frame_time.duration_since(animation.start).as_secs_f32() / SMOOTH_SCROLL_DURATION.as_secs_f32()).min(1.);
+        // ease out cubic
+        let eased = 1. - (1. - progress).powi(3);
+        let offset = Point {
+            x: animation.from.x + (animation.to.x - animation.from.x) * eased,
+            y: animation.from.y + (animation.to.y - animation.from.y) * eased
+        };
+        let mut locked = node.lock().unwrap();
+        set_scroll(&node, &mut *locked, offset, context);
+        drop(locked);
+        if progress < 1. {
+            context.scroll_animations.push(animation);
+            context.request_frame();
+        }
+    }
+}
+
+/// Stops smooth scrolling animations, for example when the user scrolls by themselves.
+pub(crate) fn stop_scroll_animations(context: &mut RenderContext) {
+    context.scroll_animations.clear();
+}
+
+fn scroll_into_view_now(node: &SharedNode, options: ScrollIntoViewOptions, context: &mut RenderContext) {
+    let Some(taffy_node) = context.node_layout.get(node) else { return; };
+    let layout = *context.taffy.layout(*taffy_node).unwrap();
+    let locked = node.lock().unwrap();
+    let offset = locked.style().transform.as_ref().map(|t| t.position).unwrap_or(Point { x: 0., y: 0. });
+    // area to show, relative to the unscrolled content of the current ancestor
+    let (mut x, mut y) = (layout.location.x + offset.x, layout.location.y + offset.y);
+    let (width, height) = (layout.size.width, layout.size.height);
+    let mut parent = locked.parent();
+    drop(locked);
+
+    while let Some(ancestor) = parent {
+        let Some(taffy_node) = context.node_layout.get(&ancestor) else { return; };
+        let layout = *context.taffy.layout(*taffy_node).unwrap();
+        let mut locked = ancestor.lock().unwrap();
+        let style = locked.style();
+        let current = scroll_offset(style, &layout);
+        let mut target = current;
+        if style.layout.overflow.x == Overflow::Scroll {
+            target.x = align(x, width, current.x, layout.size.width, layout.content_size.width, options.inline);
+        }
+        if style.layout.overflow.y == Overflow::Scroll {
+            target.y = align(y, height, current.y, layout.size.height, layout.content_size.height, options.block);
+        }
+        let offset = style.transform.as_ref().map(|t| t.position).unwrap_or(Point { x: 0., y: 0. });
+        if target != current {
+            start_scroll(&ancestor, &mut *locked, current, target, options.behavior, context);
+        }
+        x = x - target.x + layout.location.x + offset.x;
+        y = y - target.y + layout.location.y + offset.y;
+        parent = locked.parent();
+    }
+}
+
+/// Returns the new scroll offset along one axis, so that the area at `start` with `length` is visible according to the alignment.
+fn align(start: f32, length: f32, scroll: f32, size: f32, content: f32, alignment: ScrollAlignment) -> f32 {
+    let target = match alignment {
+        ScrollAlignment::Start => start,
+        ScrollAlignment::Center => start + length / 2. - size / 2.,
+        ScrollAlignment::End => start + length - size,
+        ScrollAlignment::Nearest => {
+            if start < scroll || length > size {
+                start
+            } else if start + length > scroll + size {
+                start + length - size
+            } else {
+                scroll
+            }
+        }
+    };
+    target.min(content - size).max(0.)
+}
+
+fn start_scroll(node: &SharedNode, locked: &mut dyn Node, from: Point<f32>, to: Point<f32>, behavior: ScrollBehavior, context: &mut RenderContext) {
+    let weak = Arc::downgrade(node);
+    context.scroll_animations.retain(|animation| !animation.node.ptr_eq(&weak));
+    match behavior {
+        ScrollBehavior::Instant => set_scroll(node, locked, to, context),
+        ScrollBehavior::Smooth => {
+            context.scroll_animations.push(ScrollAnimation { node: weak, from, to, start: context.frame_time });
+            context.request_frame();
+        }
+    }
+}
+
+fn set_scroll(node: &SharedNode, locked: &mut dyn Node, offset: Point<f32>, context: &mut RenderContext) {
+    let Some(style) = locked.style_mut() else { return; };
+    if style.scroll_x == offset.x && style.scroll_y == offset.y {
+        return;
+    }
+    style.scroll_x = offset.x;
+    style.scroll_y = offset.y;
+    context.queued_events.push((Arc::downgrade(node), InnerEvent::Scroll(Location::new(offset.x, offset.y))));
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ScrollAxis {
+    X,
+    Y
+}
+
+#[derive(Copy, Clone, Debug)]
+struct ScrollbarDrag {
+    axis: ScrollAxis,
+    /// Pointer position along the axis when the drag started
+    start_pointer: f32,
+    /// Scroll offset along the axis when the drag started
+    start_scroll: f32
+}
+
+/// A layout node which scrolls its children.
+///
+/// Handles mouse wheel and touchpad scrolling (including kinetic scrolling after the fingers are lifted)
+/// and draws overlay scrollbars, which can be dragged.
+/// Only axes with [`Overflow::Scroll`] are scrolled, which is the default for both axes.
+/// If you replace the style, remember to set the overflow again.
+///
+/// Scroll offset is stored in [`Style::scroll_x`] and [`Style::scroll_y`] and is kept within the content size.
+/// A [`InnerEvent::Scroll`] event is sent whenever it changes.
+pub struct ScrollView {
+    pub style: Style,
+    pub children: NodeChildren,
+    pub events: EventHandlerDatabase,
+    pub parent: Option<WeakNode>,
+    /// Distance in pixels scrolled by one line of a mouse wheel. Default is 40
+    pub line_height: f32,
+    /// Paint of the scrollbar thumbs. Scrollbars are hidden if None
+    pub scrollbar_fill: Option<Paint>,
+    /// Width of the scrollbar thumbs in pixels. Default is 8
+    pub scrollbar_width: f32,
+    /// Deceleration of kinetic scrolling. Velocity is multiplied by `e^(-friction * seconds)`. Default is 4
+    pub friction: f32,
+    /// Kinetic scrolling velocity in pixels per second
+    velocity: Point<f32>,
+    /// Time of the last touchpad sample, used to estimate the velocity
+    last_sample: Option<Instant>,
+    drag: Option<ScrollbarDrag>,
+    /// Layout from the last render
+    layout: Layout,
+    /// Location of the top left corner relative to the window from the last render
+    origin: Location
+}
+
+impl Default for ScrollView {
+    fn default() -> Self {
+        let mut style = Style::default();
+        style.layout.overflow = Point { x: Overflow::Scroll, y: Overflow::Scroll };
+        ScrollView {
+            style,
+            children: NodeChildren::default(),
+            events: EventHandlerDatabase::default(),
+            parent: None,
+            line_height: 40.,
+            scrollbar_fill: Some(Paint::color(Color::rgba(128, 128, 128, 160))),
+            scrollbar_width: 8.,
+            friction: 4.,
+            velocity: Point { x: 0., y: 0. },
+            last_sample: None,
+            drag: None,
+            layout: Layout::new(),
+            origin: Location::default()
+        }
+    }
+}
+
+impl ScrollView {
+    pub fn new(children: NodeChildren) -> ScrollView {
+        ScrollView {
+            children,
+            ..Default::default()
+        }
+    }
+
+    pub fn style(mut self, style: Style) -> ScrollView {
+        self.style = style;
+        self
+    }
+
+    /// Returns the layout from the last render.
+    pub fn last_layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Returns the maximal scroll offset based on the last layout. Axes which don't scroll have a maximum of 0.
+    pub fn max_scroll(&self) -> Point<f32> {
+        Point {
+            x: if self.scrolls(ScrollAxis::X) { (self.layout.content_size.width - self.layout.size.width).max(0.) } else { 0. },
+            y: if self.scrolls(ScrollAxis::Y) { (self.layout.content_size.height - self.layout.size.height).max(0.) } else { 0. }
+        }
+    }
+
+    /// Sets the scroll offset, clamped to the scrollable area.
+    /// Sends [`InnerEvent::Scroll`] if the offset changed. Returns `true` in that case.
+    pub fn scroll_to(&mut self, x: f32, y: f32, context: &mut RenderContext) -> bool {
+        let max = self.max_scroll();
+        let x = x.min(max.x).max(0.);
+        let y = y.min(max.y).max(0.);
+        if x == self.style.scroll_x && y == self.style.scroll_y {
+            return false;
+        }
+        self.style.scroll_x = x;
+        self.style.scroll_y = y;
+        context.emit(InnerEvent::Scroll(Location::new(x, y)));
+        true
+    }
+
+    fn scrolls(&self, axis: ScrollAxis) -> bool {
+        match axis {
+            ScrollAxis::X => self.style.layout.overflow.x == Overflow::Scroll,
+            ScrollAxis::Y => self.style.layout.overflow.y == Overflow::Scroll
+        }
+    }
+
+    /// Returns the start and length of the scrollbar thumb along the axis, or None if there's nothing to scroll
+    fn thumb(&self, axis: ScrollAxis) -> Option<(f32, f32)> {
+        let (size, content, scroll) = match axis {
+            ScrollAxis::X => (self.layout.size.width, self.layout.content_size.width, scroll_offset(&self.style, &self.layout).x),
+            ScrollAxis::Y => (self.layout.size.height, self.layout.content_size.height, scroll_offset(&self.style, &self.layout).y)
+        };
+        if !self.scrolls(axis) || content <= size {
+            return None;
+        }
+        let track = size - 2. * SCROLLBAR_MARGIN;
+        let length = (size / content * track).max(MIN_THUMB_LENGTH).min(track);
+        let start = SCROLLBAR_MARGIN + scroll / (content - size) * (track - length);
+        Some((start, length))
+    }
+
+    /// Returns the axis of the scrollbar under the location (relative to the scroll view)
+    fn scrollbar_at(&self, location: Location) -> Option<ScrollAxis> {
+        self.scrollbar_fill.as_ref()?;
+        let size = self.layout.size;
+        let bar = self.scrollbar_width + 2. * SCROLLBAR_MARGIN;
+        if self.thumb(ScrollAxis::Y).is_some() && location.x >= size.width - bar && location.x <= size.width {
+            return Some(ScrollAxis::Y);
+        }
+        if self.thumb(ScrollAxis::X).is_some() && location.y >= size.height - bar && location.y <= size.height {
+            return Some(ScrollAxis::X);
+        }
+        None
+    }
+
+    fn scroll_by(&mut self, x: f32, y: f32, context: &mut RenderContext) -> bool {
+        self.scroll_to(self.style.scroll_x + x, self.style.scroll_y + y, context)
+    }
+
+    fn handle_wheel(&mut self, delta: MouseScrollDelta, phase: TouchPhase, context: &mut RenderContext) {
+        let (x, y) = match delta {
+            MouseScrollDelta::LineDelta(x, y) => (x * self.line_height, y * self.line_height),
+            MouseScrollDelta::PixelDelta(position) => (position.x as f32, position.y as f32)
+        };
+        // wheel deltas are positive when scrolling up, scroll offset is positive when scrolled down
+        let (x, y) = (-x, -y);
+        if matches!(delta, MouseScrollDelta::PixelDelta(_)) {
+            let now = Instant::now();
+            match phase {
+                TouchPhase::Started | TouchPhase::Cancelled => {
+                    self.velocity = Point { x: 0., y: 0. };
+                    self.last_sample = None;
+                },
+                TouchPhase::Moved => {
+                    if let Some(last_sample) = self.last_sample {
+                        let elapsed = now.duration_since(last_sample).as_secs_f32();
+                        if elapsed > 0. {
+                            // smooth the velocity as touchpad samples are noisy
+                            self.velocity.x = self.velocity.x * 0.2 + x / elapsed * 0.8;
+                            self.velocity.y = self.velocity.y * 0.2 + y / elapsed * 0.8;
+                        }
+                    }
+                    self.last_sample = Some(now);
+                },
+                TouchPhase::Ended => {
+                    let recent = self.last_sample
+                        .map(|last_sample| now.duration_since(last_sample).as_secs_f32() < MAX_SAMPLE_AGE)
+                        .unwrap_or(false);
+                    if !recent {
+                        self.velocity = Point { x: 0., y: 0. };
+                    }
+                    self.last_sample = None;
+                    context.request_frame();
+                }
+            }
+        } else {
+            self.velocity = Point { x: 0., y: 0. };
+        }
+        if self.scroll_by(x, y, context) {
+            context.mark_handled();
+        }
+    }
+}
+
+impl Debug for ScrollView {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScrollView")
+            .field("style", &self.style)
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+impl Node for ScrollView {
+    fn style(&self) -> &Style {
+        &self.style
+    }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
+    fn children(&self) -> Option<&NodeChildren> {
+        Some(&self.children)
+    }
+
+    fn prepare_render(&mut self, context: &mut RenderContext) {
+        if self.drag.is_some() || self.last_sample.is_some() || (self.velocity.x == 0. && self.velocity.y == 0.) {
+            return;
+        }
+        // long frames (like the first one after idling) would make the content jump
+        let elapsed = context.frame_delta.as_secs_f32().min(1. / 30.);
+        let moved = self.scroll_by(self.velocity.x * elapsed, self.velocity.y * elapsed, context);
+        let decay = (-self.friction * elapsed).exp();
+        self.velocity.x *= decay;
+        self.velocity.y *= decay;
+        if !moved || (self.velocity.x.abs() < MIN_VELOCITY && self.velocity.y.abs() < MIN_VELOCITY) {
+            self.velocity = Point { x: 0., y: 0. };
+        } else {
+            context.request_frame();
+        }
+    }
+
+    fn on_event(&mut self, event: &NodeEvent, context: &mut RenderContext) {
+        match &event.event {
+            InnerEvent::Wheel { delta, phase, .. } if !context.is_handled() => {
+                self.handle_wheel(*delta, *phase, context);
+            },
+            InnerEvent::MouseDown(mouse) if mouse.button == Some(MouseButton::Left) => {
+                if context.is_handled() { return; }
+                let location = mouse.client - self.origin;
+                let Some(axis) = self.scrollbar_at(location) else { return; };
+                let (pointer, scroll) = match axis {
+                    ScrollAxis::X => (location.x, self.style.scroll_x),
+                    ScrollAxis::Y => (location.y, self.style.scroll_y)
+                };
+                self.velocity = Point { x: 0., y: 0. };
+                self.drag = Some(ScrollbarDrag { axis, start_pointer: pointer, start_scroll: scroll });
+                if let Some((start, length)) = self.thumb(axis) {
+                    if pointer < start || pointer > start + length {
+                        // clicked on the track - jump so that the thumb is centered on the pointer, then continue dragging
+                        let size = match axis {
+                            ScrollAxis::X => self.layout.size.width,
+                            ScrollAxis::Y => self.layout.size.height
+                        };
+                        let max = match axis {
+                            ScrollAxis::X => self.max_scroll().x,
+                            ScrollAxis::Y => self.max_scroll().y
+                        };
+                        let track = size - 2. * SCROLLBAR_MARGIN - length;
+                        let target = ((pointer - SCROLLBAR_MARGIN - length / 2.) / track * max).min(max).max(0.);
+                        match axis {
+                            ScrollAxis::X => self.scroll_to(target, self.style.scroll_y, context),
+                            ScrollAxis::Y => self.scroll_to(self.style.scroll_x, target, context)
+                        };
+                        self.drag = Some(ScrollbarDrag { axis, start_pointer: pointer, start_scroll: target });
+                    }
+                }
+                context.capture_pointer();
+                context.mark_handled();
+            },
+            InnerEvent::MouseMove(mouse) => {
+                let Some(drag) = self.drag else { return; };
+                let Some((_, length)) = self.thumb(drag.axis) else { return; };
+                let location = mouse.client - self.origin;
+                let (pointer, size, max) = match drag.axis {
+                    ScrollAxis::X => (location.x, self.layout.size.width, self.max_scroll().x),
+                    ScrollAxis::Y => (location.y, self.layout.size.height, self.max_scroll().y)
+                };
+                let track = size - 2. * SCROLLBAR_MARGIN - length;
+                if track <= 0. { return; }
+                let target = drag.start_scroll + (pointer - drag.start_pointer) / track * max;
+                match drag.axis {
+                    ScrollAxis::X => self.scroll_to(target, self.style.scroll_y, context),
+                    ScrollAxis::Y => self.scroll_to(self.style.scroll_x, target, context)
+                };
+                context.mark_handled();
+            },
+            InnerEvent::MouseUp(mouse) if mouse.button == Some(MouseButton::Left) && self.drag.is_some() => {
+                self.drag = None;
+                context.release_pointer();
+                context.mark_handled();
+            },
+            _ => {}
+        }
+    }
+
+    fn render_pre_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        self.layout = layout;
+        // content may have shrunk, keep the stored offset within it
+        self.scroll_by(0., 0., context);
+        let transform = context.canvas.transform();
+        self.origin = Location::new(transform[4], transform[5]);
+        draw_background(&context.resolve_style(&self.style), layout.size, context);
+    }
+
+    fn render_post_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        let Some(fill) = &self.scrollbar_fill else { return; };
+        let radius = self.scrollbar_width / 2.;
+        if let Some((start, length)) = self.thumb(ScrollAxis::Y) {
+            let mut path = Path::new();
+            path.rounded_rect(layout.size.width - self.scrollbar_width - SCROLLBAR_MARGIN, start, self.scrollbar_width, length, radius);
+            context.canvas.fill_path(&path, fill);
+        }
+        if let Some((start, length)) = self.thumb(ScrollAxis::X) {
+            let mut path = Path::new();
+            path.rounded_rect(start, layout.size.height - self.scrollbar_width - SCROLLBAR_MARGIN, length, self.scrollbar_width, radius);
+            context.canvas.fill_path(&path, fill);
+        }
+    }
+
+    fn add_child_at(&mut self, child: SharedNode, index: usize) -> Result<(), super::ChildAddError> {
+        let mut index = index;
+        if let Some(i) = self.has_child(&child) {
+            self.children.remove(i);
+            if i < index {
+                index -= 1;
+            }
+        }
+        self.children.insert(index, child);
+        Ok(())
+    }
+
+    fn event_handlers(&self) -> Option<crate::events::handler::InnerEventHandlerDataset> {
+        Some(self.events.handlers.clone())
+    }
+    fn set_parent(&mut self, parent: Option<WeakNode>) {
+        self.parent = parent;
+    }
+    fn parent(&self) -> Option<SharedNode> {
+        match &self.parent {
+            Some(parent) => parent.upgrade(),
+            None => None
+        }
+    }
+    fn remove_child(&mut self, child: &SharedNode) -> Result<(), super::ChildAddError> {
+        if let Some(i) = self.has_child(child) {
+            self.children.remove(i);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,630 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use cosmic_text::Metrics;
+use femtovg::{LineCap, LineJoin, Paint, Path};
+use taffy::{AlignItems, Dimension, FlexDirection, Layout, LengthPercentage, Overflow, Point, Rect, Size};
+use crate::{nodes::{Node, NodeChildren, Style, TaffyStyle, ToShared}, events::handler::EventHandlerDatabase, WeakNode, SharedNode};
+use crate::events::{ControlValue, InnerEvent, Key, KeyboardEvent, MouseButton, NamedKey, NodeEvent};
+use crate::nodes::interaction::focus;
+use crate::nodes::overlay::{open_overlay, Anchor, Overlay, OverlayHandle};
+use crate::nodes::primitives::{draw_background, draw_control_background, draw_focus_ring};
+use crate::nodes::scroll_view::{scroll_into_view, ScrollIntoViewOptions, ScrollView};
+use crate::nodes::text::Text;
+use crate::nodes::theme::StyleTokens;
+use crate::nodes::RenderContext;
+
+/// Maximal height of the option list, longer lists scroll
+pub const MAX_POPUP_HEIGHT: f32 = 300.;
+/// Space between the select and its option list, in pixels
+const POPUP_OFFSET: f32 = 4.;
+/// Width of the arrow drawn on the right of selects, in pixels
+const CHEVRON_WIDTH: f32 = 10.;
+/// Width of the outline of selects and their option lists, in pixels
+const BORDER_WIDTH: f32 = 1.;
+/// Number of options PageUp and PageDown move the highlight by
+const PAGE_OPTIONS: usize = 8;
+/// Typed characters are added to the type-ahead query if they follow the previous one within this duration
+const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Option of a [`Select`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SelectOption {
+    /// Value sent with the change event when the option is selected
+    pub value: String,
+    /// Text shown in the option list and in the select. Type-ahead matches the start of it
+    pub label: String,
+    /// Disabled options are shown but can't be selected
+    pub disabled: bool
+}
+
+impl SelectOption {
+    pub fn new(value: impl Into<String>, label: impl Into<String>) -> SelectOption {
+        SelectOption {
+            value: value.into(),
+            label: label.into(),
+            disabled: false
+        }
+    }
+
+    pub fn disabled(mut self, disabled: bool) -> SelectOption {
+        self.disabled = disabled;
+        self
+    }
+}
+
+/// Characters typed in quick succession, matched against the start of the option labels.
+#[derive(Default, Debug)]
+struct TypeAhead {
+    query: String,
+    last_key: Option<Instant>
+}
+
+impl TypeAhead {
+    fn is_typing(&self) -> bool {
+        !self.query.is_empty() && self.last_key.is_some_and(|last_key| last_key.elapsed() < TYPE_AHEAD_TIMEOUT)
+    }
+
+    /// Adds the text to the query (which is restarted after a pause) and returns the text to search for.
+    fn push(&mut self, text: &str) -> String {
+        if !self.is_typing() {
+            self.query.clear();
+        }
+        self.last_key = Some(Instant::now());
+        self.query.push_str(&text.to_lowercase());
+        // typing the same letter repeatedly cycles through the options starting with it
+        let first = self.query.chars().next().unwrap_or_default();
+        match self.query.chars().all(|c| c == first) {
+            true => first.to_string(),
+            false => self.query.clone()
+        }
+    }
+}
+
+struct Popup {
+    handle: OverlayHandle,
+    list: Arc<Mutex<SelectList>>
+}
+
+/// Button showing the selected option, which opens a list of the options in an overlay (see [`open_overlay`]).
+///
+/// While the select is focused, Enter, Space and the arrow keys open the list. In the list, arrow keys, Home, End,
+/// PageUp and PageDown move the highlighted option, Enter or Space selects it and Escape closes the list.
+/// Typing the start of a label highlights the matching option (or selects it while the list is closed).
+/// Selecting an option sends [`InnerEvent::Change`] with [`ControlValue::Selected`].
+pub struct Select {
+    pub style: Style,
+    pub events: EventHandlerDatabase,
+    pub parent: Option<WeakNode>,
+    pub options: Vec<SelectOption>,
+    /// Index of the selected option. Can be changed by the application, no change event is sent then.
+    pub selected: Option<usize>,
+    /// Text shown while no option is selected, colored with the `text-muted` token
+    pub placeholder: String,
+    /// Shows the label of the selected option or the placeholder
+    label: Arc<Mutex<Text>>,
+    /// Only contains the label
+    children: NodeChildren,
+    popup: Option<Popup>,
+    type_ahead: TypeAhead,
+    /// Width of the node, from the last render. The option list is at least as wide
+    width: f32
+}
+
+impl Select {
+    pub fn new(options: Vec<SelectOption>) -> Select {
+        let label = Text::new(String::new(), Metrics::new(16., 20.))
+            .style(Style {
+                tokens: StyleTokens { text_fill: Some("text"), typography: Some("body"), ..Default::default() },
+                ..Default::default()
+            })
+            .to_arcmutex();
+        Select {
+            style: Select::default_style(),
+            events: EventHandlerDatabase::default(),
+            parent: None,
+            options,
+            selected: None,
+            placeholder: String::new(),
+            children: vec![label.clone()],
+            label,
+            popup: None,
+            type_ahead: TypeAhead::default(),
+            width: 0.
+        }
+    }
+
+    pub fn selected(mut self, selected: Option<usize>) -> Select {
+        self.selected = selected;
+        self
+    }
+
+    pub fn placeholder(mut self, placeholder: impl Into<String>) -> Select {
+        self.placeholder = placeholder.into();
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> Select {
+        self.style = style;
+        self
+    }
+
+    /// Row with the `surface` background and room for the arrow on the right.
+    pub fn default_style() -> Style {
+        Style {
+            layout: TaffyStyle {
+                align_items: Some(AlignItems::Center),
+                padding: Rect {
+                    left: LengthPercentage::Length(12.),
+                    right: LengthPercentage::Length(12. + CHEVRON_WIDTH + 8.),
+                    top: LengthPercentage::Length(8.),
+                    bottom: LengthPercentage::Length(8.)
+                },
+                min_size: Size { width: Dimension::Length(120.), height: Dimension::Auto },
+                ..Default::default()
+            },
+            tokens: StyleTokens { background: Some("surface"), border_radius: Some("md"), ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    /// Returns the selected option.
+    pub fn selected_option(&self) -> Option<&SelectOption> {
+        self.selected.and_then(|index| self.options.get(index))
+    }
+
+    /// Returns true if the option list is open.
+    pub fn is_open(&self) -> bool {
+        self.popup.is_some()
+    }
+
+    /// Returns the first enabled option from `start`, going forward or backward.
+    fn enabled_from(&self, start: usize, forward: bool) -> Option<usize> {
+        let enabled = |index: &usize| !self.options[*index].disabled;
+        if start >= self.options.len() {
+            return None;
+        }
+        match forward {
+            true => (start..self.options.len()).find(enabled),
+            false => (0..=start).rev().find(enabled)
+        }
+    }
+
+    fn highlighted(&self) -> Option<usize> {
+        self.popup.as_ref().and_then(|popup| popup.list.lock().unwrap().highlighted)
+    }
+
+    fn select(&mut self, index: usize, context: &mut RenderContext) {
+        context.request_frame();
+        if self.selected == Some(index) {
+            return;
+        }
+        self.selected = Some(index);
+        let value = self.options[index].value.clone();
+        context.emit(InnerEvent::Change(ControlValue::Selected { index, value }));
+    }
+
+    fn open(&mut self, context: &mut RenderContext) {
+        let Some(node) = context.current_node.clone() else { return; };
+        let highlighted = self.selected
+            .filter(|index| self.enabled_from(*index, true) == Some(*index))
+            .or_else(|| self.enabled_from(0, true));
+        let list = SelectList::new(&self.options, self.selected, highlighted, self.width).to_arcmutex();
+        if let Some(index) = highlighted {
+            let row: SharedNode = list.lock().unwrap().rows[index].clone();
+            scroll_into_view(&row, ScrollIntoViewOptions::default());
+        }
+        let dismissed = list.clone();
+        let overlay = Overlay::new(list.clone())
+            .anchor(Anchor::Node(node))
+            .offset(POPUP_OFFSET)
+            .dismiss_on_click_outside(move || dismissed.lock().unwrap().dismissed = true);
+        self.popup = Some(Popup { handle: open_overlay(overlay), list });
+        context.request_frame();
+    }
+
+    fn close(&mut self, context: &mut RenderContext) {
+        if let Some(popup) = self.popup.take() {
+            popup.handle.close();
+            context.request_frame();
+        }
+    }
+
+    fn set_highlighted(&mut self, index: Option<usize>, context: &mut RenderContext) {
+        let (Some(popup), Some(index)) = (&self.popup, index) else { return; };
+        let mut list = popup.list.lock().unwrap();
+        list.highlighted = Some(index);
+        let row: SharedNode = list.rows[index].clone();
+        drop(list);
+        scroll_into_view(&row, ScrollIntoViewOptions::default());
+        context.request_frame();
+    }
+
+    /// Highlights (or selects, while the list is closed) the next option matching the typed text.
+    fn type_ahead(&mut self, text: &str, context: &mut RenderContext) {
+        let search = self.type_ahead.push(text);
+        let count = self.options.len();
+        let current = if self.is_open() { self.highlighted() } else { self.selected };
+        // a single letter moves to the next match, a longer query keeps the current option while it matches
+        let start = current.map_or(0, |current| if search.chars().count() == 1 { current + 1 } else { current });
+        let found = (0..count)
+            .map(|offset| (start + offset) % count)
+            .find(|index| !self.options[*index].disabled && self.options[*index].label.to_lowercase().starts_with(&search));
+        match (found, self.is_open()) {
+            (Some(index), true) => self.set_highlighted(Some(index), context),
+            (Some(index), false) => self.select(index, context),
+            (None, _) => {}
+        }
+    }
+
+    /// Handles a key pressed while the select is focused, returns true if it was used.
+    fn handle_key(&mut self, key: &KeyboardEvent, context: &mut RenderContext) -> bool {
+        if key.is_named(NamedKey::Tab) {
+            // the focus moves on, so does the list
+            self.close(context);
+            return false;
+        }
+        if key.is_named(NamedKey::Escape) {
+            let open = self.is_open();
+            self.close(context);
+            return open;
+        }
+        let space = key.is_named(NamedKey::Space) && !self.type_ahead.is_typing();
+        if !self.is_open() {
+            let opens = key.is_named(NamedKey::Enter) || space
+                || key.is_named(NamedKey::ArrowDown) || key.is_named(NamedKey::ArrowUp);
+            if opens && !key.repeat {
+                self.open(context);
+                return true;
+            }
+        } else if key.is_named(NamedKey::Enter) || space {
+            let highlighted = self.highlighted();
+            self.close(context);
+            if let Some(index) = highlighted {
+                self.select(index, context);
+            }
+            return true;
+        } else {
+            let last = self.options.len().saturating_sub(1);
+            let highlighted = self.highlighted();
+            let next = if key.is_named(NamedKey::ArrowDown) {
+                highlighted.map_or(self.enabled_from(0, true), |index| self.enabled_from(index + 1, true).or(Some(index)))
+            } else if key.is_named(NamedKey::ArrowUp) {
+                match highlighted {
+                    Some(0) => Some(0),
+                    Some(index) => self.enabled_from(index - 1, false).or(Some(index)),
+                    None => self.enabled_from(last, false)
+                }
+            } else if key.is_named(NamedKey::Home) {
+                self.enabled_from(0, true)
+            } else if key.is_named(NamedKey::End) {
+                self.enabled_from(last, false)
+            } else if key.is_named(NamedKey::PageDown) {
+                let target = highlighted.map_or(0, |index| (index + PAGE_OPTIONS).min(last));
+                self.enabled_from(target, true).or_else(|| self.enabled_from(target, false))
+            } else if key.is_named(NamedKey::PageUp) {
+                let target = highlighted.map_or(0, |index| index.saturating_sub(PAGE_OPTIONS));
+                self.enabled_from(target, false).or_else(|| self.enabled_from(target, true))
+            } else {
+                None
+            };
+            if next.is_some() {
+                self.set_highlighted(next, context);
+                return true;
+            }
+        }
+        match &key.key {
+            Some(Key::Character(text)) if !key.ctrl() && !key.alt() && !key.logo() && !text.chars().any(char::is_control) => {
+                self.type_ahead(text, context);
+                true
+            },
+            // while typing, space is part of the search instead of selecting
+            Some(Key::Named(NamedKey::Space)) if self.type_ahead.is_typing() => {
+                self.type_ahead(" ", context);
+                true
+            },
+            _ => false
+        }
+    }
+}
+
+impl Drop for Select {
+    fn drop(&mut self) {
+        if let Some(popup) = self.popup.take() {
+            popup.handle.close();
+        }
+    }
+}
+
+impl Debug for Select {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Select")
+            .field("style", &self.style)
+            .field("options", &self.options)
+            .field("selected", &self.selected)
+            .field("open", &self.is_open())
+            .finish()
+    }
+}
+
+impl Node for Select {
+    fn style(&self) -> &Style {
+        &self.style
+    }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
+    fn children(&self) -> Option<&NodeChildren> {
+        Some(&self.children)
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn prepare_render(&mut self, context: &mut RenderContext) {
+        if let Some(popup) = &self.popup {
+            let mut list = popup.list.lock().unwrap();
+            let picked = list.picked.take();
+            let dismissed = list.dismissed;
+            drop(list);
+            if self.style.disabled || dismissed {
+                self.close(context);
+            } else if let Some(index) = picked {
+                self.close(context);
+                self.select(index, context);
+                // pressing the list moved the focus away from the select
+                if let Some(node) = context.current_node.as_ref().and_then(|node| node.upgrade()) {
+                    focus(&node);
+                }
+            }
+        }
+        let (text, fill) = match self.selected_option() {
+            Some(option) => (option.label.clone(), "text"),
+            None => (self.placeholder.clone(), "text-muted")
+        };
+        let mut label = self.label.lock().unwrap();
+        if label.text != text {
+            label.text = text;
+        }
+        label.style.tokens.text_fill = Some(fill);
+    }
+
+    fn on_event(&mut self, event: &NodeEvent, context: &mut RenderContext) {
+        if self.style.disabled {
+            return;
+        }
+        match &event.event {
+            InnerEvent::MouseDown(mouse) if mouse.button == Some(MouseButton::Left) => {
+                if context.is_handled() { return; }
+                if self.is_open() {
+                    self.close(context);
+                } else {
+                    self.open(context);
+                }
+                context.mark_handled();
+            },
+            // key events bubble from the focused node, only handle them when the select itself is focused
+            InnerEvent::KeyDown(key) if context.style_environment().state.focused => {
+                let handled = self.handle_key(key, context);
+                if handled {
+                    context.mark_handled();
+                }
+            },
+            InnerEvent::Focus | InnerEvent::Blur => context.request_frame(),
+            _ => {}
+        }
+    }
+
+    fn render_pre_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        let style = context.resolve_style(&self.style);
+        draw_control_background(&style, layout.size, context);
+        self.width = layout.size.width;
+        let size = layout.size;
+        let mut outline = Path::new();
+        style.border_radius.add_to_path(&mut outline, BORDER_WIDTH / 2., BORDER_WIDTH / 2., size.width - BORDER_WIDTH, size.height - BORDER_WIDTH);
+        let hovered = context.style_environment().state.hovered;
+        let border = if hovered || self.is_open() { "accent" } else { "border" };
+        let paint = Paint::color(context.theme.get_color(border)).with_line_width(BORDER_WIDTH);
+        context.canvas.stroke_path(&outline, &paint);
+
+        // arrow pointing down, or up while the list is open
+        let x = size.width - 12. - CHEVRON_WIDTH;
+        let y = size.height / 2.;
+        let half = CHEVRON_WIDTH / 4. * if self.is_open() { -1. } else { 1. };
+        let mut chevron = Path::new();
+        chevron.move_to(x, y - half);
+        chevron.line_to(x + CHEVRON_WIDTH / 2., y + half);
+        chevron.line_to(x + CHEVRON_WIDTH, y - half);
+        let paint = Paint::color(context.theme.get_color("text-muted"))
+            .with_line_width(1.5)
+            .with_line_cap(LineCap::Round)
+            .with_line_join(LineJoin::Round);
+        context.canvas.stroke_path(&chevron, &paint);
+    }
+
+    fn render_post_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        if context.style_environment().state.focused {
+            let radius = context.resolve_style(&self.style).border_radius;
+            draw_focus_ring(&radius, layout.size, context);
+        }
+    }
+
+    fn event_handlers(&self) -> Option<crate::events::handler::InnerEventHandlerDataset> {
+        Some(self.events.handlers.clone())
+    }
+    fn set_parent(&mut self, parent: Option<WeakNode>) {
+        self.parent = parent;
+    }
+    fn parent(&self) -> Option<SharedNode> {
+        match &self.parent {
+            Some(parent) => parent.upgrade(),
+            None => None
+        }
+    }
+}
+
+/// Option list of an open [`Select`], root of its overlay. The select reads the picked option on the next frame.
+struct SelectList {
+    style: Style,
+    parent: Option<WeakNode>,
+    /// Scroll view containing the rows
+    children: NodeChildren,
+    rows: Vec<Arc<Mutex<Text>>>,
+    disabled: Vec<bool>,
+    selected: Option<usize>,
+    /// Option moved to with the keyboard or the pointer
+    highlighted: Option<usize>,
+    /// Option clicked by the user, taken by the select
+    picked: Option<usize>,
+    /// Set when the list was closed by a click outside of it
+    dismissed: bool
+}
+
+impl SelectList {
+    fn new(options: &[SelectOption], selected: Option<usize>, highlighted: Option<usize>, min_width: f32) -> SelectList {
+        let rows: Vec<_> = options.iter()
+            .map(|option| Text::new(option.label.clone(), Metrics::new(16., 20.))
+                .style(Style {
+                    layout: TaffyStyle {
+                        padding: Rect {
+                            left: LengthPercentage::Length(12.),
+                            right: LengthPercentage::Length(12.),
+                            top: LengthPercentage::Length(6.),
+                            bottom: LengthPercentage::Length(6.)
+                        },
+                        ..Default::default()
+                    },
+                    tokens: StyleTokens { typography: Some("body"), border_radius: Some("sm"), ..Default::default() },
+                    ..Default::default()
+                })
+                .to_arcmutex())
+            .collect();
+        let scroll_view = ScrollView::new(rows.iter().map(|row| row.clone() as SharedNode).collect())
+            .style(Style {
+                layout: TaffyStyle {
+                    flex_direction: FlexDirection::Column,
+                    overflow: Point { x: Overflow::Hidden, y: Overflow::Scroll },
+                    max_size: Size { width: Dimension::Auto, height: Dimension::Length(MAX_POPUP_HEIGHT) },
+                    flex_grow: 1.,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+        SelectList {
+            style: Style {
+                layout: TaffyStyle {
+                    flex_direction: FlexDirection::Column,
+                    padding: Rect {
+                        left: LengthPercentage::Length(4.),
+                        right: LengthPercentage::Length(4.),
+                        top: LengthPercentage::Length(4.),
+                        bottom: LengthPercentage::Length(4.)
+                    },
+                    min_size: Size { width: Dimension::Length(min_width), height: Dimension::Auto },
+                    ..Default::default()
+                },
+                tokens: StyleTokens { background: Some("background"), border_radius: Some("md"), ..Default::default() },
+                ..Default::default()
+            },
+            parent: None,
+            children: vec![scroll_view.to_shared()],
+            rows,
+            disabled: options.iter().map(|option| option.disabled).collect(),
+            selected,
+            highlighted,
+            picked: None,
+            dismissed: false
+        }
+    }
+
+    /// Returns the index of the row in the path of the event, if it's enabled.
+    fn row_in_path(&self, path: &[SharedNode]) -> Option<usize> {
+        self.rows.iter()
+            .position(|row| path.iter().any(|node| std::ptr::addr_eq(Arc::as_ptr(node), Arc::as_ptr(row))))
+            .filter(|index| !self.disabled[*index])
+    }
+}
+
+impl Debug for SelectList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SelectList")
+            .field("style", &self.style)
+            .field("highlighted", &self.highlighted)
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+impl Node for SelectList {
+    fn style(&self) -> &Style {
+        &self.style
+    }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
+    fn children(&self) -> Option<&NodeChildren> {
+        Some(&self.children)
+    }
+
+    fn prepare_render(&mut self, _context: &mut RenderContext) {
+        for (index, row) in self.rows.iter().enumerate() {
+            let mut row = row.lock().unwrap();
+            let highlighted = self.highlighted == Some(index);
+            row.style.tokens.background = highlighted.then_some("accent");
+            row.style.tokens.text_fill = Some(if highlighted {
+                "on-accent"
+            } else if self.disabled[index] {
+                "text-muted"
+            } else if self.selected == Some(index) {
+                "accent"
+            } else {
+                "text"
+            });
+        }
+    }
+
+    fn on_event(&mut self, event: &NodeEvent, context: &mut RenderContext) {
+        match &event.event {
+            InnerEvent::MouseMove(_) => {
+                let Some(index) = self.row_in_path(&event.path) else { return; };
+                if self.highlighted != Some(index) {
+                    self.highlighted = Some(index);
+                    context.request_frame();
+                }
+            },
+            InnerEvent::MouseUp(mouse) if mouse.button == Some(MouseButton::Left) => {
+                if context.is_handled() { return; }
+                let Some(index) = self.row_in_path(&event.path) else { return; };
+                self.picked = Some(index);
+                context.request_frame();
+                context.mark_handled();
+            },
+            _ => {}
+        }
+    }
+
+    fn render_pre_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        let style = context.resolve_style(&self.style);
+        draw_background(&style, layout.size, context);
+    }
+
+    fn render_post_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        let style = context.resolve_style(&self.style);
+        let size = layout.size;
+        let mut outline = Path::new();
+        style.border_radius.add_to_path(&mut outline, BORDER_WIDTH / 2., BORDER_WIDTH / 2., size.width - BORDER_WIDTH, size.height - BORDER_WIDTH);
+        let paint = Paint::color(context.theme.get_color("border")).with_line_width(BORDER_WIDTH);
+        context.canvas.stroke_path(&outline, &paint);
+    }
+
+    fn set_parent(&mut self, parent: Option<WeakNode>) {
+        self.parent = parent;
+    }
+    fn parent(&self) -> Option<SharedNode> {
+        match &self.parent {
+            Some(parent) => parent.upgrade(),
+            None => None
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use femtovg::{LineCap, LineJoin, Paint, Path};
+use taffy::{AvailableSpace, Layout, Size};
+use crate::{nodes::{Node, NodeChildren, RenderContext, Style}, events::handler::EventHandlerDatabase, WeakNode, SharedNode};
+use crate::nodes::{CanvasRenderer, MeasureContext};
+
+/// Area of the coordinate space which is shown in the node (x, y, width, height), like the `viewBox` of SVGs.
+pub type ViewBox = [f32; 4];
+
+/// Vector shape node. Draws a path with a fill and/or a stroke.
+///
+/// Without a [`Shape::view_box`], the path is drawn in the coordinates of the node (with 0, 0 at its top left corner).
+#[derive(Debug)]
+pub struct Shape {
+    pub style: Style,
+    pub events: EventHandlerDatabase,
+    pub parent: Option<WeakNode>,
+    pub path: Path,
+    pub fill: Option<Paint>,
+    pub stroke: Option<Paint>,
+    /// Width of the stroke in path coordinates. Default is 1
+    pub stroke_width: f32,
+    pub line_cap: LineCap,
+    pub line_join: LineJoin,
+    /// Area of the path coordinates which is scaled to the size of the node, keeping the aspect ratio.
+    /// The node is sized to the view box if it doesn't have a size set.
+    pub view_box: Option<ViewBox>
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Shape {
+            style: Style::default(),
+            events: EventHandlerDatabase::default(),
+            parent: None,
+            path: Path::new(),
+            fill: None,
+            stroke: None,
+            stroke_width: 1.,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Miter,
+            view_box: None
+        }
+    }
+}
+
+impl Shape {
+    pub fn new(path: Path) -> Shape {
+        Shape {
+            path,
+            ..Default::default()
+        }
+    }
+    pub fn style(mut self, style: Style) -> Shape {
+        self.style = style;
+        self
+    }
+    pub fn fill(mut self, fill: Paint) -> Shape {
+        self.fill = Some(fill);
+        self
+    }
+    pub fn stroke(mut self, stroke: Paint, width: f32) -> Shape {
+        self.stroke = Some(stroke);
+        self.stroke_width = width;
+        self
+    }
+    pub fn view_box(mut self, view_box: ViewBox) -> Shape {
+        self.view_box = Some(view_box);
+        self
+    }
+}
+
+/// Transforms the canvas so that the view box is centered in the area and fits it, keeping the aspect ratio.
+pub(crate) fn apply_view_box(view_box: ViewBox, size: Size<f32>, canvas: &mut CanvasRenderer) {
+    let [x, y, width, height] = view_box;
+    if width <= 0. || height <= 0. {
+        return;
+    }
+    let scale = (size.width / width).min(size.height / height);
+    canvas.translate(
+        (size.width - width * scale) / 2. - x * scale,
+        (size.height - height * scale) / 2. - y * scale
+    );
+    canvas.scale(scale, scale);
+}
+
+/// Size of a node showing content of the size, keeping its aspect ratio when one dimension is known.
+pub(crate) fn measure_aspect(content: Size<f32>, known_dimensions: Size<Option<f32>>) -> Size<f32> {
+    if content.width <= 0. || content.height <= 0. {
+        return Size {
+            width: known_dimensions.width.unwrap_or(content.width),
+            height: known_dimensions.height.unwrap_or(content.height)
+        };
+    }
+    match (known_dimensions.width, known_dimensions.height) {
+        (Some(width), Some(height)) => Size { width, height },
+        (Some(width), None) => Size { width, height: width / content.width * content.height },
+        (None, Some(height)) => Size { width: height / content.height * content.width, height },
+        (None, None) => content
+    }
+}
+
+impl Node for Shape {
+    fn style(&self) -> &Style {
+        &self.style
+    }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
+    fn children(&self) -> Option<&NodeChildren> {
+        None
+    }
+
+    fn render_pre_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        context.canvas.save();
+        if let Some(view_box) = self.view_box {
+            apply_view_box(view_box, layout.size, &mut context.canvas);
+        }
+        if let Some(fill) = &self.fill {
+            context.canvas.fill_path(&self.path, fill);
+        }
+        if let Some(stroke) = &self.stroke {
+            let mut stroke = stroke.clone();
+            stroke.set_line_width(self.stroke_width);
+            stroke.set_line_cap(self.line_cap);
+            stroke.set_line_join(self.line_join);
+            context.canvas.stroke_path(&self.path, &stroke);
+        }
+        context.canvas.restore();
+    }
+
+    fn measure(&mut self, _context: &mut MeasureContext, known_dimensions: Size<Option<f32>>, _available_space: Size<AvailableSpace>) -> Size<f32> {
+        match self.view_box {
+            Some([_, _, width, height]) => measure_aspect(Size { width, height }, known_dimensions),
+            None => Size {
+                width: known_dimensions.width.unwrap_or(0.),
+                height: known_dimensions.height.unwrap_or(0.)
+            }
+        }
+    }
+
+    fn event_handlers(&self) -> Option<crate::events::handler::InnerEventHandlerDataset> {
+        Some(self.events.handlers.clone())
+    }
+    fn set_parent(&mut self, parent: Option<WeakNode>) {
+        self.parent = parent;
+    }
+    fn parent(&self) -> Option<SharedNode> {
+        match &self.parent {
+            Some(parent) => parent.upgrade(),
+            None => None
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use std::fmt::{Debug, Formatter};
+use femtovg::Path;
+use taffy::{Dimension, Layout, Size};
+use crate::{nodes::{Node, NodeChildren, Style, TaffyStyle}, events::handler::EventHandlerDatabase, WeakNode, SharedNode};
+use crate::events::{ControlValue, InnerEvent, Location, MouseButton, NamedKey, NodeEvent};
+use crate::nodes::primitives::{draw_control_background, draw_focus_ring};
+use crate::nodes::theme::ThemePaint;
+use crate::nodes::{BorderRadius, RenderContext};
+
+/// Diameter of the thumbs of sliders, in pixels
+pub const THUMB_SIZE: f32 = 18.;
+/// Height of the track of sliders, in pixels
+const TRACK_HEIGHT: f32 = 4.;
+
+/// Thumb of a [`Slider`]. Sliders which aren't in range mode only have the start thumb
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum SliderThumb {
+    #[default]
+    Start,
+    End
+}
+
+/// Paints of sliders. The defaults use the theme tokens.
+#[derive(Clone, Debug)]
+pub struct SliderColors {
+    /// Track of the slider. Default is `border`
+    pub track: ThemePaint,
+    /// Part of the track before the thumb, or between the thumbs in range mode. Default is `accent`
+    pub fill: ThemePaint,
+    /// Default is `accent`
+    pub thumb: ThemePaint
+}
+
+impl Default for SliderColors {
+    fn default() -> Self {
+        SliderColors {
+            track: "border".into(),
+            fill: "accent".into(),
+            thumb: "accent".into()
+        }
+    }
+}
+
+/// Horizontal slider selecting a value (or a range of values with two thumbs) between `min` and `max`.
+///
+/// The thumbs are dragged with the mouse, which keeps the pointer until it's released. While focused, arrow keys
+/// change the value by `step`, PageUp and PageDown by `page_step`, Home and End move to the ends.
+/// In range mode, the keys move the thumb which was dragged last, Tab moves from the start thumb to the end thumb.
+///
+/// Each change sends [`InnerEvent::Change`] with [`ControlValue::Number`] (or [`ControlValue::Range`]).
+/// [`InnerEvent::Commit`] follows when the thumb is released and after each key press - use it for expensive updates.
+pub struct Slider {
+    pub style: Style,
+    pub events: EventHandlerDatabase,
+    pub parent: Option<WeakNode>,
+    pub min: f32,
+    pub max: f32,
+    /// Values snap to multiples of the step from `min`, 0 allows any value. Default is 1
+    pub step: f32,
+    /// Amount PageUp and PageDown change the value by. Default is a tenth of the range
+    pub page_step: Option<f32>,
+    /// Value of the thumb, or of the start thumb in range mode
+    pub value: f32,
+    /// Value of the end thumb in range mode, see [`Slider::range`]
+    pub end: Option<f32>,
+    pub colors: SliderColors,
+    /// Thumb being dragged
+    drag: Option<SliderThumb>,
+    /// Thumb moved by the keyboard
+    active: SliderThumb,
+    /// Window location of the node, from the last render
+    origin: Location,
+    /// Width of the node, from the last render
+    width: f32
+}
+
+impl Slider {
+    pub fn new(min: f32, max: f32, value: f32) -> Slider {
+        Slider {
+            style: Slider::default_style(),
+            events: EventHandlerDatabase::default(),
+            parent: None,
+            min,
+            max,
+            step: 1.,
+            page_step: None,
+            value,
+            end: None,
+            colors: SliderColors::default(),
+            drag: None,
+            active: SliderThumb::Start,
+            origin: Location::default(),
+            width: 0.
+        }
+    }
+
+    /// Slider with two thumbs selecting the range from `start` to `end`.
+    pub fn range(min: f32, max: f32, start: f32, end: f32) -> Slider {
+        Slider {
+            end: Some(end),
+            ..Slider::new(min, max, start)
+        }
+    }
+
+    pub fn step(mut self, step: f32) -> Slider {
+        self.step = step;
+        self
+    }
+
+    pub fn page_step(mut self, page_step: f32) -> Slider {
+        self.page_step = Some(page_step);
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> Slider {
+        self.style = style;
+        self
+    }
+
+    pub fn colors(mut self, colors: SliderColors) -> Slider {
+        self.colors = colors;
+        self
+    }
+
+    /// 200 pixels wide, as tall as the thumbs.
+    pub fn default_style() -> Style {
+        Style {
+            layout: TaffyStyle {
+                size: Size { width: Dimension::Length(200.), height: Dimension::Length(THUMB_SIZE) },
+                flex_shrink: 1.,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Returns the value of the slider as sent with its events.
+    pub fn control_value(&self) -> ControlValue {
+        match self.end {
+            Some(end) => ControlValue::Range(self.value, end),
+            None => ControlValue::Number(self.value)
+        }
+    }
+
+    fn thumb_value(&self, thumb: SliderThumb) -> f32 {
+        match (thumb, self.end) {
+            (SliderThumb::End, Some(end)) => end,
+            _ => self.value
+        }
+    }
+
+    /// Rounds the value to the step and keeps it between `min` and `max`.
+    fn snap(&self, value: f32) -> f32 {
+        let value = value.clamp(self.min, self.max);
+        if self.step <= 0. {
+            return value;
+        }
+        (self.min + ((value - self.min) / self.step).round() * self.step).min(self.max)
+    }
+
+    /// Returns the x coordinate of the center of a thumb with the value.
+    fn position_of(&self, value: f32) -> f32 {
+        let radius = THUMB_SIZE / 2.;
+        let fraction = if self.max > self.min { (value - self.min) / (self.max - self.min) } else { 0. };
+        radius + fraction.clamp(0., 1.) * (self.width - THUMB_SIZE).max(0.)
+    }
+
+    /// Returns the value of a thumb centered on the x coordinate.
+    fn value_at(&self, x: f32) -> f32 {
+        let track = (self.width - THUMB_SIZE).max(1.);
+        let fraction = ((x - THUMB_SIZE / 2.) / track).clamp(0., 1.);
+        self.min + fraction * (self.max - self.min)
+    }
+
+    /// Returns the thumb closest to the value. When the thumbs overlap, the one on the side of the value is used.
+    fn thumb_at(&self, value: f32) -> SliderThumb {
+        let Some(end) = self.end else { return SliderThumb::Start; };
+        let to_start = (value - self.value).abs();
+        let to_end = (value - end).abs();
+        if to_end < to_start || (to_end == to_start && value > end) {
+            SliderThumb::End
+        } else {
+            SliderThumb::Start
+        }
+    }
+
+    /// Moves the thumb to the value (snapped, and kept on its side of the other thumb).
+    /// Sends a change event and returns true if the value changed.
+    fn set_thumb(&mut self, thumb: SliderThumb, value: f32, context: &mut RenderContext) -> bool {
+        let value = self.snap(value);
+        let (target, value) = match (thumb, self.end.as_mut()) {
+            (SliderThumb::End, Some(end)) => (end, value.max(self.value)),
+            (_, end) => {
+                let max = end.map_or(self.max, |end| *end);
+                (&mut self.value, value.min(max))
+            }
+        };
+        if *target == value {
+            return false;
+        }
+        *target = value;
+        context.emit(InnerEvent::Change(self.control_value()));
+        context.request_frame();
+        true
+    }
+}
+
+impl Debug for Slider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Slider")
+            .field("style", &self.style)
+            .field("min", &self.min)
+            .field("max", &self.max)
+            .field("value", &self.value)
+            .field("end", &self.end)
+            .finish()
+    }
+}
+
+impl Node for Slider {
+    fn style(&self) -> &Style {
+        &self.style
+    }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
+    fn children(&self) -> Option<&NodeChildren> {
+        None
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: &NodeEvent, context: &mut RenderContext) {
+        if self.style.disabled {
+            self.drag = None;
+            return;
+        }
+        match &event.event {
+            InnerEvent::MouseDown(mouse) if mouse.button == Some(MouseButton::Left) => {
+                if context.is_handled() { return; }
+                let value = self.value_at(mouse.client.x - self.origin.x);
+                let thumb = self.thumb_at(value);
+                self.drag = Some(thumb);
+                self.active = thumb;
+                self.set_thumb(thumb, value, context);
+                context.capture_pointer();
+                context.mark_handled();
+            },
+            InnerEvent::MouseMove(mouse) => {
+                let Some(thumb) = self.drag else { return; };
+                self.set_thumb(thumb, self.value_at(mouse.client.x - self.origin.x), context);
+                context.mark_handled();
+            },
+            InnerEvent::MouseUp(mouse) if mouse.button == Some(MouseButton::Left) && self.drag.is_some() => {
+                self.drag = None;
+                context.release_pointer();
+                context.emit(InnerEvent::Commit(self.control_value()));
+                context.request_frame();
+                context.mark_handled();
+            },
+            InnerEvent::KeyDown(key) if context.style_environment().state.focused => {
+                if self.end.is_some() && key.is_named(NamedKey::Tab) {
+                    // Tab moves between the thumbs before leaving the slider
+                    let next = if key.shift() { SliderThumb::Start } else { SliderThumb::End };
+                    if self.active != next {
+                        self.active = next;
+                        context.request_frame();
+                        context.mark_handled();
+                    }
+                    return;
+                }
+                let step = if self.step > 0. { self.step } else { (self.max - self.min) / 100. };
+                let page = self.page_step.unwrap_or((self.max - self.min) / 10.).max(step);
+                let current = self.thumb_value(self.active);
+                let value = if key.is_named(NamedKey::ArrowRight) || key.is_named(NamedKey::ArrowUp) {
+                    current + step
+                } else if key.is_named(NamedKey::ArrowLeft) || key.is_named(NamedKey::ArrowDown) {
+                    current - step
+                } else if key.is_named(NamedKey::PageUp) {
+                    current + page
+                } else if key.is_named(NamedKey::PageDown) {
+                    current - page
+                } else if key.is_named(NamedKey::Home) {
+                    self.min
+                } else if key.is_named(NamedKey::End) {
+                    self.max
+                } else {
+                    return;
+                };
+                if self.set_thumb(self.active, value, context) {
+                    context.emit(InnerEvent::Commit(self.control_value()));
+                }
+                context.mark_handled();
+            },
+            InnerEvent::Focus | InnerEvent::Blur => context.request_frame(),
+            _ => {}
+        }
+    }
+
+    fn render_pre_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        let style = context.resolve_style(&self.style);
+        draw_control_background(&style, layout.size, context);
+        let transform = context.canvas.transform();
+        self.origin = Location::new(transform[4], transform[5]);
+        self.width = layout.size.width;
+        let center = layout.size.height / 2.;
+
+        let mut track = Path::new();
+        track.rounded_rect(0., center - TRACK_HEIGHT / 2., layout.size.width, TRACK_HEIGHT, TRACK_HEIGHT / 2.);
+        context.canvas.fill_path(&track, &self.colors.track.paint(&context.theme));
+        let (fill_start, fill_end) = match self.end {
+            Some(end) => (self.position_of(self.value), self.position_of(end)),
+            None => (0., self.position_of(self.value))
+        };
+        let mut fill = Path::new();
+        fill.rounded_rect(fill_start, center - TRACK_HEIGHT / 2., (fill_end - fill_start).max(0.), TRACK_HEIGHT, TRACK_HEIGHT / 2.);
+        context.canvas.fill_path(&fill, &self.colors.fill.paint(&context.theme));
+
+        let focused = context.style_environment().state.focused;
+        let thumbs: &[SliderThumb] = if self.end.is_some() { &[SliderThumb::Start, SliderThumb::End] } else { &[SliderThumb::Start] };
+        for thumb in thumbs {
+            let x = self.position_of(self.thumb_value(*thumb));
+            let radius = THUMB_SIZE / 2.;
+            // the dragged thumb grows a little
+            let grow = if self.drag == Some(*thumb) { 1. } else { 0. };
+            let mut circle = Path::new();
+            circle.circle(x, center, radius + grow);
+            context.canvas.fill_path(&circle, &self.colors.thumb.paint(&context.theme));
+            if focused && self.active == *thumb {
+                context.canvas.save();
+                context.canvas.translate(x - radius, center - radius);
+                draw_focus_ring(&BorderRadius::all(radius), Size { width: THUMB_SIZE, height: THUMB_SIZE }, context);
+                context.canvas.restore();
+            }
+        }
+    }
+
+    fn event_handlers(&self) -> Option<crate::events::handler::InnerEventHandlerDataset> {
+        Some(self.events.handlers.clone())
+    }
+    fn set_parent(&mut self, parent: Option<WeakNode>) {
+        self.parent = parent;
+    }
+    fn parent(&self) -> Option<SharedNode> {
+        match &self.parent {
+            Some(parent) => parent.upgrade(),
+            None => None
+        }
+    }
+}
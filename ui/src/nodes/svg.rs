@@ -0,0 +1,624 @@
+use std::collections::HashMap;
+use std::path::Path as FilePath;
+use std::str::FromStr;
+use femtovg::{Color, FillRule, LineCap, LineJoin, Paint, Path, Transform2D};
+use roxmltree::{Document, Node as XmlNode};
+use svgtypes::{SimplePathSegment, SimplifyingPathParser};
+use taffy::{AvailableSpace, Layout, Size};
+use crate::{nodes::{Node, NodeChildren, RenderContext, Style}, events::handler::EventHandlerDatabase, WeakNode, SharedNode};
+use crate::nodes::{BorderRadius, CanvasRenderer, CornerRadius, MeasureContext};
+use crate::nodes::shape::{apply_view_box, measure_aspect, ViewBox};
+
+/// Parsed SVG image, see [`Svg`].
+#[derive(Debug, Clone)]
+pub struct SvgDocument {
+    pub view_box: ViewBox,
+    /// Size from the `width` and `height` attributes, or the size of the view box
+    pub size: Size<f32>,
+    elements: Vec<SvgElement>
+}
+
+#[derive(Debug, Clone)]
+enum SvgElement {
+    Group {
+        transform: Option<Transform2D>,
+        children: Vec<SvgElement>
+    },
+    /// Boxed, as paints make shapes much larger than groups
+    Shape(Box<SvgShape>)
+}
+
+#[derive(Debug, Clone)]
+struct SvgShape {
+    transform: Option<Transform2D>,
+    path: Path,
+    fill: Option<Paint>,
+    stroke: Option<Paint>
+}
+
+#[derive(Debug, Clone)]
+struct Gradient {
+    radial: bool,
+    /// x1, y1, x2, y2 for linear gradients, cx, cy, r (and an unused value) for radial ones
+    coords: [f32; 4],
+    /// Coordinates are fractions of the bounding box of the shape
+    bounding_box_units: bool,
+    /// `gradientTransform`, applied to the coordinates before the bounding box
+    transform: Transform2D,
+    stops: Vec<(f32, Color)>
+}
+
+#[derive(Debug, Clone)]
+enum SvgPaint {
+    Color(Color),
+    Gradient(Gradient)
+}
+
+/// Inherited presentation attributes
+#[derive(Debug, Clone)]
+struct Presentation {
+    fill: Option<SvgPaint>,
+    stroke: Option<SvgPaint>,
+    stroke_width: f32,
+    line_cap: LineCap,
+    line_join: LineJoin,
+    fill_rule: FillRule,
+    fill_opacity: f32,
+    stroke_opacity: f32,
+    /// Group opacity is approximated by multiplying the opacity of shapes
+    opacity: f32,
+    color: Color
+}
+
+impl Default for Presentation {
+    fn default() -> Self {
+        Presentation {
+            fill: Some(SvgPaint::Color(Color::black())),
+            stroke: None,
+            stroke_width: 1.,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Miter,
+            fill_rule: FillRule::NonZero,
+            fill_opacity: 1.,
+            stroke_opacity: 1.,
+            opacity: 1.,
+            color: Color::black()
+        }
+    }
+}
+
+struct ParseContext {
+    gradients: HashMap<String, Gradient>,
+    view_box: ViewBox
+}
+
+/// Returns the value of a presentation property, from the `style` attribute or the attribute itself.
+fn property<'a>(node: XmlNode<'a, '_>, name: &str) -> Option<&'a str> {
+    let from_style = node.attribute("style").and_then(|style| {
+        style.split(';')
+            .filter_map(|declaration| declaration.split_once(':'))
+            .rfind(|(key, _)| key.trim() == name)
+            .map(|(_, value)| value.trim())
+    });
+    from_style.or_else(|| node.attribute(name).map(str::trim))
+}
+
+/// Parses a length or a number, returns the value and whether it's a percentage.
+/// Units other than percents are ignored.
+fn parse_length(value: &str) -> Option<(f32, bool)> {
+    let value = value.trim();
+    if let Some(percent) = value.strip_suffix('%') {
+        return percent.trim().parse::<f32>().ok().map(|percent| (percent / 100., true));
+    }
+    let number = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    number.parse::<f32>().ok().map(|number| (number, false))
+}
+
+/// Parses a length, resolving percentages against the extent.
+fn resolve_length(value: &str, extent: f32) -> Option<f32> {
+    parse_length(value).map(|(value, percent)| if percent { value * extent } else { value })
+}
+
+fn length(node: XmlNode, name: &str, default: f32, extent: f32) -> f32 {
+    node.attribute(name).and_then(|value| resolve_length(value, extent)).unwrap_or(default)
+}
+
+/// Returns the extent percentages of lengths which are neither horizontal nor vertical (radii, stroke widths) are relative to.
+fn diagonal(view_box: ViewBox) -> f32 {
+    (view_box[2] * view_box[2] + view_box[3] * view_box[3]).sqrt() / std::f32::consts::SQRT_2
+}
+
+fn parse_numbers(value: &str) -> Vec<f32> {
+    value.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    svgtypes::Color::from_str(value).ok()
+        .map(|color| Color::rgba(color.red, color.green, color.blue, color.alpha))
+}
+
+fn parse_transform(value: &str) -> Option<Transform2D> {
+    svgtypes::Transform::from_str(value).ok().map(|t| {
+        Transform2D([t.a as f32, t.b as f32, t.c as f32, t.d as f32, t.e as f32, t.f as f32])
+    })
+}
+
+fn with_opacity(mut color: Color, opacity: f32) -> Color {
+    color.a *= opacity;
+    color
+}
+
+/// Parses the value of `fill` or `stroke`. Returns `Err` for `inherit` and unsupported values.
+fn parse_paint(value: &str, presentation: &Presentation, context: &ParseContext) -> Result<Option<SvgPaint>, ()> {
+    match value {
+        "none" => return Ok(None),
+        "currentColor" => return Ok(Some(SvgPaint::Color(presentation.color))),
+        "inherit" => return Err(()),
+        _ => {}
+    }
+    if let Some(reference) = value.strip_prefix("url(") {
+        let (id, fallback) = reference.split_once(')').unwrap_or((reference, ""));
+        let id = id.trim().trim_matches(|c| c == '\'' || c == '"').trim_start_matches('#');
+        return match context.gradients.get(id) {
+            Some(gradient) => Ok(Some(SvgPaint::Gradient(gradient.clone()))),
+            None => match fallback.trim() {
+                "" => Ok(None),
+                fallback => parse_paint(fallback, presentation, context)
+            }
+        };
+    }
+    parse_color(value).map(|color| Some(SvgPaint::Color(color))).ok_or(())
+}
+
+fn presentation(node: XmlNode, parent: &Presentation, context: &ParseContext) -> Presentation {
+    let mut result = parent.clone();
+    let number = |name: &str| property(node, name).and_then(parse_length).map(|(value, _)| value);
+    if let Some(color) = property(node, "color").and_then(parse_color) {
+        result.color = color;
+    }
+    if let Some(Ok(fill)) = property(node, "fill").map(|value| parse_paint(value, &result, context)) {
+        result.fill = fill;
+    }
+    if let Some(Ok(stroke)) = property(node, "stroke").map(|value| parse_paint(value, &result, context)) {
+        result.stroke = stroke;
+    }
+    if let Some(width) = property(node, "stroke-width").and_then(|value| resolve_length(value, diagonal(context.view_box))) {
+        result.stroke_width = width;
+    }
+    match property(node, "stroke-linecap") {
+        Some("butt") => result.line_cap = LineCap::Butt,
+        Some("round") => result.line_cap = LineCap::Round,
+        Some("square") => result.line_cap = LineCap::Square,
+        _ => {}
+    }
+    match property(node, "stroke-linejoin") {
+        Some("miter") => result.line_join = LineJoin::Miter,
+        Some("round") => result.line_join = LineJoin::Round,
+        Some("bevel") => result.line_join = LineJoin::Bevel,
+        _ => {}
+    }
+    match property(node, "fill-rule") {
+        Some("nonzero") => result.fill_rule = FillRule::NonZero,
+        Some("evenodd") => result.fill_rule = FillRule::EvenOdd,
+        _ => {}
+    }
+    // percentages of opacity are fractions already
+    if let Some(opacity) = number("fill-opacity") {
+        result.fill_opacity = opacity;
+    }
+    if let Some(opacity) = number("stroke-opacity") {
+        result.stroke_opacity = opacity;
+    }
+    if let Some(opacity) = number("opacity") {
+        result.opacity *= opacity;
+    }
+    result
+}
+
+/// Path with the bounding box of its points, needed for gradients in bounding box units.
+struct PathBuilder {
+    path: Path,
+    bounds: [f32; 4]
+}
+
+impl PathBuilder {
+    fn new() -> PathBuilder {
+        PathBuilder {
+            path: Path::new(),
+            bounds: [f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY]
+        }
+    }
+
+    /// Extends the bounds by the point. Control points are included, so the bounds may be larger than the shape.
+    fn point(&mut self, x: f32, y: f32) {
+        self.bounds = [self.bounds[0].min(x), self.bounds[1].min(y), self.bounds[2].max(x), self.bounds[3].max(y)];
+    }
+
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.point(x, y);
+        self.point(x + width, y + height);
+    }
+}
+
+/// Builds the path of the shape. Percentages are relative to the view box.
+fn build_path(node: XmlNode, view_box: ViewBox) -> Option<PathBuilder> {
+    let mut builder = PathBuilder::new();
+    let (width, height, diagonal) = (view_box[2], view_box[3], diagonal(view_box));
+    let x = |name: &str| length(node, name, 0., width);
+    let y = |name: &str| length(node, name, 0., height);
+    match node.tag_name().name() {
+        "path" => {
+            for segment in SimplifyingPathParser::from(node.attribute("d")?) {
+                // like browsers, render the path up to the first error
+                let Ok(segment) = segment else { break; };
+                match segment {
+                    SimplePathSegment::MoveTo { x, y } => {
+                        builder.path.move_to(x as f32, y as f32);
+                        builder.point(x as f32, y as f32);
+                    },
+                    SimplePathSegment::LineTo { x, y } => {
+                        builder.path.line_to(x as f32, y as f32);
+                        builder.point(x as f32, y as f32);
+                    },
+                    SimplePathSegment::CurveTo { x1, y1, x2, y2, x, y } => {
+                        builder.path.bezier_to(x1 as f32, y1 as f32, x2 as f32, y2 as f32, x as f32, y as f32);
+                        builder.point(x1 as f32, y1 as f32);
+                        builder.point(x2 as f32, y2 as f32);
+                        builder.point(x as f32, y as f32);
+                    },
+                    SimplePathSegment::Quadratic { x1, y1, x, y } => {
+                        builder.path.quad_to(x1 as f32, y1 as f32, x as f32, y as f32);
+                        builder.point(x1 as f32, y1 as f32);
+                        builder.point(x as f32, y as f32);
+                    },
+                    SimplePathSegment::ClosePath => builder.path.close()
+                }
+            }
+        },
+        "rect" => {
+            let (rx, ry) = (node.attribute("rx").and_then(|rx| resolve_length(rx, width)), node.attribute("ry").and_then(|ry| resolve_length(ry, height)));
+            let (x, y, width, height) = (x("x"), y("y"), x("width"), y("height"));
+            if width <= 0. || height <= 0. {
+                return None;
+            }
+            // a missing radius is the same as the other one
+            let (rx, ry) = match (rx, ry) {
+                (Some(rx), Some(ry)) => (rx, ry),
+                (Some(r), None) | (None, Some(r)) => (r, r),
+                (None, None) => (0., 0.)
+            };
+            let corner = CornerRadius::elliptical(rx.min(width / 2.), ry.min(height / 2.));
+            BorderRadius { top_left: corner, top_right: corner, bottom_right: corner, bottom_left: corner }
+                .add_to_path(&mut builder.path, x, y, width, height);
+            builder.rect(x, y, width, height);
+        },
+        "circle" => {
+            let (cx, cy, r) = (x("cx"), y("cy"), length(node, "r", 0., diagonal));
+            if r <= 0. {
+                return None;
+            }
+            builder.path.circle(cx, cy, r);
+            builder.rect(cx - r, cy - r, r * 2., r * 2.);
+        },
+        "ellipse" => {
+            let (cx, cy, rx, ry) = (x("cx"), y("cy"), x("rx"), y("ry"));
+            if rx <= 0. || ry <= 0. {
+                return None;
+            }
+            builder.path.ellipse(cx, cy, rx, ry);
+            builder.rect(cx - rx, cy - ry, rx * 2., ry * 2.);
+        },
+        "line" => {
+            let (x1, y1, x2, y2) = (x("x1"), y("y1"), x("x2"), y("y2"));
+            builder.path.move_to(x1, y1);
+            builder.path.line_to(x2, y2);
+            builder.point(x1, y1);
+            builder.point(x2, y2);
+        },
+        "polyline" | "polygon" => {
+            let points = parse_numbers(node.attribute("points")?);
+            let mut pairs = points.chunks_exact(2);
+            let first = pairs.next()?;
+            builder.path.move_to(first[0], first[1]);
+            builder.point(first[0], first[1]);
+            for point in pairs {
+                builder.path.line_to(point[0], point[1]);
+                builder.point(point[0], point[1]);
+            }
+            if node.tag_name().name() == "polygon" {
+                builder.path.close();
+            }
+        },
+        _ => return None
+    }
+    Some(builder)
+}
+
+fn to_paint(paint: &SvgPaint, opacity: f32, bounds: [f32; 4]) -> Paint {
+    match paint {
+        SvgPaint::Color(color) => Paint::color(with_opacity(*color, opacity)),
+        SvgPaint::Gradient(gradient) => {
+            let [x1, y1, x2, y2] = gradient.coords;
+            let stops = gradient.stops.iter().map(|(offset, color)| (*offset, with_opacity(*color, opacity)));
+            let (x, y, width, height) = match gradient.bounding_box_units {
+                true => (bounds[0], bounds[1], bounds[2] - bounds[0], bounds[3] - bounds[1]),
+                false => (0., 0., 1., 1.)
+            };
+            // maps a point of the gradient to user space, through the gradient transform and then the bounding box
+            let to_user = |px: f32, py: f32| {
+                let (px, py) = gradient.transform.transform_point(px, py);
+                (x + px * width, y + py * height)
+            };
+            let (start_x, start_y) = to_user(x1, y1);
+            if gradient.radial {
+                // non-square bounding boxes would need an elliptic gradient, use the average size instead.
+                // Transforms are similarities (see parse_gradients), which scale the radius evenly
+                let [a, b, c, d, _, _] = gradient.transform.0;
+                let radius = x2 * (a * d - b * c).abs().sqrt() * (width + height) / 2.;
+                Paint::radial_gradient_stops(start_x, start_y, 0., radius, stops)
+            } else {
+                let (end_x, end_y) = linear_gradient_end(x1, y1, x2, y2, to_user);
+                Paint::linear_gradient_stops(start_x, start_y, end_x, end_y, stops)
+            }
+        }
+    }
+}
+
+/// Returns the end of a linear gradient from (x1, y1) to (x2, y2) once mapped to user space, for a gradient starting at the mapped start.
+///
+/// Skews and uneven scales tilt the lines of equal color of a mapped gradient relative to its direction, while they stay
+/// perpendicular to the direction of a femtovg gradient. They stay parallel though, so the mapped gradient is still a
+/// linear gradient: the end is moved along the normal of these lines, to where the gradient reaches its last stop.
+fn linear_gradient_end(x1: f32, y1: f32, x2: f32, y2: f32, to_user: impl Fn(f32, f32) -> (f32, f32)) -> (f32, f32) {
+    let (start_x, start_y) = to_user(x1, y1);
+    let ((origin_x, origin_y), (ax, ay), (bx, by)) = (to_user(0., 0.), to_user(1., 0.), to_user(0., 1.));
+    // columns of the linear part of the mapping
+    let (ax, ay, bx, by) = (ax - origin_x, ay - origin_y, bx - origin_x, by - origin_y);
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let determinant = ax * by - ay * bx;
+    let length = dx * dx + dy * dy;
+    if determinant == 0. || length == 0. {
+        return to_user(x2, y2);
+    }
+    // gradient of the stop offset in user space
+    let gx = (dx * by - ay * dy) / (determinant * length);
+    let gy = (ax * dy - bx * dx) / (determinant * length);
+    let magnitude = gx * gx + gy * gy;
+    (start_x + gx / magnitude, start_y + gy / magnitude)
+}
+
+fn parse_shape(node: XmlNode, presentation: &Presentation, context: &ParseContext) -> Option<SvgElement> {
+    let builder = build_path(node, context.view_box)?;
+    let fill = presentation.fill.as_ref().map(|fill| {
+        let mut paint = to_paint(fill, presentation.fill_opacity * presentation.opacity, builder.bounds);
+        paint.set_fill_rule(presentation.fill_rule);
+        paint
+    });
+    let stroke = presentation.stroke.as_ref().filter(|_| presentation.stroke_width > 0.).map(|stroke| {
+        let mut paint = to_paint(stroke, presentation.stroke_opacity * presentation.opacity, builder.bounds);
+        paint.set_line_width(presentation.stroke_width);
+        paint.set_line_cap(presentation.line_cap);
+        paint.set_line_join(presentation.line_join);
+        paint
+    });
+    Some(SvgElement::Shape(Box::new(SvgShape {
+        transform: node.attribute("transform").and_then(parse_transform),
+        path: builder.path,
+        fill,
+        stroke
+    })))
+}
+
+fn parse_children(node: XmlNode, parent: &Presentation, context: &ParseContext) -> Vec<SvgElement> {
+    let mut elements = Vec::new();
+    for child in node.children().filter(|child| child.is_element()) {
+        if property(child, "display") == Some("none") {
+            continue;
+        }
+        let presentation = presentation(child, parent, context);
+        match child.tag_name().name() {
+            "g" | "a" | "svg" => elements.push(SvgElement::Group {
+                transform: child.attribute("transform").and_then(parse_transform),
+                children: parse_children(child, &presentation, context)
+            }),
+            // everything else (defs, text, images, filters...) is not supported
+            _ => elements.extend(parse_shape(child, &presentation, context))
+        }
+    }
+    elements
+}
+
+/// Returns the `href` of the element, with or without the xlink namespace.
+fn href<'a>(node: XmlNode<'a, '_>) -> Option<&'a str> {
+    node.attribute(("http://www.w3.org/1999/xlink", "href"))
+        .or_else(|| node.attribute("href"))
+        .map(|href| href.trim_start_matches('#'))
+}
+
+fn parse_stops(node: XmlNode) -> Vec<(f32, Color)> {
+    let mut last = 0.;
+    node.children()
+        .filter(|child| child.tag_name().name() == "stop")
+        .map(|stop| {
+            let offset = stop.attribute("offset").and_then(parse_length).map(|(offset, _)| offset).unwrap_or(0.);
+            // offsets can't decrease
+            last = offset.clamp(last, 1.);
+            let color = property(stop, "stop-color").and_then(parse_color).unwrap_or(Color::black());
+            let opacity = property(stop, "stop-opacity").and_then(parse_length).map(|(opacity, _)| opacity).unwrap_or(1.);
+            (last, with_opacity(color, opacity))
+        })
+        .collect()
+}
+
+/// Returns whether the transform only translates, rotates, mirrors and scales evenly, keeping circles circular.
+fn is_similarity(transform: &Transform2D) -> bool {
+    let [a, b, c, d, _, _] = transform.0;
+    let epsilon = 1e-4 * (a.abs() + b.abs() + c.abs() + d.abs());
+    ((a - d).abs() <= epsilon && (b + c).abs() <= epsilon) || ((a + d).abs() <= epsilon && (b - c).abs() <= epsilon)
+}
+
+fn parse_gradients(document: &Document, view_box: ViewBox) -> HashMap<String, Gradient> {
+    let nodes: HashMap<&str, XmlNode> = document.descendants()
+        .filter(|node| matches!(node.tag_name().name(), "linearGradient" | "radialGradient"))
+        .filter_map(|node| node.attribute("id").map(|id| (id, node)))
+        .collect();
+    let mut gradients = HashMap::new();
+    for (id, node) in &nodes {
+        let radial = node.tag_name().name() == "radialGradient";
+        let bounding_box_units = node.attribute("gradientUnits") != Some("userSpaceOnUse");
+        // percentages are relative to the bounding box, or to the view box in user space
+        let coord = |name: &str, default: f32, extent: f32| match node.attribute(name).and_then(parse_length) {
+            Some((value, true)) if !bounding_box_units => value * extent,
+            Some((value, _)) => value,
+            None if bounding_box_units => default,
+            None => default * extent
+        };
+        let (width, height) = (view_box[2], view_box[3]);
+        let transform = node.attribute("gradientTransform").and_then(parse_transform).unwrap_or_else(Transform2D::identity);
+        if radial && !is_similarity(&transform) {
+            // skews and uneven scales would need an elliptic gradient, which femtovg can't draw. Shapes using it get the
+            // fallback of their paint (or no paint), instead of a gradient of the wrong shape
+            continue;
+        }
+        let coords = match radial {
+            true => [coord("cx", 0.5, width), coord("cy", 0.5, height), coord("r", 0.5, diagonal(view_box)), 0.],
+            false => [coord("x1", 0., width), coord("y1", 0., height), coord("x2", 1., width), coord("y2", 0., height)]
+        };
+        // stops can be inherited from the referenced gradient
+        let mut stops = parse_stops(*node);
+        let mut referenced = href(*node);
+        while stops.is_empty() {
+            let Some(other) = referenced.and_then(|id| nodes.get(id)) else { break; };
+            stops = parse_stops(*other);
+            referenced = href(*other);
+        }
+        gradients.insert(id.to_string(), Gradient { radial, coords, bounding_box_units, transform, stops });
+    }
+    gradients
+}
+
+impl SvgDocument {
+    /// Parses the supported subset of SVG: paths, basic shapes, groups, transforms, solid fills and gradients.
+    /// Unsupported elements are skipped, as are radial gradients whose `gradientTransform` skews or scales unevenly.
+    /// Percentages are relative to the view box of the root element.
+    pub fn parse(source: &str) -> Result<SvgDocument, String> {
+        let document = Document::parse(source).map_err(|e| e.to_string())?;
+        let root = document.root_element();
+        if root.tag_name().name() != "svg" {
+            return Err("Root element is not <svg>".to_owned());
+        }
+        let width = root.attribute("width").and_then(parse_length).filter(|(_, percent)| !percent).map(|(width, _)| width);
+        let height = root.attribute("height").and_then(parse_length).filter(|(_, percent)| !percent).map(|(height, _)| height);
+        let view_box = match root.attribute("viewBox").map(parse_numbers) {
+            Some(numbers) if numbers.len() == 4 => [numbers[0], numbers[1], numbers[2], numbers[3]],
+            _ => [0., 0., width.unwrap_or(0.), height.unwrap_or(0.)]
+        };
+        let size = Size { width: width.unwrap_or(view_box[2]), height: height.unwrap_or(view_box[3]) };
+        let context = ParseContext { gradients: parse_gradients(&document, view_box), view_box };
+        let presentation = presentation(root, &Presentation::default(), &context);
+        let elements = parse_children(root, &presentation, &context);
+        Ok(SvgDocument { view_box: context.view_box, size, elements })
+    }
+}
+
+fn render_elements(elements: &[SvgElement], canvas: &mut CanvasRenderer) {
+    for element in elements {
+        match element {
+            SvgElement::Group { transform, children } => {
+                canvas.save();
+                if let Some(transform) = transform {
+                    canvas.set_transform(transform);
+                }
+                render_elements(children, canvas);
+                canvas.restore();
+            },
+            SvgElement::Shape(shape) => {
+                canvas.save();
+                if let Some(transform) = &shape.transform {
+                    canvas.set_transform(transform);
+                }
+                if let Some(fill) = &shape.fill {
+                    canvas.fill_path(&shape.path, fill);
+                }
+                if let Some(stroke) = &shape.stroke {
+                    canvas.stroke_path(&shape.path, stroke);
+                }
+                canvas.restore();
+            }
+        }
+    }
+}
+
+/// Renders an SVG image, scaled to the size of the node (keeping the aspect ratio).
+/// The node is sized to the SVG if it doesn't have a size set.
+///
+/// Only a subset of SVG is supported, see [`SvgDocument::parse`].
+#[derive(Debug)]
+pub struct Svg {
+    pub style: Style,
+    pub document: SvgDocument,
+    pub events: EventHandlerDatabase,
+    pub parent: Option<WeakNode>
+}
+
+impl Svg {
+    pub fn new(document: SvgDocument) -> Svg {
+        Svg {
+            style: Style::default(),
+            document,
+            events: EventHandlerDatabase::default(),
+            parent: None
+        }
+    }
+    pub fn parse(source: &str) -> Result<Svg, String> {
+        SvgDocument::parse(source).map(Svg::new)
+    }
+    pub fn from_file(path: impl AsRef<FilePath>) -> Result<Svg, String> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Svg::parse(&source)
+    }
+    pub fn style(mut self, style: Style) -> Svg {
+        self.style = style;
+        self
+    }
+}
+
+impl Node for Svg {
+    fn style(&self) -> &Style {
+        &self.style
+    }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
+    fn children(&self) -> Option<&NodeChildren> {
+        None
+    }
+
+    fn render_pre_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        context.canvas.save();
+        apply_view_box(self.document.view_box, layout.size, &mut context.canvas);
+        render_elements(&self.document.elements, &mut context.canvas);
+        context.canvas.restore();
+    }
+
+    fn measure(&mut self, _context: &mut MeasureContext, known_dimensions: Size<Option<f32>>, _available_space: Size<AvailableSpace>) -> Size<f32> {
+        measure_aspect(self.document.size, known_dimensions)
+    }
+
+    fn event_handlers(&self) -> Option<crate::events::handler::InnerEventHandlerDataset> {
+        Some(self.events.handlers.clone())
+    }
+    fn set_parent(&mut self, parent: Option<WeakNode>) {
+        self.parent = parent;
+    }
+    fn parent(&self) -> Option<SharedNode> {
+        match &self.parent {
+            Some(parent) => parent.upgrade(),
+            None => None
+        }
+    }
+}
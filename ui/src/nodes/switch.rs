@@ -0,0 +1,182 @@
+use std::fmt::{Debug, Formatter};
+use std::time::Duration;
+use femtovg::Path;
+use taffy::{Layout, Size};
+use crate::{nodes::{Node, NodeChildren, Style}, events::handler::EventHandlerDatabase, WeakNode, SharedNode};
+use crate::events::{ControlValue, InnerEvent, NodeEvent};
+use crate::nodes::interaction::Activation;
+use crate::nodes::checkbox::{indicator_style, label, translate_to_indicator, ToggleColors};
+use crate::nodes::primitives::{draw_control_background, draw_focus_ring};
+use crate::nodes::{BorderRadius, RenderContext};
+
+/// Width of the track of switches, in pixels
+pub const SWITCH_WIDTH: f32 = 36.;
+/// Height of the track of switches, in pixels
+pub const SWITCH_HEIGHT: f32 = 20.;
+/// Space between the thumb and the edge of the track, in pixels
+const THUMB_MARGIN: f32 = 2.;
+/// Duration of the thumb animation when the switch is toggled
+const TOGGLE_DURATION: Duration = Duration::from_millis(120);
+
+/// On/off switch with an optional label (its children). Clicking it or pressing Space or Enter while it's focused toggles it
+/// and sends [`InnerEvent::Change`] with [`ControlValue::Toggle`].
+pub struct Switch {
+    pub style: Style,
+    pub children: NodeChildren,
+    pub events: EventHandlerDatabase,
+    pub parent: Option<WeakNode>,
+    pub checked: bool,
+    pub colors: ToggleColors,
+    activation: Activation,
+    /// Position of the thumb, from 0 (off) to 1 (on). Follows `checked` with an animation
+    position: f32
+}
+
+impl Default for Switch {
+    fn default() -> Self {
+        Switch::new(false)
+    }
+}
+
+impl Switch {
+    pub fn new(checked: bool) -> Switch {
+        Switch {
+            style: indicator_style(SWITCH_WIDTH),
+            children: NodeChildren::default(),
+            events: EventHandlerDatabase::default(),
+            parent: None,
+            checked,
+            colors: ToggleColors::default(),
+            activation: Activation::default(),
+            position: if checked { 1. } else { 0. }
+        }
+    }
+
+    /// Switch followed by a text label, colored with the `text` token.
+    pub fn with_label(checked: bool, text: impl Into<String>) -> Switch {
+        let mut switch = Switch::new(checked);
+        switch.children.push(label(text.into()));
+        switch
+    }
+
+    pub fn style(mut self, style: Style) -> Switch {
+        self.style = style;
+        self
+    }
+
+    pub fn colors(mut self, colors: ToggleColors) -> Switch {
+        self.colors = colors;
+        self
+    }
+}
+
+impl Debug for Switch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Switch")
+            .field("style", &self.style)
+            .field("checked", &self.checked)
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+impl Node for Switch {
+    fn style(&self) -> &Style {
+        &self.style
+    }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
+    fn children(&self) -> Option<&NodeChildren> {
+        Some(&self.children)
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn prepare_render(&mut self, context: &mut RenderContext) {
+        let target = if self.checked { 1. } else { 0. };
+        if self.position == target {
+            return;
+        }
+        // the first frame after idling would otherwise skip the whole animation
+        let step = context.frame_delta.as_secs_f32().min(1. / 30.) / TOGGLE_DURATION.as_secs_f32();
+        self.position = match target > self.position {
+            true => (self.position + step).min(target),
+            false => (self.position - step).max(target)
+        };
+        context.request_frame();
+    }
+
+    fn on_event(&mut self, event: &NodeEvent, context: &mut RenderContext) {
+        if self.activation.handle(event, context, self.style.disabled, true) {
+            self.checked = !self.checked;
+            context.emit(InnerEvent::Change(ControlValue::Toggle(self.checked)));
+            context.request_frame();
+        }
+    }
+
+    fn render_pre_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        let style = self.activation.resolve_style(&self.style, context);
+        draw_control_background(&style, layout.size, context);
+        let state = self.activation.state(context);
+        let size = Size { width: SWITCH_WIDTH, height: SWITCH_HEIGHT };
+        let radius = BorderRadius::all(SWITCH_HEIGHT / 2.);
+        context.canvas.save();
+        translate_to_indicator(size, &layout, context);
+        let track = radius.to_path(size);
+        if self.checked {
+            context.canvas.fill_path(&track, &self.colors.checked.paint(&context.theme));
+        } else {
+            context.canvas.fill_path(&track, &self.colors.unchecked.paint(&context.theme));
+            let border = if state.hovered { &self.colors.checked } else { &self.colors.border };
+            let mut outline = Path::new();
+            radius.add_to_path(&mut outline, 0.75, 0.75, size.width - 1.5, size.height - 1.5);
+            context.canvas.stroke_path(&outline, &border.paint(&context.theme).with_line_width(1.5));
+        }
+        let thumb_radius = SWITCH_HEIGHT / 2. - THUMB_MARGIN;
+        // the thumb grows a little while pressed
+        let thumb_radius = if state.pressed { thumb_radius + 1. } else { thumb_radius };
+        let travel = SWITCH_WIDTH - SWITCH_HEIGHT;
+        let mut thumb = Path::new();
+        thumb.circle(SWITCH_HEIGHT / 2. + travel * self.position, SWITCH_HEIGHT / 2., thumb_radius);
+        let thumb_paint = if self.checked { &self.colors.mark } else { &self.colors.border };
+        context.canvas.fill_path(&thumb, &thumb_paint.paint(&context.theme));
+        if state.focused {
+            draw_focus_ring(&radius, size, context);
+        }
+        context.canvas.restore();
+    }
+
+    fn add_child_at(&mut self, child: SharedNode, index: usize) -> Result<(), super::ChildAddError> {
+        let mut index = index;
+        if let Some(i) = self.has_child(&child) {
+            self.children.remove(i);
+            if i < index {
+                index -= 1;
+            }
+        }
+        self.children.insert(index, child);
+        Ok(())
+    }
+
+    fn event_handlers(&self) -> Option<crate::events::handler::InnerEventHandlerDataset> {
+        Some(self.events.handlers.clone())
+    }
+    fn set_parent(&mut self, parent: Option<WeakNode>) {
+        self.parent = parent;
+    }
+    fn parent(&self) -> Option<SharedNode> {
+        match &self.parent {
+            Some(parent) => parent.upgrade(),
+            None => None
+        }
+    }
+    fn remove_child(&mut self, child: &SharedNode) -> Result<(), super::ChildAddError> {
+        if let Some(i) = self.has_child(child) {
+            self.children.remove(i);
+        }
+        Ok(())
+    }
+}
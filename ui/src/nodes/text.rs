@@ -4,7 +4,7 @@ use super::{text_render_cache::RENDER_CACHE, Node, NodeChildren, Style, MeasureC
 use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
 use taffy::{AvailableSpace, Size};
 use femtovg::{Color, Paint, Path};
-use crate::nodes::primitives::draw_rect;
+use crate::nodes::primitives::draw_background;
 use crate::nodes::text_render_cache::TextConfig;
 
 #[derive(Debug, Default)]
@@ -13,7 +13,9 @@ pub struct Text {
     pub text: String,
     pub events: EventHandlerDatabase,
     pub parent: Option<WeakNode>,
-    pub buffer: Option<Buffer>
+    pub buffer: Option<Buffer>,
+    /// Text last set to the buffer, so that the text isn't shaped again on every frame
+    pub shaped_text: Option<String>
 }
 
 impl Text {
@@ -32,11 +34,11 @@ impl Text {
         self
     }
     
-    fn get_metrics(&self) -> Metrics {
-        let fontSize = self.style.font_size.unwrap_or(16.);
+    fn get_metrics(style: &Style) -> Metrics {
+        let fontSize = style.font_size.unwrap_or(16.);
         Metrics {
             font_size: fontSize,
-            line_height: fontSize * self.style.line_height.unwrap_or(1.2)
+            line_height: fontSize * style.line_height.unwrap_or(1.2)
         }
     }
 }
@@ -45,23 +47,35 @@ impl Node for Text {
     fn style(&self) -> &Style {
         &self.style
     }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.style)
+    }
 
     fn children(&self) -> Option<&NodeChildren> {
         None
     }
 
-    fn prepare_render(&mut self, _context: &mut RenderContext) {
+    fn prepare_render(&mut self, context: &mut RenderContext) {
         if let None = self.buffer {
-            self.buffer = Some(Buffer::new(&mut FONT_SYSTEM.lock().unwrap(), self.get_metrics()));
+            let metrics = Self::get_metrics(&context.resolve_style(&self.style));
+            self.buffer = Some(Buffer::new(&mut FONT_SYSTEM.lock().unwrap(), metrics));
+            self.shaped_text = None;
+        }
+        if self.shaped_text.as_ref() == Some(&self.text) {
+            return;
         }
         let buf = self.buffer.as_mut().unwrap();
         let mut font = FONT_SYSTEM.lock().unwrap();
         buf.set_text(&mut font, &self.text, Attrs::new(), Shaping::Advanced);
+        self.shaped_text = Some(self.text.clone());
+        // size of the text most likely changed
+        context.request_relayout();
     }
 
     fn render_pre_children(&mut self, context: &mut super::RenderContext, layout: taffy::prelude::Layout) {
-        if let Some(background) = &self.style.background { draw_rect(layout.size, background, self.style.border_radius, &mut context.canvas); }
-        let metrics = self.get_metrics();
+        let style = context.resolve_style(&self.style);
+        draw_background(&style, layout.size, context);
+        let metrics = Self::get_metrics(&style);
         // this can crash, but it should crash earlier during measure -> see the comment there.
         let buf = self.buffer.as_mut().unwrap();
         let mut font = FONT_SYSTEM.lock().unwrap();
@@ -81,7 +95,7 @@ impl Node for Text {
         let cmds = RENDER_CACHE.lock().unwrap()
             .fill_to_cmds(&mut context.canvas, buf, position, context.scale_factor, TextConfig { hint: false, subpixel: false })
             .unwrap();
-        context.canvas.draw_glyph_commands(cmds, self.style.text_fill.as_ref().unwrap_or(&Paint::color(Color::black())), context.scale_factor);
+        context.canvas.draw_glyph_commands(cmds, style.text_fill.as_ref().unwrap_or(&Paint::color(Color::black())), context.scale_factor);
     }
 
     fn measure(&mut self, context: &mut MeasureContext, known_dimensions: Size<Option<f32>>, available_space: Size<AvailableSpace>) -> Size<f32> {
@@ -90,7 +104,7 @@ impl Node for Text {
             AvailableSpace::MaxContent => f32::INFINITY,
             AvailableSpace::Definite(width) => width,
         });
-        let metrics = self.get_metrics();
+        let metrics = Self::get_metrics(&context.resolve_style(&self.style));
         // yes, this can crash if someone removes `buffer` during render from another thread.
         // though they're asking for it, so let them crash.
         let buf = self.buffer.as_mut().unwrap();
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use femtovg::{Color, Paint};
+use taffy::{LengthPercentage, Rect, Size};
+use crate::nodes::background::Background;
+use crate::nodes::{BorderRadius, Style};
+
+lazy_static::lazy_static! {
+    static ref THEMES: Mutex<Themes> = Mutex::new(Themes::default());
+}
+
+/// Light or dark appearance, reported by the OS or chosen by the user
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ThemeMode {
+    Light,
+    #[default]
+    Dark
+}
+
+/// Font size and line height (multiplier of the font size) of a typography token
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Typography {
+    pub font_size: f32,
+    pub line_height: f32
+}
+
+impl Typography {
+    pub fn new(font_size: f32, line_height: f32) -> Self {
+        Typography { font_size, line_height }
+    }
+}
+
+/// Named design tokens styles can refer to through [`StyleTokens`]. Switching the theme restyles the whole tree.
+///
+/// The built-in [`Theme::light`] and [`Theme::dark`] themes define the tokens used by mangui widgets:
+/// - colors: `background`, `surface`, `text`, `text-muted`, `accent`, `accent-hover`, `accent-pressed`, `on-accent`, `border`, `focus`
+/// - spacing: `xs`, `sm`, `md`, `lg`, `xl`
+/// - radii: `sm`, `md`, `lg`, `full`
+/// - typography: `small`, `body`, `title`, `headline`
+#[derive(Clone, Debug, Default)]
+pub struct Theme {
+    pub mode: ThemeMode,
+    pub colors: HashMap<String, Color>,
+    pub spacing: HashMap<String, f32>,
+    pub radii: HashMap<String, f32>,
+    pub typography: HashMap<String, Typography>
+}
+
+impl Theme {
+    pub fn new(mode: ThemeMode) -> Self {
+        Theme { mode, ..Default::default() }
+    }
+
+    pub fn light() -> Self {
+        Theme::new(ThemeMode::Light)
+            .color("background", Color::hex("FFFFFF"))
+            .color("surface", Color::hex("F2F3F5"))
+            .color("text", Color::hex("1F2328"))
+            .color("text-muted", Color::hex("59636E"))
+            .color("accent", Color::hex("0969DA"))
+            .color("accent-hover", Color::hex("0860CA"))
+            .color("accent-pressed", Color::hex("0757BA"))
+            .color("on-accent", Color::hex("FFFFFF"))
+            .color("border", Color::hex("D0D7DE"))
+            .color("focus", Color::hex("0969DA"))
+            .with_default_metrics()
+    }
+
+    pub fn dark() -> Self {
+        Theme::new(ThemeMode::Dark)
+            .color("background", Color::hex("282C34"))
+            .color("surface", Color::hex("32363E"))
+            .color("text", Color::hex("FFFFFF"))
+            .color("text-muted", Color::hex("ABB2BF"))
+            .color("accent", Color::hex("61AFEF"))
+            .color("accent-hover", Color::hex("7ABCF2"))
+            .color("accent-pressed", Color::hex("93C9F5"))
+            .color("on-accent", Color::hex("282C34"))
+            .color("border", Color::hex("4B5263"))
+            .color("focus", Color::hex("61AFEF"))
+            .with_default_metrics()
+    }
+
+    fn with_default_metrics(self) -> Self {
+        self
+            .spacing("xs", 4.)
+            .spacing("sm", 8.)
+            .spacing("md", 12.)
+            .spacing("lg", 16.)
+            .spacing("xl", 24.)
+            .radius("sm", 4.)
+            .radius("md", 8.)
+            .radius("lg", 16.)
+            .radius("full", 9999.)
+            .typography("small", Typography::new(14., 1.3))
+            .typography("body", Typography::new(16., 1.25))
+            .typography("title", Typography::new(20., 1.2))
+            .typography("headline", Typography::new(24., 1.25))
+    }
+
+    pub fn color(mut self, name: &str, color: Color) -> Self {
+        self.colors.insert(name.to_owned(), color);
+        self
+    }
+
+    pub fn spacing(mut self, name: &str, spacing: f32) -> Self {
+        self.spacing.insert(name.to_owned(), spacing);
+        self
+    }
+
+    pub fn radius(mut self, name: &str, radius: f32) -> Self {
+        self.radii.insert(name.to_owned(), radius);
+        self
+    }
+
+    pub fn typography(mut self, name: &str, typography: Typography) -> Self {
+        self.typography.insert(name.to_owned(), typography);
+        self
+    }
+
+    /// Returns the color of the token, or transparent if the theme doesn't define it.
+    pub fn get_color(&self, name: &str) -> Color {
+        self.colors.get(name).copied().unwrap_or(Color::rgba(0, 0, 0, 0))
+    }
+
+    pub fn get_spacing(&self, name: &str) -> Option<f32> {
+        self.spacing.get(name).copied()
+    }
+
+    pub fn get_radius(&self, name: &str) -> Option<f32> {
+        self.radii.get(name).copied()
+    }
+
+    pub fn get_typography(&self, name: &str) -> Option<Typography> {
+        self.typography.get(name).copied()
+    }
+}
+
+/// Paint of a control which follows the theme, like the fill of a checked checkbox
+#[derive(Clone, Debug)]
+pub enum ThemePaint {
+    /// Color token of the theme
+    Token(&'static str),
+    /// Paint used regardless of the theme
+    Paint(Paint)
+}
+
+impl ThemePaint {
+    /// Returns the paint for the theme. Tokens the theme doesn't define are transparent.
+    pub fn paint(&self, theme: &Theme) -> Paint {
+        match self {
+            ThemePaint::Token(name) => Paint::color(theme.get_color(name)),
+            ThemePaint::Paint(paint) => paint.clone()
+        }
+    }
+}
+
+impl From<&'static str> for ThemePaint {
+    fn from(token: &'static str) -> Self {
+        ThemePaint::Token(token)
+    }
+}
+
+impl From<Paint> for ThemePaint {
+    fn from(paint: Paint) -> Self {
+        ThemePaint::Paint(paint)
+    }
+}
+
+/// References to theme tokens, overriding the matching literal properties of the style (after variants are applied).
+/// Tokens the theme doesn't define leave the literal properties as they are.
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct StyleTokens {
+    /// Color token of the background
+    pub background: Option<&'static str>,
+    /// Color token of the text
+    pub text_fill: Option<&'static str>,
+    /// Typography token setting the font size and line height
+    pub typography: Option<&'static str>,
+    /// Radius token of all corners
+    pub border_radius: Option<&'static str>,
+    /// Spacing token of the padding on all sides
+    pub padding: Option<&'static str>,
+    /// Spacing token of the gap between children
+    pub gap: Option<&'static str>
+}
+
+impl StyleTokens {
+    pub fn is_empty(&self) -> bool {
+        *self == StyleTokens::default()
+    }
+
+    /// Replaces the properties of the style with the values of the tokens.
+    pub fn apply(&self, theme: &Theme, style: &mut Style) {
+        if let Some(color) = self.background.and_then(|name| theme.colors.get(name)) {
+            style.background = Some(Background::Color(*color));
+        }
+        if let Some(color) = self.text_fill.and_then(|name| theme.colors.get(name)) {
+            style.text_fill = Some(Paint::color(*color));
+        }
+        if let Some(typography) = self.typography.and_then(|name| theme.get_typography(name)) {
+            style.font_size = Some(typography.font_size);
+            style.line_height = Some(typography.line_height);
+        }
+        if let Some(radius) = self.border_radius.and_then(|name| theme.get_radius(name)) {
+            style.border_radius = BorderRadius::all(radius);
+        }
+        if let Some(padding) = self.padding.and_then(|name| theme.get_spacing(name)) {
+            style.layout.padding = Rect {
+                left: LengthPercentage::Length(padding),
+                right: LengthPercentage::Length(padding),
+                top: LengthPercentage::Length(padding),
+                bottom: LengthPercentage::Length(padding)
+            };
+        }
+        if let Some(gap) = self.gap.and_then(|name| theme.get_spacing(name)) {
+            style.layout.gap = Size { width: LengthPercentage::Length(gap), height: LengthPercentage::Length(gap) };
+        }
+    }
+}
+
+/// Themes used for each mode and how the mode is chosen
+struct Themes {
+    light: Arc<Theme>,
+    dark: Arc<Theme>,
+    /// Mode chosen by the user, None follows the OS
+    preference: Option<ThemeMode>,
+    /// Mode reported by the OS
+    system: ThemeMode,
+    changed: bool
+}
+
+impl Default for Themes {
+    fn default() -> Self {
+        Themes {
+            light: Arc::new(Theme::light()),
+            dark: Arc::new(Theme::dark()),
+            preference: None,
+            system: ThemeMode::default(),
+            changed: false
+        }
+    }
+}
+
+impl Themes {
+    fn current(&self) -> Arc<Theme> {
+        match self.preference.unwrap_or(self.system) {
+            ThemeMode::Light => self.light.clone(),
+            ThemeMode::Dark => self.dark.clone()
+        }
+    }
+}
+
+fn update_themes(update: impl FnOnce(&mut Themes)) {
+    let mut themes = THEMES.lock().unwrap();
+    update(&mut themes);
+    themes.changed = true;
+    drop(themes);
+    crate::request_frame();
+}
+
+/// Sets the themes used in light and dark mode. Can be called from any thread, the tree is restyled on the next frame.
+pub fn set_themes(light: Theme, dark: Theme) {
+    update_themes(|themes| {
+        themes.light = Arc::new(light);
+        themes.dark = Arc::new(dark);
+    });
+}
+
+/// Forces light or dark mode. None (the default) follows the OS preference.
+pub fn set_theme_mode(mode: Option<ThemeMode>) {
+    update_themes(|themes| themes.preference = mode);
+}
+
+/// Returns the theme currently in use.
+pub fn current_theme() -> Arc<Theme> {
+    THEMES.lock().unwrap().current()
+}
+
+/// Sets the mode reported by the OS, called by the event loop when the window theme changes.
+pub(crate) fn set_system_mode(mode: ThemeMode) {
+    update_themes(|themes| themes.system = mode);
+}
+
+/// Returns the new theme if it changed since the previous call.
+pub(crate) fn apply_theme_changes() -> Option<Arc<Theme>> {
+    let mut themes = THEMES.lock().unwrap();
+    if !themes.changed {
+        return None;
+    }
+    themes.changed = false;
+    Some(themes.current())
+}
@@ -0,0 +1,248 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use cosmic_text::Metrics;
+use taffy::{Dimension, LengthPercentage, Rect, Size};
+use weak_table::PtrWeakKeyHashMap;
+use crate::events::Location;
+use crate::nodes::layout::Layout;
+use crate::nodes::overlay::{open_overlay, Anchor, Overlay, OverlayHandle, Placement};
+use crate::nodes::text::Text;
+use crate::nodes::theme::StyleTokens;
+use crate::nodes::{PointerEvents, RenderContext, Style, TaffyStyle, ToShared};
+use crate::{SharedNode, WeakNode};
+
+lazy_static::lazy_static! {
+    static ref TOOLTIPS: Mutex<PtrWeakKeyHashMap<WeakNode, Arc<Tooltip>>> = Mutex::new(PtrWeakKeyHashMap::new());
+}
+
+/// Default time the pointer needs to rest on a node before its tooltip is shown
+pub const TOOLTIP_DELAY: Duration = Duration::from_millis(500);
+/// Distance between the pointer and tooltips shown at the pointer, in pixels. Leaves room for the cursor
+const POINTER_OFFSET: f32 = 16.;
+/// Distance between the node and anchored tooltips, in pixels
+const ANCHOR_OFFSET: f32 = 6.;
+/// Text tooltips wrap at this width
+const MAX_TEXT_WIDTH: f32 = 320.;
+
+/// Content of a tooltip.
+#[derive(Clone, Debug)]
+pub enum TooltipContent {
+    /// Shown with the `text` color as background and the `background` color as text
+    Text(String),
+    /// Shown as is. The node can be shared between tooltips
+    Node(SharedNode)
+}
+
+/// Where a tooltip is shown. Tooltips are moved to stay inside the window.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TooltipPosition {
+    /// Below the pointer, where it was when the tooltip appeared
+    #[default]
+    Pointer,
+    /// Against the node, see [`Overlay::placement`]
+    Anchored(Placement)
+}
+
+/// Text or node shown in an overlay after the pointer rested on a node, see [`set_tooltip`].
+///
+/// ```rust
+/// set_tooltip(&button, Tooltip::text("Mark as completed").anchored(Placement::new(Side::Top, PlacementAlign::Center)));
+/// ```
+pub struct Tooltip {
+    pub content: TooltipContent,
+    /// Time the pointer needs to rest on the node. Default is [`TOOLTIP_DELAY`]
+    pub delay: Duration,
+    pub position: TooltipPosition,
+    /// Checked when the delay is over, the tooltip is only shown if it returns true.
+    /// For example, a title can show its full text only when it's truncated
+    pub condition: Option<Box<dyn Fn() -> bool + Send + Sync>>
+}
+
+impl Tooltip {
+    pub fn new(content: TooltipContent) -> Tooltip {
+        Tooltip {
+            content,
+            delay: TOOLTIP_DELAY,
+            position: TooltipPosition::Pointer,
+            condition: None
+        }
+    }
+
+    pub fn text(text: impl Into<String>) -> Tooltip {
+        Tooltip::new(TooltipContent::Text(text.into()))
+    }
+
+    pub fn node(node: SharedNode) -> Tooltip {
+        Tooltip::new(TooltipContent::Node(node))
+    }
+
+    pub fn delay(mut self, delay: Duration) -> Tooltip {
+        self.delay = delay;
+        self
+    }
+
+    /// Shows the tooltip against the node instead of below the pointer.
+    pub fn anchored(mut self, placement: Placement) -> Tooltip {
+        self.position = TooltipPosition::Anchored(placement);
+        self
+    }
+
+    /// Shows the tooltip only if the condition returns true when the delay is over.
+    pub fn when(mut self, condition: impl Fn() -> bool + Send + Sync + 'static) -> Tooltip {
+        self.condition = Some(Box::new(condition));
+        self
+    }
+
+    /// Returns the root node of the overlay showing the tooltip. It ignores the pointer, so that the tooltip
+    /// doesn't take the hover from the node it belongs to.
+    fn overlay_node(&self) -> SharedNode {
+        let style = Style {
+            pointer_events: PointerEvents::None,
+            ..Default::default()
+        };
+        match &self.content {
+            TooltipContent::Node(node) => Layout::new(vec![node.clone()]).style(style).to_shared(),
+            TooltipContent::Text(text) => {
+                let text = Text::new(text.clone(), Metrics::new(14., 18.))
+                    .style(Style {
+                        tokens: StyleTokens { text_fill: Some("background"), typography: Some("small"), ..Default::default() },
+                        ..Default::default()
+                    });
+                Layout::new(vec![text.to_shared()])
+                    .style(Style {
+                        layout: TaffyStyle {
+                            padding: Rect {
+                                left: LengthPercentage::Length(8.),
+                                right: LengthPercentage::Length(8.),
+                                top: LengthPercentage::Length(4.),
+                                bottom: LengthPercentage::Length(4.)
+                            },
+                            max_size: Size { width: Dimension::Length(MAX_TEXT_WIDTH), height: Dimension::Auto },
+                            ..Default::default()
+                        },
+                        tokens: StyleTokens { background: Some("text"), border_radius: Some("sm"), ..Default::default() },
+                        ..style
+                    })
+                    .to_shared()
+            }
+        }
+    }
+}
+
+impl Debug for Tooltip {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tooltip")
+            .field("content", &self.content)
+            .field("delay", &self.delay)
+            .field("position", &self.position)
+            .field("condition", &self.condition.is_some())
+            .finish()
+    }
+}
+
+/// Attaches the tooltip to the node, replacing its previous tooltip. Can be called from any thread.
+/// The tooltip is shown while the pointer is over the node (or its children without a tooltip of their own),
+/// and hidden when the pointer leaves it, on scroll, on mouse press or on Escape.
+pub fn set_tooltip(node: &SharedNode, tooltip: Tooltip) {
+    TOOLTIPS.lock().unwrap().insert(node.clone(), Arc::new(tooltip));
+}
+
+/// Removes the tooltip of the node. A tooltip which is shown already stays until the pointer leaves the node.
+pub fn remove_tooltip(node: &SharedNode) {
+    TOOLTIPS.lock().unwrap().remove(node);
+}
+
+/// Returns the innermost hovered node with a tooltip.
+fn hovered_tooltip(context: &RenderContext) -> Option<(SharedNode, Arc<Tooltip>)> {
+    let tooltips = TOOLTIPS.lock().unwrap();
+    context.mouse.as_ref()?.iter().rev()
+        .filter_map(|node| node.upgrade())
+        .find_map(|node| tooltips.get(&node).cloned().map(|tooltip| (node, tooltip)))
+}
+
+fn is_node(weak: &Option<WeakNode>, node: &SharedNode) -> bool {
+    weak.as_ref().is_some_and(|weak| std::ptr::addr_eq(weak.as_ptr(), Arc::as_ptr(node)))
+}
+
+/// Tooltip being waited for or shown, owned by the event loop.
+#[derive(Default)]
+pub(crate) struct TooltipState {
+    /// Hovered node with a tooltip, and when the tooltip should be shown
+    pending: Option<(WeakNode, Instant)>,
+    /// Node whose tooltip is shown, and the overlay showing it
+    shown: Option<(WeakNode, OverlayHandle)>,
+    /// Hovered node whose tooltip was hidden by scrolling, a press or Escape. It isn't shown again until the pointer leaves the node
+    suppressed: Option<WeakNode>,
+    /// Last location of the pointer, in window coordinates
+    pointer: Location
+}
+
+impl TooltipState {
+    fn close(&mut self) {
+        if let Some((_, handle)) = self.shown.take() {
+            handle.close();
+        }
+    }
+}
+
+/// Starts waiting for the tooltip of the hovered node, or hides the tooltip of a node which isn't hovered anymore.
+/// Call after the hovered path changed, with the location of the pointer (None when it left the window).
+pub(crate) fn update_tooltip(context: &mut RenderContext, pointer: Option<Location>) {
+    if let Some(pointer) = pointer {
+        context.tooltips.pointer = pointer;
+    }
+    let hovered = hovered_tooltip(context);
+    let state = &mut context.tooltips;
+    let Some((node, tooltip)) = hovered else {
+        state.close();
+        state.pending = None;
+        state.suppressed = None;
+        return;
+    };
+    if !is_node(&state.suppressed, &node) {
+        state.suppressed = None;
+    }
+    if is_node(&state.shown.as_ref().map(|(shown, _)| shown.clone()), &node)
+        || is_node(&state.pending.as_ref().map(|(pending, _)| pending.clone()), &node)
+        || state.suppressed.is_some() {
+        return;
+    }
+    state.close();
+    let time = Instant::now() + tooltip.delay;
+    state.pending = Some((Arc::downgrade(&node), time));
+    context.request_frame_at(time);
+}
+
+/// Hides the shown tooltip (or the one being waited for) until the pointer leaves its node.
+pub(crate) fn hide_tooltip(context: &mut RenderContext) {
+    let state = &mut context.tooltips;
+    let node = state.shown.as_ref().map(|(node, _)| node.clone())
+        .or_else(|| state.pending.as_ref().map(|(node, _)| node.clone()));
+    state.close();
+    state.pending = None;
+    if node.is_some() {
+        state.suppressed = node;
+    }
+}
+
+/// Shows the tooltip being waited for once its delay is over. Call at the start of each frame, before overlay requests are applied.
+pub(crate) fn show_due_tooltip(context: &mut RenderContext) {
+    let Some((node, time)) = context.tooltips.pending.clone() else { return; };
+    if context.frame_time < time {
+        context.request_frame_at(time);
+        return;
+    }
+    context.tooltips.pending = None;
+    let Some(node) = node.upgrade() else { return; };
+    let Some(tooltip) = TOOLTIPS.lock().unwrap().get(&node).cloned() else { return; };
+    if tooltip.condition.as_ref().is_some_and(|condition| !condition()) {
+        return;
+    }
+    let overlay = Overlay::new(tooltip.overlay_node());
+    let overlay = match tooltip.position {
+        TooltipPosition::Pointer => overlay.anchor(Anchor::Point(context.tooltips.pointer)).offset(POINTER_OFFSET),
+        TooltipPosition::Anchored(placement) => overlay.anchor(Anchor::Node(Arc::downgrade(&node))).placement(placement).offset(ANCHOR_OFFSET)
+    };
+    context.tooltips.shown = Some((Arc::downgrade(&node), open_overlay(overlay)));
+}
@@ -0,0 +1,246 @@
+use std::ops::{Add, Mul, Neg, Sub};
+use taffy::{Dimension, LengthPercentage, LengthPercentageAuto, Rect, Size};
+use crate::nodes::TaffyStyle;
+
+/// Font size used when neither the node nor its ancestors set one
+pub const DEFAULT_FONT_SIZE: f32 = 16.;
+
+/// Length in relative units, or a sum of them like CSS `calc(100% - 20px)`.
+/// Like in CSS, `percent(100.)`, `vw(100.)` and `vh(100.)` are the full size, `em(1.)` is the font size.
+/// Lengths are combined with `+`, `-` and `*`, e.g. `Length::percent(100.) - Length::px(20.)`.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct Length {
+    /// Pixels
+    pub px: f32,
+    /// Percent of the size of the parent (its width for horizontal properties, margins and padding, its height otherwise)
+    pub percent: f32,
+    /// Multiples of the font size of the node, inherited from its ancestors
+    pub em: f32,
+    /// Multiples of the font size of the root node
+    pub rem: f32,
+    /// Percent of the window width
+    pub vw: f32,
+    /// Percent of the window height
+    pub vh: f32
+}
+
+impl Length {
+    pub fn px(px: f32) -> Self {
+        Length { px, ..Default::default() }
+    }
+
+    pub fn percent(percent: f32) -> Self {
+        Length { percent, ..Default::default() }
+    }
+
+    pub fn em(em: f32) -> Self {
+        Length { em, ..Default::default() }
+    }
+
+    pub fn rem(rem: f32) -> Self {
+        Length { rem, ..Default::default() }
+    }
+
+    pub fn vw(vw: f32) -> Self {
+        Length { vw, ..Default::default() }
+    }
+
+    pub fn vh(vh: f32) -> Self {
+        Length { vh, ..Default::default() }
+    }
+
+    /// Returns the length in pixels, without the percentage part.
+    pub fn absolute(&self, units: &UnitContext) -> f32 {
+        self.px
+            + self.em * units.font_size
+            + self.rem * units.root_font_size
+            + self.vw / 100. * units.window_size.width
+            + self.vh / 100. * units.window_size.height
+    }
+
+    fn is_percent(&self) -> bool {
+        self.percent != 0. && *self == Length::percent(self.percent)
+    }
+
+    /// Returns the length in pixels. Percentages are resolved against `relative_to`, the size of the parent
+    /// from the previous layout pass.
+    fn resolve(&self, units: &mut UnitContext, relative_to: f32) -> f32 {
+        if self.percent != 0. {
+            // taffy can't add percentages to other units, the parent size is known only after the layout
+            units.uses_parent_size = true;
+        }
+        self.absolute(units) + self.percent / 100. * relative_to
+    }
+
+    fn to_dimension(self, units: &mut UnitContext, relative_to: f32) -> Dimension {
+        match self.is_percent() {
+            true => Dimension::Percent(self.percent / 100.),
+            false => Dimension::Length(self.resolve(units, relative_to))
+        }
+    }
+
+    fn to_length_percentage(self, units: &mut UnitContext, relative_to: f32) -> LengthPercentage {
+        match self.is_percent() {
+            true => LengthPercentage::Percent(self.percent / 100.),
+            false => LengthPercentage::Length(self.resolve(units, relative_to))
+        }
+    }
+
+    fn to_length_percentage_auto(self, units: &mut UnitContext, relative_to: f32) -> LengthPercentageAuto {
+        match self.is_percent() {
+            true => LengthPercentageAuto::Percent(self.percent / 100.),
+            false => LengthPercentageAuto::Length(self.resolve(units, relative_to))
+        }
+    }
+}
+
+impl Add for Length {
+    type Output = Length;
+
+    fn add(self, rhs: Length) -> Length {
+        Length {
+            px: self.px + rhs.px,
+            percent: self.percent + rhs.percent,
+            em: self.em + rhs.em,
+            rem: self.rem + rhs.rem,
+            vw: self.vw + rhs.vw,
+            vh: self.vh + rhs.vh
+        }
+    }
+}
+
+impl Neg for Length {
+    type Output = Length;
+
+    fn neg(self) -> Length {
+        self * -1.
+    }
+}
+
+impl Sub for Length {
+    type Output = Length;
+
+    fn sub(self, rhs: Length) -> Length {
+        self + -rhs
+    }
+}
+
+impl Mul<f32> for Length {
+    type Output = Length;
+
+    fn mul(self, rhs: f32) -> Length {
+        Length {
+            px: self.px * rhs,
+            percent: self.percent * rhs,
+            em: self.em * rhs,
+            rem: self.rem * rhs,
+            vw: self.vw * rhs,
+            vh: self.vh * rhs
+        }
+    }
+}
+
+/// Layout properties in relative units. Set properties override the matching properties of [`TaffyStyle`]
+/// and are converted to taffy values during the layout pass, so they follow font size and window size changes.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct LayoutLengths {
+    pub size: Size<Option<Length>>,
+    pub min_size: Size<Option<Length>>,
+    pub max_size: Size<Option<Length>>,
+    pub margin: Rect<Option<Length>>,
+    pub padding: Rect<Option<Length>>,
+    pub border: Rect<Option<Length>>,
+    pub inset: Rect<Option<Length>>,
+    pub gap: Size<Option<Length>>,
+    pub flex_basis: Option<Length>
+}
+
+/// Values relative units are resolved against, passed down the tree during the layout pass
+#[derive(Copy, Clone, Debug)]
+pub struct UnitContext {
+    /// Font size of the node, or the one inherited from its ancestors
+    pub font_size: f32,
+    /// Font size of the root node
+    pub root_font_size: f32,
+    /// Window size in physical pixels
+    pub window_size: Size<f32>,
+    /// Content size of the parent in the previous layout pass
+    pub parent_size: Size<f32>,
+    /// Set when a percentage was added to other units, which needs another layout pass once the parent size is known
+    pub(crate) uses_parent_size: bool
+}
+
+impl UnitContext {
+    pub(crate) fn root(font_size: Option<f32>, window_size: Size<f32>) -> Self {
+        let font_size = font_size.unwrap_or(DEFAULT_FONT_SIZE);
+        UnitContext {
+            font_size,
+            root_font_size: font_size,
+            window_size,
+            parent_size: window_size,
+            uses_parent_size: false
+        }
+    }
+}
+
+/// Overrides the layout properties set in `lengths`.
+pub(crate) fn apply_lengths(layout: &mut TaffyStyle, lengths: &LayoutLengths, units: &mut UnitContext) {
+    let parent = units.parent_size;
+    let size = |target: &mut Size<Dimension>, lengths: &Size<Option<Length>>, units: &mut UnitContext| {
+        if let Some(width) = lengths.width {
+            target.width = width.to_dimension(units, parent.width);
+        }
+        if let Some(height) = lengths.height {
+            target.height = height.to_dimension(units, parent.height);
+        }
+    };
+    size(&mut layout.size, &lengths.size, units);
+    size(&mut layout.min_size, &lengths.min_size, units);
+    size(&mut layout.max_size, &lengths.max_size, units);
+
+    // like in CSS, percentages of margins and padding refer to the width of the parent
+    let sides = |target: &mut Rect<LengthPercentage>, lengths: &Rect<Option<Length>>, units: &mut UnitContext| {
+        for (target, length) in [
+            (&mut target.left, lengths.left),
+            (&mut target.right, lengths.right),
+            (&mut target.top, lengths.top),
+            (&mut target.bottom, lengths.bottom)
+        ] {
+            if let Some(length) = length {
+                *target = length.to_length_percentage(units, parent.width);
+            }
+        }
+    };
+    sides(&mut layout.padding, &lengths.padding, units);
+    sides(&mut layout.border, &lengths.border, units);
+    for (target, length) in [
+        (&mut layout.margin.left, lengths.margin.left),
+        (&mut layout.margin.right, lengths.margin.right),
+        (&mut layout.margin.top, lengths.margin.top),
+        (&mut layout.margin.bottom, lengths.margin.bottom)
+    ] {
+        if let Some(length) = length {
+            *target = length.to_length_percentage_auto(units, parent.width);
+        }
+    }
+    for (target, length, relative_to) in [
+        (&mut layout.inset.left, lengths.inset.left, parent.width),
+        (&mut layout.inset.right, lengths.inset.right, parent.width),
+        (&mut layout.inset.top, lengths.inset.top, parent.height),
+        (&mut layout.inset.bottom, lengths.inset.bottom, parent.height)
+    ] {
+        if let Some(length) = length {
+            *target = length.to_length_percentage_auto(units, relative_to);
+        }
+    }
+    if let Some(width) = lengths.gap.width {
+        layout.gap.width = width.to_length_percentage(units, parent.width);
+    }
+    if let Some(height) = lengths.gap.height {
+        layout.gap.height = height.to_length_percentage(units, parent.height);
+    }
+    if let Some(basis) = lengths.flex_basis {
+        // the main axis of the parent isn't known here, the width is the common case
+        layout.flex_basis = basis.to_dimension(units, parent.width);
+    }
+}
@@ -0,0 +1,143 @@
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::sync::Arc;
+use taffy::Size;
+use crate::nodes::Style;
+use crate::nodes::interaction::InteractionState;
+use crate::nodes::theme::{Theme, ThemeMode};
+
+/// Condition under which a [`StyleVariant`] applies. Window sizes are in physical pixels, like [`crate::nodes::RenderContext::window_size`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum StyleCondition {
+    /// The window is at least this wide
+    MinWidth(f32),
+    /// The window is at most this wide
+    MaxWidth(f32),
+    /// The window is at least this tall
+    MinHeight(f32),
+    /// The window is at most this tall
+    MaxHeight(f32),
+    /// The scale factor of the display is at least this value
+    MinScaleFactor(f32),
+    /// The scale factor of the display is at most this value
+    MaxScaleFactor(f32),
+    /// The current theme is light or dark
+    Theme(ThemeMode),
+    /// The pointer is over the node or its children
+    Hovered,
+    /// The node or its children are being pressed
+    Pressed,
+    /// The node has the keyboard focus
+    Focused,
+    /// The node is disabled
+    Disabled,
+    /// All of the conditions apply
+    All(Vec<StyleCondition>),
+    /// At least one of the conditions applies
+    Any(Vec<StyleCondition>),
+    /// The condition doesn't apply
+    Not(Box<StyleCondition>)
+}
+
+/// Override of some style properties, applied on top of the style when its condition is met.
+/// Variants are applied in order, so later variants win.
+///
+/// Variants are re-evaluated on every layout pass and frame, so nodes react to window resizes without
+/// implementing [`crate::nodes::Node::resize`].
+#[derive(Clone)]
+pub struct StyleVariant {
+    pub condition: StyleCondition,
+    pub apply: Arc<dyn Fn(&mut Style) + Send + Sync>
+}
+
+impl StyleVariant {
+    pub fn new(condition: StyleCondition, apply: impl Fn(&mut Style) + Send + Sync + 'static) -> Self {
+        StyleVariant {
+            condition,
+            apply: Arc::new(apply)
+        }
+    }
+}
+
+impl Debug for StyleVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StyleVariant")
+            .field("condition", &self.condition)
+            .finish_non_exhaustive()
+    }
+}
+
+impl StyleCondition {
+    /// Returns true if the condition depends on the interaction state of the node, which changes more often than the window.
+    pub fn depends_on_state(&self) -> bool {
+        match self {
+            StyleCondition::Hovered | StyleCondition::Pressed | StyleCondition::Focused | StyleCondition::Disabled => true,
+            StyleCondition::All(conditions) | StyleCondition::Any(conditions) => conditions.iter().any(StyleCondition::depends_on_state),
+            StyleCondition::Not(condition) => condition.depends_on_state(),
+            _ => false
+        }
+    }
+}
+
+/// State of the window and the node style conditions are evaluated against
+#[derive(Clone, Debug)]
+pub struct StyleEnvironment {
+    pub window_size: Size<f32>,
+    pub scale_factor: f32,
+    /// Theme the tokens of styles are resolved with
+    pub theme: Arc<Theme>,
+    /// Interaction state of the node the style belongs to
+    pub state: InteractionState
+}
+
+impl StyleEnvironment {
+    pub fn matches(&self, condition: &StyleCondition) -> bool {
+        match condition {
+            StyleCondition::MinWidth(width) => self.window_size.width >= *width,
+            StyleCondition::MaxWidth(width) => self.window_size.width <= *width,
+            StyleCondition::MinHeight(height) => self.window_size.height >= *height,
+            StyleCondition::MaxHeight(height) => self.window_size.height <= *height,
+            StyleCondition::MinScaleFactor(scale) => self.scale_factor >= *scale,
+            StyleCondition::MaxScaleFactor(scale) => self.scale_factor <= *scale,
+            StyleCondition::Theme(mode) => self.theme.mode == *mode,
+            StyleCondition::Hovered => self.state.hovered,
+            StyleCondition::Pressed => self.state.pressed,
+            StyleCondition::Focused => self.state.focused,
+            StyleCondition::Disabled => self.state.disabled,
+            StyleCondition::All(conditions) => conditions.iter().all(|condition| self.matches(condition)),
+            StyleCondition::Any(conditions) => conditions.iter().any(|condition| self.matches(condition)),
+            StyleCondition::Not(condition) => !self.matches(condition)
+        }
+    }
+}
+
+impl Style {
+    /// Adds a variant overriding the style when the condition is met.
+    pub fn variant(mut self, condition: StyleCondition, apply: impl Fn(&mut Style) + Send + Sync + 'static) -> Self {
+        self.variants.push(StyleVariant::new(condition, apply));
+        self
+    }
+
+    /// Returns the style with the variants matching the environment applied, followed by its theme tokens.
+    /// The style is only cloned if a variant matches or it uses tokens.
+    pub fn resolve(&self, environment: &StyleEnvironment) -> Cow<'_, Style> {
+        let state = match self.disabled {
+            true => InteractionState { hovered: environment.state.hovered, disabled: true, ..Default::default() },
+            false => environment.state
+        };
+        let environment = &StyleEnvironment { state, ..environment.clone() };
+        let mut matching = self.variants.iter().filter(|variant| environment.matches(&variant.condition)).peekable();
+        if matching.peek().is_none() && self.tokens.is_empty() {
+            return Cow::Borrowed(self);
+        }
+        let mut style = self.clone();
+        for variant in matching {
+            (variant.apply)(&mut style);
+        }
+        // variants can change the tokens too
+        let tokens = std::mem::take(&mut style.tokens);
+        tokens.apply(&environment.theme, &mut style);
+        style.tokens = tokens;
+        Cow::Owned(style)
+    }
+}
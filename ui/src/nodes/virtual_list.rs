@@ -0,0 +1,309 @@
+use std::fmt::{Debug, Formatter};
+use taffy::{Dimension, FlexDirection, Layout, LengthPercentageAuto, Overflow, Point, Position};
+use crate::{nodes::{Node, NodeChildren, Style}, events::NodeEvent, WeakNode, SharedNode};
+use crate::nodes::layout::Layout as LayoutNode;
+use crate::nodes::scroll_view::ScrollView;
+use crate::nodes::{RenderContext, ToShared};
+
+/// Creates the node for an item of a [`VirtualList`].
+///
+/// Called with the index of the item and a node which is no longer visible, if there's any.
+/// Update the recycled node to show the item and return it, or return a new node.
+/// Returned nodes need to support [`Node::style_mut`], as the list positions them.
+/// The builder is called while the list is locked, so it must not lock the list.
+pub type ItemBuilder = dyn FnMut(usize, Option<SharedNode>) -> SharedNode + Send;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RowHeight {
+    /// All rows have the same height in pixels. Rows are resized to this height.
+    Fixed(f32),
+    /// Rows are sized by their content. The value is used for rows which weren't shown yet.
+    Estimated(f32)
+}
+
+/// A scrolling list which only creates nodes for the items in the visible area (plus [`VirtualList::overscan`] rows around it).
+///
+/// Nodes of items which scroll out of view are passed to the [`ItemBuilder`] to be reused for other items.
+/// Scrolling is handled by the inner [`ScrollView`], which can be configured through [`VirtualList::scroll`].
+pub struct VirtualList {
+    /// Handles scrolling. Its children are managed by the list - don't change them.
+    pub scroll: ScrollView,
+    /// Number of rows rendered above and below the visible area. Default is 3
+    pub overscan: usize,
+    item_count: usize,
+    row_height: RowHeight,
+    builder: Box<ItemBuilder>,
+    /// Measured heights of rows (only for [`RowHeight::Estimated`])
+    heights: Vec<Option<f32>>,
+    /// Offsets of the tops of the rows, followed by the height of the list (only for [`RowHeight::Estimated`]).
+    /// Updated whenever `heights` changes
+    offsets: Vec<f32>,
+    /// Items currently in the tree, sorted by index
+    rows: Vec<(usize, SharedNode)>,
+    /// Nodes which scrolled out of view, passed to the builder
+    recycled: Vec<SharedNode>,
+    /// Sets the height of the content, as rows are positioned absolutely
+    spacer: SharedNode,
+    /// Returns true for rows which are kept while the rows after them are visible, see [`VirtualList::sticky_rows`]
+    sticky: Option<Box<dyn Fn(usize) -> bool + Send>>,
+    /// Sorted indices of the sticky rows, collected when first needed after the items changed
+    sticky_indices: Option<Vec<usize>>,
+    /// Rows need to be rebuilt even if they're still visible
+    dirty: bool
+}
+
+impl VirtualList {
+    pub fn new(item_count: usize, row_height: RowHeight, builder: Box<ItemBuilder>) -> VirtualList {
+        let mut scroll = ScrollView::default();
+        scroll.style.layout.flex_direction = FlexDirection::Column;
+        scroll.style.layout.overflow = Point { x: Overflow::Hidden, y: Overflow::Scroll };
+        let mut list = VirtualList {
+            scroll,
+            overscan: 3,
+            item_count,
+            row_height,
+            builder,
+            heights: vec![None; item_count],
+            offsets: Vec::new(),
+            rows: Vec::new(),
+            recycled: Vec::new(),
+            spacer: LayoutNode::empty().to_shared(),
+            sticky: None,
+            sticky_indices: None,
+            dirty: false
+        };
+        list.update_offsets(0);
+        list
+    }
+
+    /// Replaces the style of the list. Only the y-axis should scroll, so keep overflow x at [`Overflow::Hidden`].
+    pub fn style(mut self, style: Style) -> VirtualList {
+        self.scroll.style = style;
+        self
+    }
+
+    /// Keeps the last row for which the function returns true built while the rows after it are visible,
+    /// so that section headers can stick with [`crate::nodes::Positioning::Sticky`]. The builder still needs to set the positioning.
+    ///
+    /// The function is called for every item once the items changed (see [`VirtualList::refresh`]), the result is kept until then.
+    pub fn sticky_rows(mut self, is_sticky: impl Fn(usize) -> bool + Send + 'static) -> VirtualList {
+        self.sticky = Some(Box::new(is_sticky));
+        self.sticky_indices = None;
+        self
+    }
+
+    pub fn item_count(&self) -> usize {
+        self.item_count
+    }
+
+    /// Changes the number of items. Visible rows are rebuilt.
+    pub fn set_item_count(&mut self, item_count: usize) {
+        let unchanged = self.item_count.min(item_count);
+        self.item_count = item_count;
+        self.heights.resize(item_count, None);
+        self.update_offsets(unchanged);
+        self.sticky_indices = None;
+        self.dirty = true;
+    }
+
+    /// Rebuilds all visible rows, for when the items changed. Measured heights are forgotten.
+    pub fn refresh(&mut self) {
+        self.heights = vec![None; self.item_count];
+        self.update_offsets(0);
+        self.sticky_indices = None;
+        self.dirty = true;
+    }
+
+    /// Returns the offset of the top of the row from the top of the list.
+    /// Use with [`crate::nodes::scroll_view::scroll_to`] to scroll to a row which may not be built yet.
+    pub fn offset_of(&self, index: usize) -> f32 {
+        let index = index.min(self.item_count);
+        match self.row_height {
+            RowHeight::Fixed(height) => index as f32 * height,
+            RowHeight::Estimated(_) => self.offsets[index]
+        }
+    }
+
+    /// Recomputes the offsets of the rows after the given one, after the heights from it on changed.
+    fn update_offsets(&mut self, from: usize) {
+        let RowHeight::Estimated(estimate) = self.row_height else { return; };
+        self.offsets.resize(self.item_count + 1, 0.);
+        for index in from..self.item_count {
+            self.offsets[index + 1] = self.offsets[index] + self.heights[index].unwrap_or(estimate);
+        }
+    }
+
+    /// Returns the range of rows to build, based on the scroll offset and the height of the visible area
+    fn visible_range(&self, scroll: f32, height: f32) -> (usize, usize) {
+        // the first row ending below the top of the visible area, and the first row starting below its bottom
+        let (first, last) = match self.row_height {
+            RowHeight::Fixed(row_height) if row_height > 0. => (
+                ((scroll / row_height).floor() as usize).min(self.item_count),
+                (((scroll + height) / row_height).ceil() as usize).min(self.item_count)
+            ),
+            RowHeight::Fixed(_) => (self.item_count, self.item_count),
+            RowHeight::Estimated(_) => (
+                self.offsets[1..].partition_point(|bottom| *bottom <= scroll),
+                self.offsets[..self.item_count].partition_point(|top| *top < scroll + height)
+            )
+        };
+        (first.saturating_sub(self.overscan), (last + self.overscan).min(self.item_count))
+    }
+
+    fn viewport(&self, context: &RenderContext) -> (f32, f32) {
+        let layout = self.scroll.last_layout();
+        // before the first layout, assume the list fills the window
+        let height = if layout.size.height > 0. { layout.size.height } else { context.window_size.height };
+        (self.scroll.style.scroll_y.max(0.), height)
+    }
+
+    /// Returns the sticky row above the range which needs to stay built, see [`VirtualList::sticky_rows`]
+    fn pinned_row(&mut self, start: usize) -> Option<usize> {
+        let is_sticky = self.sticky.as_ref()?;
+        let item_count = self.item_count;
+        let indices = self.sticky_indices.get_or_insert_with(|| (0..item_count).filter(|index| is_sticky(*index)).collect());
+        indices[..indices.partition_point(|index| *index < start)].last().copied()
+    }
+
+    /// Builds rows in the range (and the pinned sticky row), recycling the rest. Returns true if the children changed.
+    fn update_rows(&mut self, start: usize, end: usize) -> bool {
+        let pinned = self.pinned_row(start);
+        let dirty = std::mem::take(&mut self.dirty);
+        let mut changed = dirty;
+        let mut kept = Vec::with_capacity(end - start + 1);
+        for (index, node) in self.rows.drain(..) {
+            if !dirty && ((index >= start && index < end) || Some(index) == pinned) {
+                kept.push((index, node));
+            } else {
+                self.recycled.push(node);
+                changed = true;
+            }
+        }
+        let mut rows = Vec::with_capacity(end - start + 1);
+        let mut kept = kept.into_iter().peekable();
+        for index in pinned.into_iter().chain(start..end) {
+            let offset = self.offset_of(index);
+            let node = match kept.peek() {
+                Some((kept_index, _)) if *kept_index == index => kept.next().unwrap().1,
+                _ => {
+                    changed = true;
+                    let recycled = self.recycled.pop();
+                    (self.builder)(index, recycled)
+                }
+            };
+            if let Some(style) = node.lock().unwrap().style_mut() {
+                let top = LengthPercentageAuto::Length(offset);
+                if style.layout.position != Position::Absolute || style.layout.inset.top != top {
+                    changed = true;
+                }
+                style.layout.position = Position::Absolute;
+                style.layout.inset.top = top;
+                style.layout.inset.left = LengthPercentageAuto::Length(0.);
+                style.layout.inset.right = LengthPercentageAuto::Length(0.);
+                if let RowHeight::Fixed(height) = self.row_height {
+                    style.layout.size.height = Dimension::Length(height);
+                }
+            }
+            rows.push((index, node));
+        }
+        self.rows = rows;
+
+        let total = self.offset_of(self.item_count);
+        if let Some(style) = self.spacer.lock().unwrap().style_mut() {
+            if style.layout.size.height != Dimension::Length(total) {
+                style.layout.size.height = Dimension::Length(total);
+                style.layout.flex_shrink = 0.;
+                changed = true;
+            }
+        }
+        if changed {
+            let mut children = Vec::with_capacity(self.rows.len() + 1);
+            children.push(self.spacer.clone());
+            children.extend(self.rows.iter().map(|(_, node)| node.clone()));
+            self.scroll.children = children;
+        }
+        changed
+    }
+}
+
+impl Debug for VirtualList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VirtualList")
+            .field("scroll", &self.scroll)
+            .field("item_count", &self.item_count)
+            .field("row_height", &self.row_height)
+            .finish()
+    }
+}
+
+impl Node for VirtualList {
+    fn style(&self) -> &Style {
+        &self.scroll.style
+    }
+    fn style_mut(&mut self) -> Option<&mut Style> {
+        Some(&mut self.scroll.style)
+    }
+    fn children(&self) -> Option<&NodeChildren> {
+        Some(&self.scroll.children)
+    }
+
+    fn prepare_render(&mut self, context: &mut RenderContext) {
+        self.scroll.prepare_render(context);
+        let (scroll, height) = self.viewport(context);
+        let (start, end) = self.visible_range(scroll, height);
+        if self.update_rows(start, end) {
+            context.request_relayout();
+        }
+    }
+
+    fn on_event(&mut self, event: &NodeEvent, context: &mut RenderContext) {
+        self.scroll.on_event(event, context);
+    }
+
+    fn render_pre_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        self.scroll.render_pre_children(context, layout);
+
+        // the first row whose height changed
+        let mut changed_from = None;
+        if let RowHeight::Estimated(_) = self.row_height {
+            for (index, node) in &self.rows {
+                let Some(taffy_node) = context.node_layout.get(node) else { continue; };
+                // rows built before the row count shrank are stale until they're rebuilt
+                let Some(measured) = self.heights.get_mut(*index) else { continue; };
+                let height = context.taffy.layout(*taffy_node).unwrap().size.height;
+                if *measured != Some(height) {
+                    *measured = Some(height);
+                    changed_from = Some(changed_from.map_or(*index, |from: usize| from.min(*index)));
+                }
+            }
+        }
+        let changed = changed_from.is_some();
+        if let Some(from) = changed_from {
+            self.update_offsets(from);
+        }
+        // rows need to be moved to their measured positions, or the visible area changed since the rows were built
+        let (scroll, height) = self.viewport(context);
+        let (start, end) = self.visible_range(scroll, height);
+        let built = match (self.rows.first(), self.rows.last()) {
+            (Some((first, _)), Some((last, _))) => start >= *first && end <= *last + 1,
+            _ => start == end
+        };
+        if changed || !built {
+            context.request_frame();
+        }
+    }
+
+    fn render_post_children(&mut self, context: &mut RenderContext, layout: Layout) {
+        self.scroll.render_post_children(context, layout);
+    }
+
+    fn event_handlers(&self) -> Option<crate::events::handler::InnerEventHandlerDataset> {
+        self.scroll.event_handlers()
+    }
+    fn set_parent(&mut self, parent: Option<WeakNode>) {
+        self.scroll.set_parent(parent);
+    }
+    fn parent(&self) -> Option<SharedNode> {
+        self.scroll.parent()
+    }
+}
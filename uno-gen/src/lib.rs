@@ -1,4 +1,4 @@
-use proc_macro2::{Span, TokenStream, TokenTree};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
 use quote::{IdentFragment, quote, quote_spanned, ToTokens};
 use quote::spanned::Spanned;
 
@@ -77,6 +77,38 @@ macro_rules! impl_struct_usersettable_totokens {
                 });
             }
         }
+
+        impl ToAssignments for $name {
+            fn to_assignments(&self, target: &TokenStream, stream: &mut TokenStream) {
+                let $name { $($variant),+, .. } = self;
+                $(
+                    $variant.to_assignments(&quote! { #target.$variant }, stream);
+                )+
+                $(
+                    $(
+                        if !$qvariant.is_empty() {
+                            stream.extend(quote! { #target.$qvariant = #$qtype; });
+                        }
+                    )*
+                )?
+            }
+        }
+    }
+}
+
+/// Generates statements setting only the properties which were set by rules, used by variants
+/// which are applied on top of an existing style. Values are assigned as a whole by default.
+trait ToAssignments: ToTokens {
+    fn to_assignments(&self, target: &TokenStream, stream: &mut TokenStream) {
+        stream.extend(quote! { #target = #self; });
+    }
+}
+
+macro_rules! impl_value_assignments {
+    ($($name:ty),+) => {
+        $(
+            impl ToAssignments for $name {}
+        )+
     }
 }
 
@@ -144,6 +176,16 @@ impl<T: ToTokens> ToTokens for UserSettable<T> {
     }
 }
 
+impl<T: ToAssignments> ToAssignments for UserSettable<T> {
+    fn to_assignments(&self, target: &TokenStream, stream: &mut TokenStream) {
+        match self {
+            UserSettable::Value(value) => value.to_assignments(target, stream),
+            UserSettable::Arbitrary(value) => stream.extend(quote! { #target = #value; }),
+            UserSettable::None => {}
+        }
+    }
+}
+
 #[derive(Clone, Default, Debug)]
 struct Point<T> {
     x: UserSettable<T>,
@@ -159,6 +201,13 @@ impl<T: ToTokens> ToTokens for Point<T> {
     }
 }
 
+impl<T: ToAssignments> ToAssignments for Point<T> {
+    fn to_assignments(&self, target: &TokenStream, stream: &mut TokenStream) {
+        self.x.to_assignments(&quote! { #target.x }, stream);
+        self.y.to_assignments(&quote! { #target.y }, stream);
+    }
+}
+
 #[derive(Clone, Default, Debug)]
 struct Size<T> {
     width: UserSettable<T>,
@@ -174,6 +223,13 @@ impl<T: ToTokens> ToTokens for Size<T> {
     }
 }
 
+impl<T: ToAssignments> ToAssignments for Size<T> {
+    fn to_assignments(&self, target: &TokenStream, stream: &mut TokenStream) {
+        self.width.to_assignments(&quote! { #target.width }, stream);
+        self.height.to_assignments(&quote! { #target.height }, stream);
+    }
+}
+
 #[derive(Clone, Default, Debug)]
 struct Rect<T> {
     pub left: UserSettable<T>,
@@ -207,6 +263,15 @@ impl<T: ToTokens> ToTokens for Rect<T> {
     }
 }
 
+impl<T: ToAssignments> ToAssignments for Rect<T> {
+    fn to_assignments(&self, target: &TokenStream, stream: &mut TokenStream) {
+        self.left.to_assignments(&quote! { #target.left }, stream);
+        self.right.to_assignments(&quote! { #target.right }, stream);
+        self.top.to_assignments(&quote! { #target.top }, stream);
+        self.bottom.to_assignments(&quote! { #target.bottom }, stream);
+    }
+}
+
 #[derive(Clone, Default, Debug)]
 struct Color {
     r: f32,
@@ -252,6 +317,177 @@ impl ToTokens for Paint {
     }
 }
 
+#[derive(Copy, Clone, Debug)]
+enum GradientKind {
+    /// Angle in degrees
+    Linear(f32),
+    Radial
+}
+
+#[derive(Copy, Clone, Default, Debug)]
+enum ObjectFit {
+    Fill,
+    Contain,
+    #[default]
+    Cover,
+    None
+}
+
+impl_enum_totokens!(ObjectFit, mangui::nodes::ObjectFit, Fill, Contain, Cover, None);
+
+#[derive(Copy, Clone, Default, Debug)]
+enum BackgroundRepeat {
+    #[default]
+    NoRepeat,
+    Repeat,
+    RepeatX,
+    RepeatY
+}
+
+impl_enum_totokens!(BackgroundRepeat, mangui::nodes::background::BackgroundRepeat, NoRepeat, Repeat, RepeatX, RepeatY);
+
+#[derive(Clone, Debug)]
+enum Background {
+    /// Any expression which can be converted into a background (color, paint, gradient...)
+    Arbitrary(TokenStream),
+    /// Gradient with color stops (offset and a color expression)
+    Gradient(GradientKind, Vec<(f32, TokenStream)>),
+    Image {
+        path: TokenStream,
+        size: ObjectFit,
+        position: (f32, f32),
+        repeat: BackgroundRepeat
+    }
+}
+
+impl ToTokens for Background {
+    fn to_tokens(&self, stream: &mut TokenStream) {
+        let stops = |stops: &Vec<(f32, TokenStream)>| {
+            let stops = stops.iter().map(|(offset, color)| quote! {
+                mangui::nodes::background::GradientStop { offset: #offset, color: #color }
+            });
+            quote! { vec![#( #stops ),*] }
+        };
+        stream.extend(match self {
+            Background::Arbitrary(expression) => quote! {
+                Some(::core::convert::Into::<mangui::nodes::background::Background>::into(#expression))
+            },
+            Background::Gradient(GradientKind::Linear(angle), gradient_stops) => {
+                let stops = stops(gradient_stops);
+                quote! {
+                    Some(mangui::nodes::background::Background::LinearGradient(mangui::nodes::background::LinearGradient {
+                        angle: #angle,
+                        stops: #stops
+                    }))
+                }
+            },
+            Background::Gradient(GradientKind::Radial, gradient_stops) => {
+                let stops = stops(gradient_stops);
+                quote! {
+                    Some(mangui::nodes::background::Background::RadialGradient(mangui::nodes::background::RadialGradient {
+                        stops: #stops,
+                        ..Default::default()
+                    }))
+                }
+            },
+            Background::Image { path, size, position: (x, y), repeat } => quote! {
+                Some(mangui::nodes::background::Background::Image(
+                    mangui::nodes::background::BackgroundImage::file(#path)
+                        .size(#size)
+                        .position(mangui::nodes::ObjectPosition { x: #x, y: #y })
+                        .repeat(#repeat)
+                ))
+            }
+        });
+    }
+}
+
+/// Background parts set by separate rules (`bg_linear from-[..] to-[..]`), combined after all rules are processed
+#[derive(Clone, Default, Debug)]
+struct BackgroundRules {
+    gradient: Option<GradientKind>,
+    from: Option<TokenStream>,
+    via: Option<TokenStream>,
+    to: Option<TokenStream>,
+    image: Option<TokenStream>,
+    size: ObjectFit,
+    position: Option<(f32, f32)>,
+    repeat: BackgroundRepeat
+}
+
+impl BackgroundRules {
+    fn to_background(self, span: Span) -> Result<Option<Background>, RuleParseError> {
+        if let Some(kind) = self.gradient {
+            let colors: Vec<_> = [self.from, self.via, self.to].into_iter().flatten().collect();
+            if colors.is_empty() {
+                return Err(RuleParseError {
+                    span,
+                    message: "Gradients need colors (from-[color], via-[color], to-[color])".to_owned()
+                });
+            }
+            // stops are spread evenly, like in tailwind
+            let last = (colors.len() - 1).max(1) as f32;
+            let stops = colors.into_iter().enumerate().map(|(i, color)| (i as f32 / last, color)).collect();
+            return Ok(Some(Background::Gradient(kind, stops)));
+        }
+        if let Some(path) = self.image {
+            return Ok(Some(Background::Image {
+                path,
+                size: self.size,
+                position: self.position.unwrap_or((0.5, 0.5)),
+                repeat: self.repeat
+            }));
+        }
+        Ok(None)
+    }
+}
+
+/// Returns which corners (top left, top right, bottom right, bottom left) are rounded by a `rounded` rule with the side.
+fn rounded_corners(side: &str) -> Option<[bool; 4]> {
+    Some(match side {
+        "" => [true, true, true, true],
+        "t" => [true, true, false, false],
+        "r" => [false, true, true, false],
+        "b" => [false, false, true, true],
+        "l" => [true, false, false, true],
+        "tl" => [true, false, false, false],
+        "tr" => [false, true, false, false],
+        "br" => [false, false, true, false],
+        "bl" => [false, false, false, true],
+        _ => return None
+    })
+}
+
+/// Returns the tokens of an arbitrary value (`rule-[value]`)
+fn require_group(value: Option<TokenTree>, span: Span) -> Result<TokenStream, RuleParseError> {
+    match value {
+        Some(TokenTree::Group(group)) => Ok(group.stream()),
+        Some(value) => Err(RuleParseError {
+            span: value.span(),
+            message: "Expected a group ([value])".to_owned()
+        }),
+        None => Err(RuleParseError {
+            span,
+            message: "Expected a value".to_owned()
+        })
+    }
+}
+
+/// Returns the identifier value of a rule (`rule-ident`)
+fn require_ident(value: Option<TokenTree>, span: Span) -> Result<(String, Span), RuleParseError> {
+    match value {
+        Some(TokenTree::Ident(ident)) => Ok((ident.to_string(), ident.span())),
+        Some(value) => Err(RuleParseError {
+            span: value.span(),
+            message: "Expected an identifier".to_owned()
+        }),
+        None => Err(RuleParseError {
+            span,
+            message: "Expected a value".to_owned()
+        })
+    }
+}
+
 #[derive(Copy, Clone, Default, Debug)]
 enum Cursor {
     #[default]
@@ -260,6 +496,24 @@ enum Cursor {
 
 impl_enum_totokens!(Cursor, mangui::femtovg::Cursor, Default);
 
+#[derive(Copy, Clone, Default, Debug)]
+enum Visibility {
+    #[default]
+    Visible,
+    Hidden
+}
+
+impl_enum_totokens!(Visibility, mangui::nodes::Visibility, Visible, Hidden);
+
+#[derive(Copy, Clone, Default, Debug)]
+enum PointerEvents {
+    #[default]
+    Auto,
+    None
+}
+
+impl_enum_totokens!(PointerEvents, mangui::nodes::PointerEvents, Auto, None);
+
 #[derive(Clone, Default, Debug)]
 struct Transform {
     pub position: UserSettable<Point<f32>>,
@@ -269,24 +523,202 @@ struct Transform {
 
 impl_struct_usersettable_totokens!(Transform, mangui::nodes::Transform, position, scale, rotation);
 
+#[derive(Clone, Default, Debug)]
+struct BorderRadius {
+    top_left: UserSettable<f32>,
+    top_right: UserSettable<f32>,
+    bottom_right: UserSettable<f32>,
+    bottom_left: UserSettable<f32>
+}
+
+impl ToTokens for BorderRadius {
+    fn to_tokens(&self, stream: &mut TokenStream) {
+        let BorderRadius { top_left, top_right, bottom_right, bottom_left } = self;
+        let mut substream = TokenStream::new();
+        for (name, corner) in [("top_left", top_left), ("top_right", top_right), ("bottom_right", bottom_right), ("bottom_left", bottom_left)] {
+            if !corner.is_empty() {
+                let name = Ident::new(name, Span::call_site());
+                substream.extend(quote! { #name: mangui::nodes::CornerRadius::from(#corner), });
+            }
+        }
+        stream.extend(quote! {
+            mangui::nodes::BorderRadius {
+                #substream
+                ..Default::default()
+            }
+        });
+    }
+}
+
+impl ToAssignments for BorderRadius {
+    fn to_assignments(&self, target: &TokenStream, stream: &mut TokenStream) {
+        let BorderRadius { top_left, top_right, bottom_right, bottom_left } = self;
+        for (name, corner) in [("top_left", top_left), ("top_right", top_right), ("bottom_right", bottom_right), ("bottom_left", bottom_left)] {
+            if !corner.is_empty() {
+                let name = Ident::new(name, Span::call_site());
+                stream.extend(quote! { #target.#name = mangui::nodes::CornerRadius::from(#corner); });
+            }
+        }
+    }
+}
+
+/// Length in a relative unit (`em`, `rem`, `vw` or `vh`), set by rules with a unit suffix (`p_em-2`)
+#[derive(Clone, Debug)]
+struct UnitLength {
+    value: UserSettable<f32>,
+    unit: String
+}
+
+impl ToTokens for UnitLength {
+    fn to_tokens(&self, stream: &mut TokenStream) {
+        let UnitLength { value, unit } = self;
+        let unit = Ident::new(unit, Span::call_site());
+        stream.extend(quote! {
+            Some(mangui::nodes::units::Length::#unit(#value))
+        });
+    }
+}
+
+/// Sides in relative units, unset sides are left to the layout style
+#[derive(Clone, Default, Debug)]
+struct LengthSides {
+    pub left: UserSettable<UnitLength>,
+    pub right: UserSettable<UnitLength>,
+    pub top: UserSettable<UnitLength>,
+    pub bottom: UserSettable<UnitLength>
+}
+
+impl ToTokens for LengthSides {
+    fn to_tokens(&self, stream: &mut TokenStream) {
+        let LengthSides { left, right, top, bottom } = self;
+        stream.extend(quote! {
+            mangui::taffy::geometry::Rect { left: #left, right: #right, top: #top, bottom: #bottom }
+        });
+    }
+}
+
+impl ToAssignments for LengthSides {
+    fn to_assignments(&self, target: &TokenStream, stream: &mut TokenStream) {
+        self.left.to_assignments(&quote! { #target.left }, stream);
+        self.right.to_assignments(&quote! { #target.right }, stream);
+        self.top.to_assignments(&quote! { #target.top }, stream);
+        self.bottom.to_assignments(&quote! { #target.bottom }, stream);
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+struct LayoutLengths {
+    pub margin: UserSettable<LengthSides>,
+    pub padding: UserSettable<LengthSides>,
+    pub gap: UserSettable<Size<UnitLength>>
+}
+
+impl_struct_usersettable_totokens!(
+    LayoutLengths,
+    mangui::nodes::units::LayoutLengths,
+    margin, padding, gap
+);
+
+/// Returns the rule name and unit of rules with a relative unit suffix (`p_em`, `gap_vw`)
+fn split_unit(name: &str) -> Option<(&str, &str)> {
+    name.rsplit_once('_').filter(|(_, unit)| matches!(*unit, "em" | "rem" | "vw" | "vh"))
+}
+
 #[derive(Clone, Default, Debug)]
 struct Style {
     pub layout: UserSettable<TaffyStyle>,
+    pub lengths: UserSettable<LayoutLengths>,
     pub cursor: UserSettable<Cursor>,
-    pub background: UserSettable<Paint>,
+    pub background: UserSettable<Background>,
     pub text_fill: UserSettable<Paint>,
     pub font_size: UserSettable<f32>,
     pub line_height: UserSettable<f32>,
-    pub border_radius: UserSettable<f32>,
-    pub transform: UserSettable<Transform>
+    pub border_radius: UserSettable<BorderRadius>,
+    pub transform: UserSettable<Transform>,
+    pub z_index: UserSettable<Option<i32>>,
+    pub visibility: UserSettable<Visibility>,
+    pub pointer_events: UserSettable<PointerEvents>,
+    pub variants: Variants
 }
 
 impl_struct_usersettable_totokens!(
     Style,
     mangui::nodes::Style,
-    layout, cursor, background, text_fill, font_size, line_height, border_radius, transform
+    layout, lengths, cursor, background, text_fill, font_size, line_height, border_radius, transform, z_index, visibility, pointer_events, variants
+);
+
+impl_value_assignments!(
+    f32, Option<i32>, Cursor, Background, Paint, Visibility, PointerEvents, UnitLength,
+    Display, Position, Overflow, LengthPercentageAuto, Dimension, LengthPercentage,
+    AlignItems, AlignContent, FlexDirection, FlexWrap, GridAutoFlow, Line<GridPlacement>
 );
 
+/// Style applied when the condition of its prefix is met (`md:flex`)
+#[derive(Clone, Debug)]
+struct Variant {
+    condition: TokenStream,
+    style: Style
+}
+
+impl ToTokens for Variant {
+    fn to_tokens(&self, stream: &mut TokenStream) {
+        let Variant { condition, style } = self;
+        let mut assignments = TokenStream::new();
+        style.to_assignments(&quote! { style }, &mut assignments);
+        stream.extend(quote! {
+            mangui::nodes::variant::StyleVariant::new(#condition, |style: &mut mangui::nodes::Style| { #assignments })
+        });
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+struct Variants(Vec<Variant>);
+
+impl Variants {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl ToTokens for Variants {
+    fn to_tokens(&self, stream: &mut TokenStream) {
+        let variants = &self.0;
+        stream.extend(quote! { vec![#( #variants ),*] });
+    }
+}
+
+impl ToAssignments for Variants {
+    fn to_assignments(&self, target: &TokenStream, stream: &mut TokenStream) {
+        if !self.is_empty() {
+            stream.extend(quote! { #target.extend(#self); });
+        }
+    }
+}
+
+/// Returns the condition of a variant prefix (theme, interaction state or breakpoint). Breakpoints are minimum window widths in physical pixels.
+fn variant_condition(prefix: &str, span: Span) -> Result<TokenStream, RuleParseError> {
+    match prefix {
+        "light" => return Ok(quote! { mangui::nodes::variant::StyleCondition::Theme(mangui::nodes::theme::ThemeMode::Light) }),
+        "dark" => return Ok(quote! { mangui::nodes::variant::StyleCondition::Theme(mangui::nodes::theme::ThemeMode::Dark) }),
+        "hover" => return Ok(quote! { mangui::nodes::variant::StyleCondition::Hovered }),
+        "active" => return Ok(quote! { mangui::nodes::variant::StyleCondition::Pressed }),
+        "focus" => return Ok(quote! { mangui::nodes::variant::StyleCondition::Focused }),
+        "disabled" => return Ok(quote! { mangui::nodes::variant::StyleCondition::Disabled }),
+        _ => {}
+    }
+    let min_width = match prefix {
+        "sm" => 640f32,
+        "md" => 768f32,
+        "lg" => 1024f32,
+        "xl" => 1280f32,
+        _ => return Err(RuleParseError {
+            span,
+            message: format!("Unknown variant {prefix}")
+        })
+    };
+    Ok(quote! { mangui::nodes::variant::StyleCondition::MinWidth(#min_width) })
+}
+
 #[derive(Clone, Default, Debug)]
 enum Position {
     #[default]
@@ -738,7 +1170,7 @@ pub fn uno(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
         }
     };
 
-    let style = match process_rules(rules) {
+    let style = match process_variants(rules) {
         Ok(style) => style,
         Err(err) => {
             let RuleParseError { span, message } = err;
@@ -753,20 +1185,78 @@ pub fn uno(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     style.to_token_stream().into()
 }
 
+/// Processes rules without a prefix into the style, and rules with a prefix into its variants.
+fn process_variants(rules: Vec<Rule>) -> Result<Style, RuleParseError> {
+    let mut groups: Vec<(Option<String>, Span, Vec<Rule>)> = Vec::new();
+    for rule in rules {
+        match groups.iter_mut().find(|(prefix, _, _)| *prefix == rule.prefix) {
+            Some((_, _, rules)) => rules.push(rule),
+            None => groups.push((rule.prefix.clone(), rule.prefix_span, vec![rule]))
+        }
+    }
+    let mut style = Style::default();
+    let mut variants = Vec::new();
+    for (prefix, prefix_span, rules) in groups {
+        match prefix {
+            None => style = process_rules(rules)?,
+            Some(prefix) => variants.push(Variant {
+                condition: variant_condition(&prefix, prefix_span)?,
+                style: process_rules(rules)?
+            })
+        }
+    }
+    style.variants = Variants(variants);
+    Ok(style)
+}
+
 fn process_rules(rules: Vec<Rule>) -> Result<Style, RuleParseError> {
     let mut style = Style::default();
+    let mut background = BackgroundRules::default();
+    let mut background_span = None;
     dbg!(&rules);
 
     for rule in rules {
         let Rule {
-            prefix,
-            prefix_span,
             name,
             name_span,
             value,
-            inverse
+            inverse,
+            ..
         } = rule;
 
+        if let Some((rule, unit)) = split_unit(&name) {
+            let length = UserSettable::Value(UnitLength {
+                value: value.to_user_settable(name_span, inverse)?,
+                unit: unit.to_owned()
+            });
+            let lengths = style.lengths.require_non_arbitrary()?;
+            let sides = match rule {
+                "p" | "pt" | "pr" | "pl" | "pb" => lengths.padding.require_non_arbitrary()?,
+                "m" | "mt" | "mr" | "ml" | "mb" => lengths.margin.require_non_arbitrary()?,
+                "gap" => {
+                    lengths.gap = UserSettable::Value(Size { width: length.clone(), height: length });
+                    continue;
+                },
+                _ => return Err(RuleParseError {
+                    span: name_span,
+                    message: format!("Relative units are not supported by {rule}")
+                })
+            };
+            match rule.chars().nth(1) {
+                Some('t') => sides.top = length,
+                Some('r') => sides.right = length,
+                Some('l') => sides.left = length,
+                Some('b') => sides.bottom = length,
+                _ => {
+                    sides.top = length.clone();
+                    sides.right = length.clone();
+                    sides.left = length.clone();
+                    sides.bottom = length;
+                }
+            }
+            continue;
+        }
+
         // todo: handle passing single objects to certain attributes (p, m, gap, overflow, etc)
         match name.as_str() {
             "flex" => {
@@ -831,14 +1321,150 @@ fn process_rules(rules: Vec<Rule>) -> Result<Style, RuleParseError> {
                 let value = value.to_user_settable(name_span, inverse)?;
                 style.layout.require_non_arbitrary()?.overflow.require_non_arbitrary()?.y = value;
             },
-            "rounded" => {
-                if let Some(value) = value {
-                    let value = value.to_user_settable(name_span, inverse)?;
-                    style.border_radius = value;  
-                } else {
-                    style.border_radius = UserSettable::Value(8.);
+            rule if rule == "rounded" || rule.starts_with("rounded_") => {
+                let mut side = rule.trim_start_matches("rounded").trim_start_matches('_').to_owned();
+                let radius = match value {
+                    // rounded-t, rounded-none, rounded-full...
+                    Some(TokenTree::Ident(ident)) if side.is_empty() => match ident.to_string().as_str() {
+                        "none" => UserSettable::Value(0.),
+                        "full" => UserSettable::Value(9999.),
+                        corners => {
+                            side = corners.to_owned();
+                            UserSettable::Value(8.)
+                        }
+                    },
+                    Some(value) => value.to_user_settable(name_span, inverse)?,
+                    None => UserSettable::Value(8.)
+                };
+                let Some(corners) = rounded_corners(&side) else {
+                    return Err(RuleParseError {
+                        span: name_span,
+                        message: "Unknown corners, expected one of t, r, b, l, tl, tr, br, bl".to_owned()
+                    });
+                };
+                let border_radius = style.border_radius.require_non_arbitrary()?;
+                let [top_left, top_right, bottom_right, bottom_left] = corners;
+                for (enabled, corner) in [
+                    (top_left, &mut border_radius.top_left),
+                    (top_right, &mut border_radius.top_right),
+                    (bottom_right, &mut border_radius.bottom_right),
+                    (bottom_left, &mut border_radius.bottom_left)
+                ] {
+                    if enabled {
+                        *corner = radius.clone();
+                    }
                 }
             },
+            "visible" | "invisible" => {
+                style.visibility = UserSettable::Value(match name.as_str() {
+                    "visible" => Visibility::Visible,
+                    _ => Visibility::Hidden
+                });
+            },
+            "pointer_events" => {
+                let (value, span) = require_ident(value, name_span)?;
+                style.pointer_events = UserSettable::Value(match value.as_str() {
+                    "auto" => PointerEvents::Auto,
+                    "none" => PointerEvents::None,
+                    _ => return Err(RuleParseError {
+                        span,
+                        message: "Expected auto or none".to_owned()
+                    })
+                });
+            },
+            "absolute" | "relative" => {
+                style.layout.require_non_arbitrary()?.position = UserSettable::Value(match name.as_str() {
+                    "absolute" => Position::Absolute,
+                    _ => Position::Relative
+                });
+            },
+            "z" => {
+                // z_index is optional in the style
+                let value: UserSettable<f32> = value.to_user_settable(name_span, inverse)?;
+                style.z_index = match value {
+                    UserSettable::Value(value) => {
+                        let value = value as i32;
+                        UserSettable::Arbitrary(quote! { Some(#value) })
+                    },
+                    UserSettable::Arbitrary(stream) => UserSettable::Arbitrary(quote! { Some(#stream) }),
+                    UserSettable::None => UserSettable::None
+                };
+            },
+            "bg" => {
+                style.background = UserSettable::Value(Background::Arbitrary(require_group(value, name_span)?));
+            },
+            "bg_linear" => {
+                let angle = match value {
+                    Some(value) => {
+                        let mut angle: UserSettable<f32> = value.to_user_settable(name_span, inverse)?;
+                        *angle.require_value()?
+                    },
+                    // to bottom, like CSS
+                    None => 180.
+                };
+                background.gradient = Some(GradientKind::Linear(angle));
+                background_span = Some(name_span);
+            },
+            "bg_radial" => {
+                background.gradient = Some(GradientKind::Radial);
+                background_span = Some(name_span);
+            },
+            "from" => background.from = Some(require_group(value, name_span)?),
+            "via" => background.via = Some(require_group(value, name_span)?),
+            "to" => background.to = Some(require_group(value, name_span)?),
+            "bg_image" => {
+                background.image = Some(require_group(value, name_span)?);
+                background_span = Some(name_span);
+            },
+            "bg_size" => {
+                let (value, span) = require_ident(value, name_span)?;
+                background.size = match value.as_str() {
+                    "cover" => ObjectFit::Cover,
+                    "contain" => ObjectFit::Contain,
+                    "auto" => ObjectFit::None,
+                    "fill" => ObjectFit::Fill,
+                    _ => return Err(RuleParseError {
+                        span,
+                        message: "Expected a valid value (one of cover, contain, auto or fill)".to_owned()
+                    })
+                };
+            },
+            "bg_position" => {
+                let (value, span) = require_ident(value, name_span)?;
+                background.position = Some(match value.as_str() {
+                    "center" => (0.5, 0.5),
+                    "top" => (0.5, 0.),
+                    "bottom" => (0.5, 1.),
+                    "left" => (0., 0.5),
+                    "right" => (1., 0.5),
+                    "top_left" => (0., 0.),
+                    "top_right" => (1., 0.),
+                    "bottom_left" => (0., 1.),
+                    "bottom_right" => (1., 1.),
+                    _ => return Err(RuleParseError {
+                        span,
+                        message: "Expected a valid value (center, top, bottom, left, right or a corner like top_left)".to_owned()
+                    })
+                });
+            },
+            "bg_repeat" => {
+                background.repeat = match value {
+                    None => BackgroundRepeat::Repeat,
+                    value => {
+                        let (value, span) = require_ident(value, name_span)?;
+                        match value.as_str() {
+                            "repeat" => BackgroundRepeat::Repeat,
+                            "no_repeat" => BackgroundRepeat::NoRepeat,
+                            "x" => BackgroundRepeat::RepeatX,
+                            "y" => BackgroundRepeat::RepeatY,
+                            _ => return Err(RuleParseError {
+                                span,
+                                message: "Expected a valid value (one of repeat, no_repeat, x or y)".to_owned()
+                            })
+                        }
+                    }
+                };
+            },
             "layout" => {
                 if let Some(value) = value {
                     let value = value.to_user_settable(name_span, inverse)?;
@@ -859,6 +1485,12 @@ fn process_rules(rules: Vec<Rule>) -> Result<Style, RuleParseError> {
         }
     }
 
+    if let Some(span) = background_span {
+        if let Some(background) = background.to_background(span)? {
+            style.background = UserSettable::Value(background);
+        }
+    }
+
     Ok(style)
 }
 